@@ -0,0 +1,64 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+};
+
+use clap::Parser;
+use crustysynth::sf2::SoundFont;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long)]
+    file: PathBuf,
+    /// Directory for the WAV files
+    #[arg(short, long, default_value = ".")]
+    out_dir: PathBuf,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let soundfont = match parse_soundfont(args.file.clone()) {
+        Ok(soundfont) => soundfont,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+    if let Err(e) = export_samples(&soundfont, &args.out_dir) {
+        println!("{e}");
+    }
+}
+
+fn parse_soundfont(path: PathBuf) -> anyhow::Result<SoundFont> {
+    let file = File::open(path)?;
+
+    let soundfont = SoundFont::try_from(file)?;
+
+    Ok(soundfont)
+}
+
+fn export_samples(soundfont: &SoundFont, out_dir: &PathBuf) -> anyhow::Result<()> {
+    fs::create_dir_all(out_dir)?;
+
+    let headers = &soundfont.get_hydra().sample_headers;
+    for index in soundfont.get_export_samples() {
+        let name: String = headers[index]
+            .name
+            .chars()
+            .map(|c| match c.is_alphanumeric() || " -_#()".contains(c) {
+                true => c,
+                false => '_',
+            })
+            .collect();
+        let path = out_dir.join(format!("{index:04} {}.wav", name.trim()));
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        soundfont.write_wav(index, &mut writer)?;
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
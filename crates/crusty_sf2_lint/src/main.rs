@@ -0,0 +1,52 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use crustysynth::sf2::{validate::LintSeverity, SoundFont};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long)]
+    file: PathBuf,
+    /// Only print errors
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let soundfont = match parse_soundfont(args.file.clone()) {
+        Ok(soundfont) => soundfont,
+        Err(e) => {
+            // A file that can't be loaded fails too, apart from lint errors.
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+
+    let issues = soundfont.validate();
+    for issue in &issues {
+        if args.quiet && issue.severity != LintSeverity::Error {
+            continue;
+        }
+        println!("{issue}");
+    }
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == LintSeverity::Error)
+        .count();
+    println!("{errors} errors, {} warnings", issues.len() - errors);
+    if errors > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn parse_soundfont(path: PathBuf) -> anyhow::Result<SoundFont> {
+    let file = File::open(path)?;
+
+    let soundfont = SoundFont::try_from(file)?;
+
+    Ok(soundfont)
+}
@@ -0,0 +1,237 @@
+//! Articulation (`art1`, `art2`)
+//!
+//! Articulation is a list of connection blocks. Each block routes a source, such as an LFO or the
+//! key number, to a destination, such as pitch or an envelope time. Blocks without a source set
+//! the destination's value.
+//!
+//! Most blocks have an SF2 generator with the same meaning, and are converted into one. Blocks
+//! with a control or a transform, and blocks SF2 has no generator for, are ignored.
+
+use crate::sf2::{generator::GeneratorType, riff::RiffChunk};
+
+use super::{check_size, DlsError};
+
+/// Connection sources
+pub mod source {
+    pub const NONE: u16 = 0x0000;
+    pub const LFO: u16 = 0x0001;
+    pub const KEY_ON_VELOCITY: u16 = 0x0002;
+    pub const KEY_NUMBER: u16 = 0x0003;
+    pub const EG1: u16 = 0x0004;
+    pub const EG2: u16 = 0x0005;
+    pub const PITCH_WHEEL: u16 = 0x0006;
+    /// DLS2
+    pub const VIBRATO: u16 = 0x0009;
+}
+
+/// Connection destinations
+pub mod destination {
+    /// Gain in DLS2
+    pub const ATTENUATION: u16 = 0x0001;
+    pub const PITCH: u16 = 0x0003;
+    pub const PAN: u16 = 0x0004;
+    pub const CHORUS: u16 = 0x0080;
+    pub const REVERB: u16 = 0x0081;
+    pub const LFO_FREQUENCY: u16 = 0x0104;
+    pub const LFO_START_DELAY: u16 = 0x0105;
+    pub const VIB_FREQUENCY: u16 = 0x0114;
+    pub const VIB_START_DELAY: u16 = 0x0115;
+    pub const EG1_ATTACK_TIME: u16 = 0x0206;
+    pub const EG1_DECAY_TIME: u16 = 0x0207;
+    pub const EG1_RELEASE_TIME: u16 = 0x0209;
+    pub const EG1_SUSTAIN_LEVEL: u16 = 0x020A;
+    pub const EG1_DELAY_TIME: u16 = 0x020B;
+    pub const EG1_HOLD_TIME: u16 = 0x020C;
+    pub const EG2_ATTACK_TIME: u16 = 0x030A;
+    pub const EG2_DECAY_TIME: u16 = 0x030B;
+    pub const EG2_RELEASE_TIME: u16 = 0x030D;
+    pub const EG2_SUSTAIN_LEVEL: u16 = 0x030E;
+    pub const EG2_DELAY_TIME: u16 = 0x030F;
+    pub const EG2_HOLD_TIME: u16 = 0x0310;
+    pub const FILTER_CUTOFF: u16 = 0x0500;
+    pub const FILTER_Q: u16 = 0x0501;
+}
+
+/// Connection block
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Connection {
+    pub source: u16,
+    pub control: u16,
+    pub destination: u16,
+    pub transform: u16,
+    /// Amount in 16.16 fixed point. The unit depends on the destination.
+    pub scale: i32,
+}
+
+impl Connection {
+    pub const RECORD_SIZE: usize = 12;
+
+    pub fn parse(b: &[u8]) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([b[i], b[i + 1]]);
+        Self {
+            source: u16_at(0),
+            control: u16_at(2),
+            destination: u16_at(4),
+            transform: u16_at(6),
+            scale: i32::from_le_bytes([b[8], b[9], b[10], b[11]]),
+        }
+    }
+
+    /// Generator and value with the same effect. `None` if there isn't one.
+    pub fn to_generator(&self) -> Option<(GeneratorType, i32)> {
+        use destination::*;
+
+        if self.control != source::NONE || self.transform != 0 {
+            return None;
+        }
+        let value = self.scale >> 16;
+        // Times are absolute timecents. The smallest value means zero time.
+        let time = value.max(-12000);
+
+        let generator = match (self.source, self.destination) {
+            (source::NONE, ATTENUATION) => (GeneratorType::InitialAttenuation, (-value).max(0)),
+            (source::NONE, PAN) => (GeneratorType::Pan, value),
+            (source::NONE, CHORUS) => (GeneratorType::ChorusEffectsSend, value),
+            (source::NONE, REVERB) => (GeneratorType::ReverbEffectsSend, value),
+            (source::NONE, LFO_FREQUENCY) => (GeneratorType::FreqModLfo, value),
+            (source::NONE, LFO_START_DELAY) => (GeneratorType::DelayModLfo, time),
+            (source::NONE, VIB_FREQUENCY) => (GeneratorType::FreqVibLfo, value),
+            (source::NONE, VIB_START_DELAY) => (GeneratorType::DelayVibLfo, time),
+            (source::NONE, EG1_DELAY_TIME) => (GeneratorType::DelayVolEnv, time),
+            (source::NONE, EG1_ATTACK_TIME) => (GeneratorType::AttackVolEnv, time),
+            (source::NONE, EG1_HOLD_TIME) => (GeneratorType::HoldVolEnv, time),
+            (source::NONE, EG1_DECAY_TIME) => (GeneratorType::DecayVolEnv, time),
+            (source::NONE, EG1_RELEASE_TIME) => (GeneratorType::ReleaseVolEnv, time),
+            // Level in 0.1%, attenuation in centibels
+            (source::NONE, EG1_SUSTAIN_LEVEL) => (
+                GeneratorType::SustainVolEnv,
+                match value {
+                    ..=0 => 1440,
+                    level => (-200.0 * (level.min(1000) as f64 / 1000.0).log10()).round() as i32,
+                },
+            ),
+            (source::NONE, EG2_DELAY_TIME) => (GeneratorType::DelayModEnv, time),
+            (source::NONE, EG2_ATTACK_TIME) => (GeneratorType::AttackModEnv, time),
+            (source::NONE, EG2_HOLD_TIME) => (GeneratorType::HoldModEnv, time),
+            (source::NONE, EG2_DECAY_TIME) => (GeneratorType::DecayModEnv, time),
+            (source::NONE, EG2_RELEASE_TIME) => (GeneratorType::ReleaseModEnv, time),
+            // Level in 0.1%, decrease in 0.1%
+            (source::NONE, EG2_SUSTAIN_LEVEL) => {
+                (GeneratorType::SustainModEnv, 1000 - value.clamp(0, 1000))
+            }
+            (source::NONE, FILTER_CUTOFF) => (GeneratorType::InitialFilterFc, value),
+            (source::NONE, FILTER_Q) => (GeneratorType::InitialFilterQ, value),
+            (source::LFO, PITCH) => (GeneratorType::ModLfoToPitch, value),
+            (source::LFO, ATTENUATION) => (GeneratorType::ModLfoToVolume, value),
+            (source::LFO, FILTER_CUTOFF) => (GeneratorType::ModLfoToFilterFc, value),
+            (source::VIBRATO, PITCH) => (GeneratorType::VibLfoToPitch, value),
+            (source::EG2, PITCH) => (GeneratorType::ModEnvToPitch, value),
+            (source::EG2, FILTER_CUTOFF) => (GeneratorType::ModEnvToFilterFc, value),
+            // DLS scales over the whole key range, SF2 per key, and with the opposite sign.
+            (source::KEY_NUMBER, EG1_HOLD_TIME) => {
+                (GeneratorType::KeynumToVolEnvHold, -value / 128)
+            }
+            (source::KEY_NUMBER, EG1_DECAY_TIME) => {
+                (GeneratorType::KeynumToVolEnvDecay, -value / 128)
+            }
+            (source::KEY_NUMBER, EG2_HOLD_TIME) => {
+                (GeneratorType::KeynumToModEnvHold, -value / 128)
+            }
+            (source::KEY_NUMBER, EG2_DECAY_TIME) => {
+                (GeneratorType::KeynumToModEnvDecay, -value / 128)
+            }
+            _ => return None,
+        };
+        Some(generator)
+    }
+}
+
+/// Connections of all `art1` and `art2` chunks in a `lart` or `lar2` list.
+pub fn parse_articulation(list: &RiffChunk) -> Result<Vec<Connection>, DlsError> {
+    let mut connections = vec![];
+    for chunk in list.subchunks()? {
+        if !matches!(&chunk.get_id(), b"art1" | b"art2") {
+            continue;
+        }
+        let data = check_size(&chunk, 8)?;
+        let header_size = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let count = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        for i in 0..count {
+            let offset = header_size + i * Connection::RECORD_SIZE;
+            let Some(bytes) = data.get(offset..offset + Connection::RECORD_SIZE) else {
+                return Err(DlsError::InvalidChunkSize {
+                    id: chunk.get_id(),
+                    size: data.len(),
+                });
+            };
+            connections.push(Connection::parse(bytes));
+        }
+    }
+    Ok(connections)
+}
+
+/// Generator values of a set of connections. The pitch destination is split into coarse and
+/// fine tuning.
+pub fn to_generators(connections: &[Connection]) -> Vec<(GeneratorType, i32)> {
+    let mut generators = vec![];
+    for connection in connections {
+        if connection.source == source::NONE && connection.destination == destination::PITCH {
+            let cents = connection.scale >> 16;
+            generators.push((GeneratorType::CoarseTune, cents / 100));
+            generators.push((GeneratorType::FineTune, cents % 100));
+        } else if let Some(generator) = connection.to_generator() {
+            generators.push(generator);
+        }
+    }
+    generators
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(source: u16, destination: u16, value: i32) -> Connection {
+        Connection {
+            source,
+            destination,
+            scale: value << 16,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_to_generators() {
+        let connections = [
+            connection(source::NONE, destination::PITCH, -250),
+            connection(source::NONE, destination::EG1_ATTACK_TIME, -7973),
+            Connection {
+                scale: i32::MIN,
+                ..connection(source::NONE, destination::EG1_RELEASE_TIME, 0)
+            },
+            connection(source::NONE, destination::EG1_SUSTAIN_LEVEL, 100),
+            connection(source::NONE, destination::EG2_SUSTAIN_LEVEL, 250),
+            connection(source::KEY_NUMBER, destination::EG1_DECAY_TIME, 1280),
+            connection(source::LFO, destination::PITCH, 50),
+            // No generator
+            connection(source::PITCH_WHEEL, destination::PITCH, 200),
+            Connection {
+                control: 0x81,
+                ..connection(source::LFO, destination::PITCH, 50)
+            },
+        ];
+
+        assert_eq!(
+            to_generators(&connections),
+            vec![
+                (GeneratorType::CoarseTune, -2),
+                (GeneratorType::FineTune, -50),
+                (GeneratorType::AttackVolEnv, -7973),
+                (GeneratorType::ReleaseVolEnv, -12000),
+                (GeneratorType::SustainVolEnv, 200),
+                (GeneratorType::SustainModEnv, 750),
+                (GeneratorType::KeynumToVolEnvDecay, -10),
+                (GeneratorType::ModLfoToPitch, 50),
+            ]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Conversion to SoundFont
+//!
+//! Each wave becomes a sample, each DLS instrument becomes an SF2 instrument with one zone per
+//! region, and a preset that plays it. A region's articulation replaces the instrument's, so each
+//! zone gets one or the other. Regions that override the wave's root key, tuning, gain or loop do
+//! so through generators.
+
+use crate::sf2::{
+    edit::{InstrumentRecords, PresetRecords, ZoneBuilder, ZoneRecords},
+    generator::GeneratorType,
+    hydra::{Hydra, PresetHeader},
+    info::{SoundFontInfo, SoundFontVersion},
+    sample::{clamp_loop, SampleData, SampleHeader, SampleLink, SAMPLE_PADDING},
+    SoundFont,
+};
+
+use super::{
+    articulation::{to_generators, Connection},
+    instrument::{DlsInstrument, DlsRegion},
+    wave::{DlsWave, WaveLoop, WaveSample},
+    DlsBank,
+};
+
+/// LFO frequency when articulation doesn't set one: 5 Hz in absolute cents. SF2 defaults to
+/// 8.176 Hz.
+const DEFAULT_LFO_FREQUENCY: i32 = -851;
+
+impl DlsBank {
+    pub fn to_soundfont(&self) -> SoundFont {
+        let mut smpl = vec![];
+        let sample_headers: Vec<SampleHeader> = self
+            .get_waves()
+            .iter()
+            .map(|wave| wave_to_sample(wave, &mut smpl))
+            .collect();
+
+        let mut presets = vec![];
+        let mut instruments = vec![];
+        for (index, instrument) in self.get_instruments().iter().enumerate() {
+            instruments.push(self.convert_instrument(instrument, &sample_headers));
+            presets.push(PresetRecords {
+                header: PresetHeader {
+                    name: instrument.name.clone(),
+                    preset: instrument.program as u16 & 0x7F,
+                    bank: match instrument.is_drum() {
+                        true => 128,
+                        false => instrument.get_bank_msb() as u16,
+                    },
+                    ..Default::default()
+                },
+                zones: vec![ZoneBuilder::default()
+                    .set(GeneratorType::Instrument, index as i32)
+                    .build()],
+            });
+        }
+
+        let info = SoundFontInfo {
+            version: SoundFontVersion { major: 2, minor: 4 },
+            sound_engine: "EMU8000".into(),
+            bank_name: self.get_name().cloned().unwrap_or_default(),
+            ..Default::default()
+        };
+        SoundFont::new(
+            info,
+            SampleData::new(smpl, None),
+            Hydra::from_records(&presets, &instruments, sample_headers),
+        )
+    }
+
+    fn convert_instrument(
+        &self,
+        instrument: &DlsInstrument,
+        sample_headers: &[SampleHeader],
+    ) -> InstrumentRecords {
+        let global = ZoneBuilder::default()
+            .set(GeneratorType::FreqModLfo, DEFAULT_LFO_FREQUENCY)
+            .set(GeneratorType::FreqVibLfo, DEFAULT_LFO_FREQUENCY);
+
+        let mut zones = vec![global.build()];
+        for region in &instrument.regions {
+            let Some(wave_index) = self.get_wave_index(region.wave_link.table_index) else {
+                continue;
+            };
+            let wave = &self.get_waves()[wave_index];
+            let articulation = region
+                .articulation
+                .as_deref()
+                .unwrap_or(&instrument.articulation);
+            zones.push(convert_region(
+                region,
+                articulation,
+                wave,
+                &sample_headers[wave_index],
+                wave_index,
+            ));
+        }
+
+        InstrumentRecords {
+            name: instrument.name.clone(),
+            zones,
+        }
+    }
+}
+
+/// Append the points of a wave to `smpl`, and make its header.
+fn wave_to_sample(wave: &DlsWave, smpl: &mut Vec<i16>) -> SampleHeader {
+    let points = wave.get_points();
+    let start = smpl.len() as u32;
+    let end = start + points.len() as u32;
+    smpl.extend(points);
+    smpl.resize(smpl.len() + SAMPLE_PADDING, 0);
+
+    let wave_sample = wave.wave_sample.clone().unwrap_or_default();
+    let (start_loop, end_loop) = wave_sample
+        .loops
+        .first()
+        .and_then(|wave_loop| loop_points(start, end, wave_loop))
+        .unwrap_or((start, end));
+    SampleHeader {
+        name: wave.name.chars().take(20).collect(),
+        start,
+        end,
+        start_loop,
+        end_loop,
+        sample_rate: wave.format.sample_rate,
+        original_key: wave_sample.unity_note.min(127) as u8,
+        pitch_correction: wave_sample.fine_tune.clamp(-99, 99) as i8,
+        sample_link: 0,
+        sample_type: SampleLink::Mono as u16,
+    }
+}
+
+/// Loop points in `smpl` of a sample from `start` to `end`. Loops are kept within the sample, and
+/// dropped if nothing is left of them.
+fn loop_points(start: u32, end: u32, wave_loop: &WaveLoop) -> Option<(u32, u32)> {
+    let end_offset = wave_loop.start.saturating_add(wave_loop.length);
+    let (start_loop, end_loop) = clamp_loop(start, end, (wave_loop.start, end_offset));
+    (start_loop < end_loop).then_some((start_loop, end_loop))
+}
+
+fn convert_region(
+    region: &DlsRegion,
+    articulation: &[Connection],
+    wave: &DlsWave,
+    header: &SampleHeader,
+    sample_index: usize,
+) -> ZoneRecords {
+    let mut zone = ZoneBuilder::default()
+        .set_range(GeneratorType::KeyRange, region.key_range)
+        .set_range(GeneratorType::VelRange, region.vel_range);
+    for (generator_type, value) in to_generators(articulation) {
+        zone = zone.set(generator_type, value);
+    }
+    if region.key_group != 0 {
+        zone = zone.set(GeneratorType::ExclusiveClass, region.key_group as i32);
+    }
+
+    let wave_sample = region
+        .wave_sample
+        .as_ref()
+        .or(wave.wave_sample.as_ref())
+        .cloned()
+        .unwrap_or_default();
+    zone = apply_wave_sample(zone, &wave_sample, header);
+
+    zone.set(GeneratorType::SampleId, sample_index as i32)
+        .build()
+}
+
+/// Root key, tuning, gain and loop of a wave sample, relative to the sample header.
+fn apply_wave_sample(
+    mut zone: ZoneBuilder,
+    wave_sample: &WaveSample,
+    header: &SampleHeader,
+) -> ZoneBuilder {
+    let unity_note = wave_sample.unity_note.min(127) as i32;
+    if unity_note != header.original_key as i32 {
+        zone = zone.set(GeneratorType::OverridingRootKey, unity_note);
+    }
+    let fine_tune = wave_sample.fine_tune as i32 - header.pitch_correction as i32;
+    if fine_tune != 0 {
+        zone = zone.add(GeneratorType::FineTune, fine_tune);
+    }
+    let attenuation = wave_sample.get_attenuation_cb();
+    if attenuation != 0 {
+        zone = zone.add(GeneratorType::InitialAttenuation, attenuation);
+    }
+
+    let Some(wave_loop) = wave_sample.loops.first() else {
+        return zone;
+    };
+    let Some((start_loop, end_loop)) = loop_points(header.start, header.end, wave_loop) else {
+        return zone;
+    };
+    let mode = match wave_loop.loop_type {
+        WaveLoop::RELEASE => 3,
+        _ => 1,
+    };
+    zone.set(GeneratorType::SampleModes, mode)
+        .set_offset(
+            GeneratorType::StartloopAddrsOffset,
+            GeneratorType::StartloopAddrsCoarseOffset,
+            start_loop as i32 - header.start_loop as i32,
+        )
+        .set_offset(
+            GeneratorType::EndloopAddrsOffset,
+            GeneratorType::EndloopAddrsCoarseOffset,
+            end_loop as i32 - header.end_loop as i32,
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_points() {
+        let wave_loop = |start, length| WaveLoop {
+            loop_type: 0,
+            start,
+            length,
+        };
+        assert_eq!(loop_points(10, 20, &wave_loop(2, 5)), Some((12, 17)));
+        // Kept within the sample
+        assert_eq!(loop_points(10, 20, &wave_loop(2, 50)), Some((12, 20)));
+        assert_eq!(loop_points(10, 20, &wave_loop(2, u32::MAX)), Some((12, 20)));
+        assert_eq!(loop_points(10, 20, &wave_loop(u32::MAX, 5)), None);
+        assert_eq!(loop_points(10, 20, &wave_loop(2, 0)), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Instruments (`ins `) and regions (`rgn `, `rgn2`)
+
+use crate::sf2::riff::RiffChunk;
+
+use super::{
+    articulation::{parse_articulation, Connection},
+    check_size, parse_name,
+    wave::WaveSample,
+    DlsError,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DlsInstrument {
+    pub name: String,
+    /// MIDI bank: CC0 in bits 8-14, CC32 in bits 0-6, and the drum flag in bit 31
+    pub bank: u32,
+    pub program: u32,
+    /// Articulation of all regions that don't have their own
+    pub articulation: Vec<Connection>,
+    pub regions: Vec<DlsRegion>,
+}
+
+impl DlsInstrument {
+    pub const DRUM_FLAG: u32 = 0x8000_0000;
+
+    pub fn parse(list: &RiffChunk) -> Result<Self, DlsError> {
+        let mut instrument = Self::default();
+        for chunk in list.subchunks()? {
+            match (&chunk.get_id(), chunk.get_form_type()) {
+                (b"insh", _) => {
+                    let data = check_size(&chunk, 12)?;
+                    let u32_at = |i: usize| {
+                        u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+                    };
+                    instrument.bank = u32_at(4);
+                    instrument.program = u32_at(8);
+                }
+                (b"LIST", Some(form_type)) => match &form_type {
+                    b"lrgn" => {
+                        for region in chunk.subchunks()? {
+                            if matches!(region.get_form_type(), Some(id) if &id == b"rgn " || &id == b"rgn2")
+                            {
+                                instrument.regions.push(DlsRegion::parse(&region)?);
+                            }
+                        }
+                    }
+                    b"lart" | b"lar2" => {
+                        instrument.articulation.extend(parse_articulation(&chunk)?)
+                    }
+                    b"INFO" => instrument.name = parse_name(&chunk)?.unwrap_or_default(),
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+        Ok(instrument)
+    }
+
+    pub fn is_drum(&self) -> bool {
+        self.bank & Self::DRUM_FLAG != 0
+    }
+
+    /// Bank select MSB (CC0)
+    pub fn get_bank_msb(&self) -> u8 {
+        (self.bank >> 8) as u8 & 0x7F
+    }
+
+    /// Bank select LSB (CC32)
+    pub fn get_bank_lsb(&self) -> u8 {
+        self.bank as u8 & 0x7F
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DlsRegion {
+    pub key_range: (u8, u8),
+    pub vel_range: (u8, u8),
+    pub options: u16,
+    /// Regions of the same non-zero key group cut each other off.
+    pub key_group: u16,
+    /// DLS2 only, for editors
+    pub layer: u16,
+    /// Overrides the wave's own wave sample.
+    pub wave_sample: Option<WaveSample>,
+    pub wave_link: WaveLink,
+    /// Overrides the instrument articulation.
+    pub articulation: Option<Vec<Connection>>,
+}
+
+impl DlsRegion {
+    pub fn parse(list: &RiffChunk) -> Result<Self, DlsError> {
+        let mut region = Self::default();
+        let mut has_header = false;
+        for chunk in list.subchunks()? {
+            match (&chunk.get_id(), chunk.get_form_type()) {
+                (b"rgnh", _) => {
+                    let data = check_size(&chunk, 12)?;
+                    let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+                    let range = |i: usize| (u16_at(i).min(127) as u8, u16_at(i + 2).min(127) as u8);
+                    region.key_range = range(0);
+                    region.vel_range = range(4);
+                    // DLS1 doesn't use velocity ranges, and files often leave them zeroed.
+                    if region.vel_range == (0, 0) {
+                        region.vel_range = (0, 127);
+                    }
+                    region.options = u16_at(8);
+                    region.key_group = u16_at(10);
+                    region.layer = data.get(12..14).map_or(0, |_| u16_at(12));
+                    has_header = true;
+                }
+                (b"wsmp", _) => region.wave_sample = Some(WaveSample::parse(&chunk)?),
+                (b"wlnk", _) => region.wave_link = WaveLink::parse(&chunk)?,
+                (b"LIST", Some(form_type)) if matches!(&form_type, b"lart" | b"lar2") => {
+                    region
+                        .articulation
+                        .get_or_insert_with(Vec::new)
+                        .extend(parse_articulation(&chunk)?);
+                }
+                _ => (),
+            }
+        }
+        if !has_header {
+            return Err(DlsError::MissingChunk(*b"rgnh"));
+        }
+        Ok(region)
+    }
+
+    pub fn contains(&self, key: u8, velocity: u8) -> bool {
+        (self.key_range.0..=self.key_range.1).contains(&key)
+            && (self.vel_range.0..=self.vel_range.1).contains(&velocity)
+    }
+}
+
+/// Wave link (`wlnk`): which wave a region plays.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct WaveLink {
+    pub options: u16,
+    pub phase_group: u16,
+    /// Channel bit mask. 1 is left, 2 is right.
+    pub channel: u32,
+    /// Pool table cue
+    pub table_index: u32,
+}
+
+impl WaveLink {
+    pub fn parse(chunk: &RiffChunk) -> Result<Self, DlsError> {
+        let data = check_size(chunk, 12)?;
+        let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        Ok(Self {
+            options: u16_at(0),
+            phase_group: u16_at(2),
+            channel: u32_at(4),
+            table_index: u32_at(8),
+        })
+    }
+}
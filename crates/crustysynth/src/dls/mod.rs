@@ -0,0 +1,426 @@
+//! Downloadable Sounds banks
+//!
+//! A DLS level 1 or 2 bank is a RIFF file of form `DLS `:
+//! - `colh`: number of instruments
+//! - `lins`: instruments, each made of regions and articulation
+//! - `ptbl`: pool table, offsets of the waves in the wave pool
+//! - `wvpl`: wave pool, a list of WAV files
+//!
+//! RMID files with an embedded bank are read too. A bank is played by converting it to a
+//! [`SoundFont`](crate::sf2::SoundFont), so both bank types share the same region model.
+
+use std::{error::Error, fmt::Display, fs::File, io, io::BufReader};
+
+use crate::sf2::riff::{parse_zstr, RiffChunk, RiffChunkError};
+
+pub mod articulation;
+pub mod convert;
+pub mod instrument;
+pub mod wave;
+
+use instrument::DlsInstrument;
+use wave::DlsWave;
+
+#[derive(Debug)]
+pub enum DlsError {
+    IOError { source: std::io::Error },
+    RiffError { source: RiffChunkError },
+    NotADls,
+    MissingChunk([u8; 4]),
+    InvalidChunkSize { id: [u8; 4], size: usize },
+}
+impl Error for DlsError {}
+impl Display for DlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IOError { source } => write!(f, "{source}"),
+            Self::RiffError { source } => write!(f, "{source}"),
+            Self::NotADls => write!(f, "File is not a RIFF DLS or RMID file with a DLS bank."),
+            Self::MissingChunk(id) => write!(
+                f,
+                "DLS bank is missing required chunk: {}",
+                String::from_utf8_lossy(id)
+            ),
+            Self::InvalidChunkSize { id, size } => write!(
+                f,
+                "Invalid size for chunk {}: {size}",
+                String::from_utf8_lossy(id)
+            ),
+        }
+    }
+}
+impl From<std::io::Error> for DlsError {
+    fn from(e: std::io::Error) -> Self {
+        Self::IOError { source: e }
+    }
+}
+impl From<RiffChunkError> for DlsError {
+    fn from(e: RiffChunkError) -> Self {
+        Self::RiffError { source: e }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DlsBank {
+    /// `INAM` of the bank `INFO` list
+    name: Option<String>,
+    instruments: Vec<DlsInstrument>,
+    waves: Vec<DlsWave>,
+    /// Wave index of each pool table cue. `None` if the cue doesn't point to a wave.
+    pool_table: Vec<Option<usize>>,
+}
+
+impl TryFrom<File> for DlsBank {
+    type Error = DlsError;
+
+    fn try_from(file: File) -> Result<Self, Self::Error> {
+        Self::read(&mut BufReader::new(file))
+    }
+}
+
+impl DlsBank {
+    pub fn read<R>(reader: &mut R) -> Result<Self, DlsError>
+    where
+        R: io::Read,
+    {
+        let riff = RiffChunk::read(reader)?;
+        if &riff.get_id() != b"RIFF" {
+            return Err(DlsError::NotADls);
+        }
+        match &riff.get_form_type().unwrap_or_default() {
+            b"DLS " => Self::parse(&riff),
+            // RMID: a MIDI file, optionally followed by the bank it plays with.
+            b"RMID" => {
+                let bank = riff
+                    .subchunks()?
+                    .into_iter()
+                    .find(|chunk| {
+                        &chunk.get_id() == b"RIFF" && chunk.get_form_type() == Some(*b"DLS ")
+                    })
+                    .ok_or(DlsError::NotADls)?;
+                Self::parse(&bank)
+            }
+            _ => Err(DlsError::NotADls),
+        }
+    }
+
+    fn parse(riff: &RiffChunk) -> Result<Self, DlsError> {
+        let chunks = riff.subchunks()?;
+        let find_list = |form_type: &[u8; 4]| {
+            chunks.iter().find(|chunk| {
+                &chunk.get_id() == b"LIST" && chunk.get_form_type() == Some(*form_type)
+            })
+        };
+
+        let name = match find_list(b"INFO") {
+            Some(info) => parse_name(info)?,
+            None => None,
+        };
+
+        let mut instruments = vec![];
+        let lins = find_list(b"lins").ok_or(DlsError::MissingChunk(*b"lins"))?;
+        for ins in lins.subchunks()? {
+            if ins.get_form_type() == Some(*b"ins ") {
+                instruments.push(DlsInstrument::parse(&ins)?);
+            }
+        }
+
+        // Pool table cues are byte offsets of waves, relative to the first wave.
+        let mut waves = vec![];
+        let mut wave_offsets = vec![];
+        let mut offset = 0;
+        let wvpl = find_list(b"wvpl").ok_or(DlsError::MissingChunk(*b"wvpl"))?;
+        for wave in wvpl.subchunks()? {
+            if wave.get_form_type() == Some(*b"wave") {
+                wave_offsets.push(offset);
+                waves.push(DlsWave::parse(&wave)?);
+            }
+            let len = wave.get_data().len();
+            offset += 8 + len + len % 2;
+        }
+
+        let pool_table = match chunks.iter().find(|chunk| &chunk.get_id() == b"ptbl") {
+            Some(ptbl) => parse_pool_table(ptbl)?
+                .into_iter()
+                .map(|cue| wave_offsets.iter().position(|o| *o == cue as usize))
+                .collect(),
+            // Without a table, cues are wave indices.
+            None => (0..waves.len()).map(Some).collect(),
+        };
+
+        Ok(Self {
+            name,
+            instruments,
+            waves,
+            pool_table,
+        })
+    }
+
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+    pub fn get_instruments(&self) -> &Vec<DlsInstrument> {
+        &self.instruments
+    }
+    pub fn get_waves(&self) -> &Vec<DlsWave> {
+        &self.waves
+    }
+
+    /// Wave index of a pool table cue, as used by region wave links.
+    pub fn get_wave_index(&self, table_index: u32) -> Option<usize> {
+        self.pool_table.get(table_index as usize).copied().flatten()
+    }
+}
+
+fn parse_pool_table(ptbl: &RiffChunk) -> Result<Vec<u32>, DlsError> {
+    let data = ptbl.get_data();
+    let u32_at = |i: usize| {
+        data.get(i..i + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let invalid = || DlsError::InvalidChunkSize {
+        id: *b"ptbl",
+        size: data.len(),
+    };
+
+    let header_size = u32_at(0).ok_or_else(invalid)? as usize;
+    let cue_count = u32_at(4).ok_or_else(invalid)? as usize;
+    (0..cue_count)
+        .map(|i| u32_at(header_size + i * 4).ok_or_else(invalid))
+        .collect()
+}
+
+/// `INAM` of an `INFO` list
+pub(crate) fn parse_name(info: &RiffChunk) -> Result<Option<String>, DlsError> {
+    Ok(info
+        .subchunks()?
+        .into_iter()
+        .find(|chunk| &chunk.get_id() == b"INAM")
+        .map(|chunk| parse_zstr(chunk.get_data())))
+}
+
+/// Check that a chunk is at least `size` bytes long.
+pub(crate) fn check_size(chunk: &RiffChunk, size: usize) -> Result<&[u8], DlsError> {
+    let data = chunk.get_data();
+    if data.len() < size {
+        return Err(DlsError::InvalidChunkSize {
+            id: chunk.get_id(),
+            size: data.len(),
+        });
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{midi::keys::MidiKey, sf2::generator::GeneratorType};
+
+    fn le16(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn le32(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn info(name: &str) -> RiffChunk {
+        RiffChunk::new_list(
+            *b"LIST",
+            *b"INFO",
+            &[RiffChunk::new(*b"INAM", format!("{name}\0").into_bytes())],
+        )
+    }
+
+    /// Wave sample chunk with one forward loop
+    fn wsmp(unity_note: u16, loop_start: u32, loop_length: u32) -> RiffChunk {
+        let mut data = le32(&[20]);
+        data.extend(le16(&[unity_note, 0]));
+        data.extend(le32(&[0, 0, 1]));
+        data.extend(le32(&[16, 0, loop_start, loop_length]));
+        RiffChunk::new(*b"wsmp", data)
+    }
+
+    fn wave(points: &[i16], unity_note: u16) -> RiffChunk {
+        let mut fmt = le16(&[1, 1]);
+        fmt.extend(le32(&[22050, 44100]));
+        fmt.extend(le16(&[2, 16]));
+        let data = points.iter().flat_map(|p| p.to_le_bytes()).collect();
+        RiffChunk::new_list(
+            *b"LIST",
+            *b"wave",
+            &[
+                RiffChunk::new(*b"fmt ", fmt),
+                wsmp(unity_note, 1, 2),
+                RiffChunk::new(*b"data", data),
+            ],
+        )
+    }
+
+    fn region(keys: (u16, u16), table_index: u32, articulation: Option<RiffChunk>) -> RiffChunk {
+        let mut chunks = vec![
+            RiffChunk::new(*b"rgnh", le16(&[keys.0, keys.1, 0, 127, 0, 0])),
+            RiffChunk::new(*b"wlnk", {
+                let mut data = le16(&[0, 0]);
+                data.extend(le32(&[1, table_index]));
+                data
+            }),
+        ];
+        if let Some(art) = articulation {
+            chunks.push(RiffChunk::new_list(*b"LIST", *b"lart", &[art]));
+        }
+        RiffChunk::new_list(*b"LIST", *b"rgn ", &chunks)
+    }
+
+    /// Articulation with one connection block
+    fn art1(source: u16, destination: u16, scale: i32) -> RiffChunk {
+        let mut data = le32(&[8, 1]);
+        data.extend(le16(&[source, 0, destination, 0]));
+        data.extend(scale.to_le_bytes());
+        RiffChunk::new(*b"art1", data)
+    }
+
+    fn instrument(
+        name: &str,
+        bank: u32,
+        program: u32,
+        regions: Vec<RiffChunk>,
+        articulation: Option<RiffChunk>,
+    ) -> RiffChunk {
+        let insh = le32(&[regions.len() as u32, bank, program]);
+        let mut chunks = vec![
+            RiffChunk::new(*b"insh", insh),
+            info(name),
+            RiffChunk::new_list(*b"LIST", *b"lrgn", &regions),
+        ];
+        if let Some(art) = articulation {
+            chunks.push(RiffChunk::new_list(*b"LIST", *b"lart", &[art]));
+        }
+        RiffChunk::new_list(*b"LIST", *b"ins ", &chunks)
+    }
+
+    /// Bank with a melodic instrument of two regions, and a drum kit. The melodic instrument is
+    /// panned, and its second region replaces that with its own articulation. The pool table
+    /// lists the waves in reverse.
+    fn test_bank() -> Vec<u8> {
+        let waves = [wave(&[1, 2, 3, 4], 60), wave(&[5, 6, 7, 8, 9, 10], 72)];
+        let wave_size = |wave: &RiffChunk| 8 + wave.get_data().len() as u32;
+        let mut ptbl = le32(&[8, 2]);
+        ptbl.extend(le32(&[wave_size(&waves[0]), 0]));
+
+        let piano = instrument(
+            "Piano",
+            0,
+            1,
+            vec![
+                region((0, 59), 1, None),
+                // +12 semitones
+                region((60, 127), 0, Some(art1(0, 0x0003, 1200 << 16))),
+            ],
+            Some(art1(0, 0x0004, 200 << 16)),
+        );
+        let drums = instrument(
+            "Drums",
+            0x8000_0000,
+            0,
+            vec![region((36, 36), 1, None)],
+            None,
+        );
+
+        let riff = RiffChunk::new_list(
+            *b"RIFF",
+            *b"DLS ",
+            &[
+                RiffChunk::new(*b"colh", le32(&[2])),
+                RiffChunk::new_list(*b"LIST", *b"lins", &[piano, drums]),
+                RiffChunk::new(*b"ptbl", ptbl),
+                RiffChunk::new_list(*b"LIST", *b"wvpl", &waves),
+                info("Test Bank"),
+            ],
+        );
+        let mut bytes = vec![];
+        riff.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_parse() {
+        let bank = DlsBank::read(&mut test_bank().as_slice()).unwrap();
+        assert_eq!(bank.get_name().map(String::as_str), Some("Test Bank"));
+
+        let instruments = bank.get_instruments();
+        assert_eq!(instruments.len(), 2);
+        assert_eq!(instruments[0].name, "Piano");
+        assert_eq!(instruments[0].regions.len(), 2);
+        assert_eq!(instruments[0].regions[1].key_range, (60, 127));
+        assert!(instruments[1].is_drum());
+
+        assert_eq!(bank.get_waves().len(), 2);
+        assert_eq!(bank.get_wave_index(0), Some(1));
+        assert_eq!(bank.get_wave_index(1), Some(0));
+        assert_eq!(bank.get_wave_index(2), None);
+    }
+
+    #[test]
+    fn test_to_soundfont() {
+        let bank = DlsBank::read(&mut test_bank().as_slice()).unwrap();
+        let font = bank.to_soundfont();
+        let headers = &font.get_hydra().sample_headers;
+        let key = |k: u8| MidiKey::try_from(k).unwrap();
+
+        let low = font.get_regions(0, 1, key(40), 100);
+        assert_eq!(low.len(), 1);
+        assert_eq!(low[0].get_sample_index(), 0);
+        assert_eq!(low[0].get(GeneratorType::SampleModes), 1);
+        assert_eq!(low[0].get(GeneratorType::FreqModLfo), -851);
+        assert_eq!(low[0].get(GeneratorType::Pan), 200);
+        let sample = &headers[0];
+        assert_eq!(sample.original_key, 60);
+        assert_eq!(font.get_sample_data().get_points(sample), &[1, 2, 3, 4]);
+        assert_eq!(
+            (
+                sample.start_loop - sample.start,
+                sample.end_loop - sample.start
+            ),
+            (1, 3)
+        );
+
+        let high = font.get_regions(0, 1, key(80), 100);
+        assert_eq!(high.len(), 1);
+        assert_eq!(high[0].get_sample_index(), 1);
+        assert_eq!(high[0].get(GeneratorType::CoarseTune), 12);
+        // Region articulation replaces the instrument's.
+        assert_eq!(high[0].get(GeneratorType::Pan), 0);
+        assert_eq!(high[0].get(GeneratorType::FreqModLfo), -851);
+        assert_eq!(headers[1].original_key, 72);
+
+        let drums = font.get_regions(128, 0, key(36), 100);
+        assert_eq!(drums.len(), 1);
+        assert_eq!(drums[0].get_sample_index(), 0);
+        assert!(font.get_regions(128, 0, key(37), 100).is_empty());
+        assert!(font.validate().is_empty());
+    }
+
+    #[test]
+    fn test_rmid() {
+        let midi = RiffChunk::new(*b"data", b"MThd".to_vec());
+        let bank = RiffChunk::read(&mut test_bank().as_slice()).unwrap();
+        let rmid = RiffChunk::new_list(*b"RIFF", *b"RMID", &[midi, bank]);
+        let mut bytes = vec![];
+        rmid.write(&mut bytes).unwrap();
+
+        let bank = DlsBank::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(bank.get_instruments().len(), 2);
+    }
+
+    #[test]
+    fn test_not_a_dls() {
+        let riff = RiffChunk::new_list(*b"RIFF", *b"sfbk", &[]);
+        let mut bytes = vec![];
+        riff.write(&mut bytes).unwrap();
+        assert!(matches!(
+            DlsBank::read(&mut bytes.as_slice()),
+            Err(DlsError::NotADls)
+        ));
+    }
+}
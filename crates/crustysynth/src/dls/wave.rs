@@ -0,0 +1,215 @@
+//! Waves (`wave`) and wave samples (`wsmp`)
+
+use crate::sf2::riff::RiffChunk;
+
+use super::{check_size, parse_name, DlsError};
+
+/// `fmt ` of a wave, as in WAV files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct WaveFormat {
+    /// 1 is integer PCM, 3 is float PCM
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    /// Bytes per frame
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+}
+
+impl WaveFormat {
+    pub const PCM: u16 = 1;
+    pub const IEEE_FLOAT: u16 = 3;
+
+    pub fn parse(chunk: &RiffChunk) -> Result<Self, DlsError> {
+        let data = check_size(chunk, 16)?;
+        let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        Ok(Self {
+            format_tag: u16_at(0),
+            channels: u16_at(2),
+            sample_rate: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+            block_align: u16_at(12),
+            bits_per_sample: u16_at(14),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DlsWave {
+    pub name: String,
+    pub format: WaveFormat,
+    pub wave_sample: Option<WaveSample>,
+    /// Raw `data`, in the layout given by `format`
+    pub data: Vec<u8>,
+}
+
+impl DlsWave {
+    pub fn parse(list: &RiffChunk) -> Result<Self, DlsError> {
+        let mut wave = Self::default();
+        let mut has_format = false;
+        for chunk in list.subchunks()? {
+            match &chunk.get_id() {
+                b"fmt " => {
+                    wave.format = WaveFormat::parse(&chunk)?;
+                    has_format = true;
+                }
+                b"data" => wave.data = chunk.get_data().clone(),
+                b"wsmp" => wave.wave_sample = Some(WaveSample::parse(&chunk)?),
+                b"LIST" if chunk.get_form_type() == Some(*b"INFO") => {
+                    wave.name = parse_name(&chunk)?.unwrap_or_default();
+                }
+                _ => (),
+            }
+        }
+        if !has_format {
+            return Err(DlsError::MissingChunk(*b"fmt "));
+        }
+        Ok(wave)
+    }
+
+    /// Points of the first channel as 16-bit PCM. 8, 16, 24 and 32-bit integer and 32-bit float
+    /// data is supported. Other formats have no points.
+    pub fn get_points(&self) -> Vec<i16> {
+        let format = &self.format;
+        let bytes = (format.bits_per_sample as usize).div_ceil(8);
+        let block_align = (format.block_align as usize).max(bytes);
+        if bytes == 0 {
+            return vec![];
+        }
+
+        let frames = self
+            .data
+            .chunks_exact(block_align)
+            .map(|frame| &frame[..bytes]);
+        match (format.format_tag, bytes) {
+            (WaveFormat::PCM, 1) => frames.map(|b| (b[0] as i16 - 128) << 8).collect(),
+            (WaveFormat::PCM, 2..=4) => frames
+                .map(|b| i16::from_le_bytes([b[bytes - 2], b[bytes - 1]]))
+                .collect(),
+            (WaveFormat::IEEE_FLOAT, 4) => frames
+                .map(|b| {
+                    let value = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                    (value.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Wave sample (`wsmp`): root key, tuning, gain and loops of a wave or region.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct WaveSample {
+    /// MIDI key of the recorded pitch
+    pub unity_note: u16,
+    /// Tuning in cents
+    pub fine_tune: i16,
+    /// Gain in 1/655360 dB. Negative values attenuate.
+    pub attenuation: i32,
+    pub options: u32,
+    pub loops: Vec<WaveLoop>,
+}
+
+impl WaveSample {
+    pub fn parse(chunk: &RiffChunk) -> Result<Self, DlsError> {
+        let data = check_size(chunk, 20)?;
+        let u32_at = |i: usize| {
+            data.get(i..i + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        let header_size = u32_at(0).unwrap_or(20) as usize;
+        let loop_count = u32_at(16).unwrap_or(0) as usize;
+        let mut loops = vec![];
+        for i in 0..loop_count {
+            let offset = header_size + i * WaveLoop::RECORD_SIZE;
+            let Some(bytes) = data.get(offset..offset + WaveLoop::RECORD_SIZE) else {
+                return Err(DlsError::InvalidChunkSize {
+                    id: chunk.get_id(),
+                    size: data.len(),
+                });
+            };
+            loops.push(WaveLoop::parse(bytes));
+        }
+
+        Ok(Self {
+            unity_note: u16::from_le_bytes([data[4], data[5]]),
+            fine_tune: i16::from_le_bytes([data[6], data[7]]),
+            attenuation: u32_at(8).unwrap_or(0) as i32,
+            options: u32_at(12).unwrap_or(0),
+            loops,
+        })
+    }
+
+    /// Gain in centibels, positive when attenuating
+    pub fn get_attenuation_cb(&self) -> i32 {
+        -(self.attenuation >> 16)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct WaveLoop {
+    /// 0 loops forward, 1 loops until release
+    pub loop_type: u32,
+    /// First point of the loop
+    pub start: u32,
+    pub length: u32,
+}
+
+impl WaveLoop {
+    pub const RECORD_SIZE: usize = 16;
+
+    pub const FORWARD: u32 = 0;
+    pub const RELEASE: u32 = 1;
+
+    pub fn parse(b: &[u8]) -> Self {
+        let u32_at = |i: usize| u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+        Self {
+            loop_type: u32_at(4),
+            start: u32_at(8),
+            length: u32_at(12),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wave(format_tag: u16, channels: u16, bits: u16, data: Vec<u8>) -> DlsWave {
+        DlsWave {
+            format: WaveFormat {
+                format_tag,
+                channels,
+                sample_rate: 22050,
+                block_align: channels * bits / 8,
+                bits_per_sample: bits,
+            },
+            data,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_points() {
+        let unsigned_8 = wave(WaveFormat::PCM, 1, 8, vec![0, 128, 255]);
+        assert_eq!(unsigned_8.get_points(), vec![-32768, 0, 127 << 8]);
+
+        // Left channel of stereo 16-bit
+        let stereo_16 = wave(WaveFormat::PCM, 2, 16, vec![1, 0, 9, 9, 2, 0, 9, 9]);
+        assert_eq!(stereo_16.get_points(), vec![1, 2]);
+
+        let signed_24 = wave(WaveFormat::PCM, 1, 24, vec![0xFF, 0x34, 0x12]);
+        assert_eq!(signed_24.get_points(), vec![0x1234]);
+
+        let float = wave(
+            WaveFormat::IEEE_FLOAT,
+            1,
+            32,
+            (-2.0_f32).to_le_bytes().to_vec(),
+        );
+        assert_eq!(float.get_points(), vec![-i16::MAX]);
+
+        let adpcm = wave(2, 1, 4, vec![0; 8]);
+        assert_eq!(adpcm.get_points(), vec![]);
+    }
+}
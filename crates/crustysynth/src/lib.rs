@@ -1,2 +1,7 @@
+pub mod dls;
 pub mod midi;
 pub mod midifile;
+pub mod sf2;
+pub mod sfz;
+pub mod synth;
+pub mod ump;
@@ -2,3 +2,8 @@
 
 pub mod channels;
 pub mod keys;
+pub mod percussion;
+pub mod programs;
+pub mod stream;
+pub mod sysex;
+pub mod timecode;
@@ -0,0 +1,299 @@
+//! General MIDI percussion key map and GM2 drum kits.
+//!
+//! In GM, channel 10 is reserved for percussion. Each key plays a different drum sound.
+
+use std::{error::Error, fmt::Display};
+
+use super::{channels::MidiChannel, keys::MidiKey};
+
+/// GM percussion channel.
+pub const GM_PERCUSSION_CHANNEL: MidiChannel = MidiChannel::Ch10;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GmPercussion {
+    AcousticBassDrum = 0x23,
+    BassDrum1 = 0x24,
+    SideStick = 0x25,
+    AcousticSnare = 0x26,
+    HandClap = 0x27,
+    ElectricSnare = 0x28,
+    LowFloorTom = 0x29,
+    ClosedHiHat = 0x2A,
+    HighFloorTom = 0x2B,
+    PedalHiHat = 0x2C,
+    LowTom = 0x2D,
+    OpenHiHat = 0x2E,
+    LowMidTom = 0x2F,
+    HiMidTom = 0x30,
+    CrashCymbal1 = 0x31,
+    HighTom = 0x32,
+    RideCymbal1 = 0x33,
+    ChineseCymbal = 0x34,
+    RideBell = 0x35,
+    Tambourine = 0x36,
+    SplashCymbal = 0x37,
+    Cowbell = 0x38,
+    CrashCymbal2 = 0x39,
+    Vibraslap = 0x3A,
+    RideCymbal2 = 0x3B,
+    HiBongo = 0x3C,
+    LowBongo = 0x3D,
+    MuteHiConga = 0x3E,
+    OpenHiConga = 0x3F,
+    LowConga = 0x40,
+    HighTimbale = 0x41,
+    LowTimbale = 0x42,
+    HighAgogo = 0x43,
+    LowAgogo = 0x44,
+    Cabasa = 0x45,
+    Maracas = 0x46,
+    ShortWhistle = 0x47,
+    LongWhistle = 0x48,
+    ShortGuiro = 0x49,
+    LongGuiro = 0x4A,
+    Claves = 0x4B,
+    HiWoodBlock = 0x4C,
+    LowWoodBlock = 0x4D,
+    MuteCuica = 0x4E,
+    OpenCuica = 0x4F,
+    MuteTriangle = 0x50,
+    OpenTriangle = 0x51,
+}
+
+#[derive(Debug)]
+pub enum GmPercussionError {
+    NotAPercussionKey(MidiKey),
+}
+impl Error for GmPercussionError {}
+impl Display for GmPercussionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotAPercussionKey(key) => write!(f, "No GM percussion mapped to key {key:?}"),
+        }
+    }
+}
+
+impl TryFrom<MidiKey> for GmPercussion {
+    type Error = GmPercussionError;
+
+    /// Get drum sound from channel 10 `NoteOn.key`.
+    fn try_from(key: MidiKey) -> Result<Self, Self::Error> {
+        match key {
+            MidiKey::B2 => Ok(Self::AcousticBassDrum),
+            MidiKey::C3 => Ok(Self::BassDrum1),
+            MidiKey::CS3 => Ok(Self::SideStick),
+            MidiKey::D3 => Ok(Self::AcousticSnare),
+            MidiKey::DS3 => Ok(Self::HandClap),
+            MidiKey::E3 => Ok(Self::ElectricSnare),
+            MidiKey::F3 => Ok(Self::LowFloorTom),
+            MidiKey::FS3 => Ok(Self::ClosedHiHat),
+            MidiKey::G3 => Ok(Self::HighFloorTom),
+            MidiKey::GS3 => Ok(Self::PedalHiHat),
+            MidiKey::A3 => Ok(Self::LowTom),
+            MidiKey::AS3 => Ok(Self::OpenHiHat),
+            MidiKey::B3 => Ok(Self::LowMidTom),
+            MidiKey::C4 => Ok(Self::HiMidTom),
+            MidiKey::CS4 => Ok(Self::CrashCymbal1),
+            MidiKey::D4 => Ok(Self::HighTom),
+            MidiKey::DS4 => Ok(Self::RideCymbal1),
+            MidiKey::E4 => Ok(Self::ChineseCymbal),
+            MidiKey::F4 => Ok(Self::RideBell),
+            MidiKey::FS4 => Ok(Self::Tambourine),
+            MidiKey::G4 => Ok(Self::SplashCymbal),
+            MidiKey::GS4 => Ok(Self::Cowbell),
+            MidiKey::A4 => Ok(Self::CrashCymbal2),
+            MidiKey::AS4 => Ok(Self::Vibraslap),
+            MidiKey::B4 => Ok(Self::RideCymbal2),
+            MidiKey::C5 => Ok(Self::HiBongo),
+            MidiKey::CS5 => Ok(Self::LowBongo),
+            MidiKey::D5 => Ok(Self::MuteHiConga),
+            MidiKey::DS5 => Ok(Self::OpenHiConga),
+            MidiKey::E5 => Ok(Self::LowConga),
+            MidiKey::F5 => Ok(Self::HighTimbale),
+            MidiKey::FS5 => Ok(Self::LowTimbale),
+            MidiKey::G5 => Ok(Self::HighAgogo),
+            MidiKey::GS5 => Ok(Self::LowAgogo),
+            MidiKey::A5 => Ok(Self::Cabasa),
+            MidiKey::AS5 => Ok(Self::Maracas),
+            MidiKey::B5 => Ok(Self::ShortWhistle),
+            MidiKey::C6 => Ok(Self::LongWhistle),
+            MidiKey::CS6 => Ok(Self::ShortGuiro),
+            MidiKey::D6 => Ok(Self::LongGuiro),
+            MidiKey::DS6 => Ok(Self::Claves),
+            MidiKey::E6 => Ok(Self::HiWoodBlock),
+            MidiKey::F6 => Ok(Self::LowWoodBlock),
+            MidiKey::FS6 => Ok(Self::MuteCuica),
+            MidiKey::G6 => Ok(Self::OpenCuica),
+            MidiKey::GS6 => Ok(Self::MuteTriangle),
+            MidiKey::A6 => Ok(Self::OpenTriangle),
+            _ => Err(GmPercussionError::NotAPercussionKey(key)),
+        }
+    }
+}
+
+impl From<GmPercussion> for MidiKey {
+    fn from(percussion: GmPercussion) -> Self {
+        MidiKey::try_from(percussion as u8).unwrap()
+    }
+}
+
+impl Display for GmPercussion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl GmPercussion {
+    /// Drum sound name as listed in the GM spec.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AcousticBassDrum => "Acoustic Bass Drum",
+            Self::BassDrum1 => "Bass Drum 1",
+            Self::SideStick => "Side Stick",
+            Self::AcousticSnare => "Acoustic Snare",
+            Self::HandClap => "Hand Clap",
+            Self::ElectricSnare => "Electric Snare",
+            Self::LowFloorTom => "Low Floor Tom",
+            Self::ClosedHiHat => "Closed Hi-Hat",
+            Self::HighFloorTom => "High Floor Tom",
+            Self::PedalHiHat => "Pedal Hi-Hat",
+            Self::LowTom => "Low Tom",
+            Self::OpenHiHat => "Open Hi-Hat",
+            Self::LowMidTom => "Low-Mid Tom",
+            Self::HiMidTom => "Hi-Mid Tom",
+            Self::CrashCymbal1 => "Crash Cymbal 1",
+            Self::HighTom => "High Tom",
+            Self::RideCymbal1 => "Ride Cymbal 1",
+            Self::ChineseCymbal => "Chinese Cymbal",
+            Self::RideBell => "Ride Bell",
+            Self::Tambourine => "Tambourine",
+            Self::SplashCymbal => "Splash Cymbal",
+            Self::Cowbell => "Cowbell",
+            Self::CrashCymbal2 => "Crash Cymbal 2",
+            Self::Vibraslap => "Vibraslap",
+            Self::RideCymbal2 => "Ride Cymbal 2",
+            Self::HiBongo => "Hi Bongo",
+            Self::LowBongo => "Low Bongo",
+            Self::MuteHiConga => "Mute Hi Conga",
+            Self::OpenHiConga => "Open Hi Conga",
+            Self::LowConga => "Low Conga",
+            Self::HighTimbale => "High Timbale",
+            Self::LowTimbale => "Low Timbale",
+            Self::HighAgogo => "High Agogo",
+            Self::LowAgogo => "Low Agogo",
+            Self::Cabasa => "Cabasa",
+            Self::Maracas => "Maracas",
+            Self::ShortWhistle => "Short Whistle",
+            Self::LongWhistle => "Long Whistle",
+            Self::ShortGuiro => "Short Guiro",
+            Self::LongGuiro => "Long Guiro",
+            Self::Claves => "Claves",
+            Self::HiWoodBlock => "Hi Wood Block",
+            Self::LowWoodBlock => "Low Wood Block",
+            Self::MuteCuica => "Mute Cuica",
+            Self::OpenCuica => "Open Cuica",
+            Self::MuteTriangle => "Mute Triangle",
+            Self::OpenTriangle => "Open Triangle",
+        }
+    }
+}
+
+/// GM2 drum kits, selected with bank MSB [`GM2_PERCUSSION_BANK_MSB`] and a program change.
+///
+/// [`GM2_PERCUSSION_BANK_MSB`]: super::programs::GM2_PERCUSSION_BANK_MSB
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Gm2DrumKit {
+    Standard = 0,
+    Room = 8,
+    Power = 16,
+    Electronic = 24,
+    Analog = 25,
+    Jazz = 32,
+    Brush = 40,
+    Orchestra = 48,
+    Sfx = 56,
+}
+
+#[derive(Debug)]
+pub enum Gm2DrumKitError {
+    NotADrumKit(u8),
+}
+impl Error for Gm2DrumKitError {}
+impl Display for Gm2DrumKitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotADrumKit(val) => write!(f, "No GM2 drum kit at program {val:#04x}"),
+        }
+    }
+}
+
+impl TryFrom<u8> for Gm2DrumKit {
+    type Error = Gm2DrumKitError;
+
+    /// Get drum kit from `ProgramChange.program`.
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(Self::Standard),
+            8 => Ok(Self::Room),
+            16 => Ok(Self::Power),
+            24 => Ok(Self::Electronic),
+            25 => Ok(Self::Analog),
+            32 => Ok(Self::Jazz),
+            40 => Ok(Self::Brush),
+            48 => Ok(Self::Orchestra),
+            56 => Ok(Self::Sfx),
+            _ => Err(Gm2DrumKitError::NotADrumKit(v)),
+        }
+    }
+}
+
+impl From<Gm2DrumKit> for u8 {
+    fn from(kit: Gm2DrumKit) -> Self {
+        kit as u8
+    }
+}
+
+impl Display for Gm2DrumKit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Standard => write!(f, "Standard Kit"),
+            Self::Room => write!(f, "Room Kit"),
+            Self::Power => write!(f, "Power Kit"),
+            Self::Electronic => write!(f, "Electronic Kit"),
+            Self::Analog => write!(f, "Analog Kit"),
+            Self::Jazz => write!(f, "Jazz Kit"),
+            Self::Brush => write!(f, "Brush Kit"),
+            Self::Orchestra => write!(f, "Orchestra Kit"),
+            Self::Sfx => write!(f, "SFX Kit"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percussion_keys() {
+        assert_eq!(
+            GmPercussion::try_from(MidiKey::B2).unwrap(),
+            GmPercussion::AcousticBassDrum
+        );
+        assert_eq!(
+            GmPercussion::try_from(MidiKey::FS3).unwrap(),
+            GmPercussion::ClosedHiHat
+        );
+        assert_eq!(
+            GmPercussion::try_from(MidiKey::A6).unwrap(),
+            GmPercussion::OpenTriangle
+        );
+        assert!(GmPercussion::try_from(MidiKey::AS2).is_err());
+        assert!(GmPercussion::try_from(MidiKey::AS6).is_err());
+
+        for i in 35..=81 {
+            let key = MidiKey::try_from(i).unwrap();
+            let percussion = GmPercussion::try_from(key).unwrap();
+            assert_eq!(MidiKey::from(percussion), key);
+        }
+    }
+}
@@ -0,0 +1,672 @@
+//! General MIDI program (instrument) definitions, including GM2 bank variations.
+
+use std::{error::Error, fmt::Display};
+
+/// Bank select MSB (CC 0) for GM2 melodic sounds. The LSB (CC 32) selects the variation.
+pub const GM2_MELODIC_BANK_MSB: u8 = 0x79;
+/// Bank select MSB (CC 0) for GM2 percussion. The program number selects the drum kit.
+pub const GM2_PERCUSSION_BANK_MSB: u8 = 0x78;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GmProgram {
+    AcousticGrandPiano = 0x00,
+    BrightAcousticPiano = 0x01,
+    ElectricGrandPiano = 0x02,
+    HonkyTonkPiano = 0x03,
+    ElectricPiano1 = 0x04,
+    ElectricPiano2 = 0x05,
+    Harpsichord = 0x06,
+    Clavi = 0x07,
+    Celesta = 0x08,
+    Glockenspiel = 0x09,
+    MusicBox = 0x0A,
+    Vibraphone = 0x0B,
+    Marimba = 0x0C,
+    Xylophone = 0x0D,
+    TubularBells = 0x0E,
+    Dulcimer = 0x0F,
+    DrawbarOrgan = 0x10,
+    PercussiveOrgan = 0x11,
+    RockOrgan = 0x12,
+    ChurchOrgan = 0x13,
+    ReedOrgan = 0x14,
+    Accordion = 0x15,
+    Harmonica = 0x16,
+    TangoAccordion = 0x17,
+    AcousticGuitarNylon = 0x18,
+    AcousticGuitarSteel = 0x19,
+    ElectricGuitarJazz = 0x1A,
+    ElectricGuitarClean = 0x1B,
+    ElectricGuitarMuted = 0x1C,
+    OverdrivenGuitar = 0x1D,
+    DistortionGuitar = 0x1E,
+    GuitarHarmonics = 0x1F,
+    AcousticBass = 0x20,
+    ElectricBassFinger = 0x21,
+    ElectricBassPick = 0x22,
+    FretlessBass = 0x23,
+    SlapBass1 = 0x24,
+    SlapBass2 = 0x25,
+    SynthBass1 = 0x26,
+    SynthBass2 = 0x27,
+    Violin = 0x28,
+    Viola = 0x29,
+    Cello = 0x2A,
+    Contrabass = 0x2B,
+    TremoloStrings = 0x2C,
+    PizzicatoStrings = 0x2D,
+    OrchestralHarp = 0x2E,
+    Timpani = 0x2F,
+    StringEnsemble1 = 0x30,
+    StringEnsemble2 = 0x31,
+    SynthStrings1 = 0x32,
+    SynthStrings2 = 0x33,
+    ChoirAahs = 0x34,
+    VoiceOohs = 0x35,
+    SynthVoice = 0x36,
+    OrchestraHit = 0x37,
+    Trumpet = 0x38,
+    Trombone = 0x39,
+    Tuba = 0x3A,
+    MutedTrumpet = 0x3B,
+    FrenchHorn = 0x3C,
+    BrassSection = 0x3D,
+    SynthBrass1 = 0x3E,
+    SynthBrass2 = 0x3F,
+    SopranoSax = 0x40,
+    AltoSax = 0x41,
+    TenorSax = 0x42,
+    BaritoneSax = 0x43,
+    Oboe = 0x44,
+    EnglishHorn = 0x45,
+    Bassoon = 0x46,
+    Clarinet = 0x47,
+    Piccolo = 0x48,
+    Flute = 0x49,
+    Recorder = 0x4A,
+    PanFlute = 0x4B,
+    BlownBottle = 0x4C,
+    Shakuhachi = 0x4D,
+    Whistle = 0x4E,
+    Ocarina = 0x4F,
+    Lead1Square = 0x50,
+    Lead2Sawtooth = 0x51,
+    Lead3Calliope = 0x52,
+    Lead4Chiff = 0x53,
+    Lead5Charang = 0x54,
+    Lead6Voice = 0x55,
+    Lead7Fifths = 0x56,
+    Lead8BassLead = 0x57,
+    Pad1NewAge = 0x58,
+    Pad2Warm = 0x59,
+    Pad3Polysynth = 0x5A,
+    Pad4Choir = 0x5B,
+    Pad5Bowed = 0x5C,
+    Pad6Metallic = 0x5D,
+    Pad7Halo = 0x5E,
+    Pad8Sweep = 0x5F,
+    Fx1Rain = 0x60,
+    Fx2Soundtrack = 0x61,
+    Fx3Crystal = 0x62,
+    Fx4Atmosphere = 0x63,
+    Fx5Brightness = 0x64,
+    Fx6Goblins = 0x65,
+    Fx7Echoes = 0x66,
+    Fx8SciFi = 0x67,
+    Sitar = 0x68,
+    Banjo = 0x69,
+    Shamisen = 0x6A,
+    Koto = 0x6B,
+    Kalimba = 0x6C,
+    Bagpipe = 0x6D,
+    Fiddle = 0x6E,
+    Shanai = 0x6F,
+    TinkleBell = 0x70,
+    Agogo = 0x71,
+    SteelDrums = 0x72,
+    Woodblock = 0x73,
+    TaikoDrum = 0x74,
+    MelodicTom = 0x75,
+    SynthDrum = 0x76,
+    ReverseCymbal = 0x77,
+    GuitarFretNoise = 0x78,
+    BreathNoise = 0x79,
+    Seashore = 0x7A,
+    BirdTweet = 0x7B,
+    TelephoneRing = 0x7C,
+    Helicopter = 0x7D,
+    Applause = 0x7E,
+    Gunshot = 0x7F,
+}
+
+#[derive(Debug)]
+pub enum GmProgramError {
+    NotAProgram(u8),
+}
+impl Error for GmProgramError {}
+impl Display for GmProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotAProgram(val) => write!(f, "GM program out of range: {val:#04x}"),
+        }
+    }
+}
+
+impl TryFrom<u8> for GmProgram {
+    type Error = GmProgramError;
+
+    /// Get program from `ProgramChange.program`.
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0x00 => Ok(Self::AcousticGrandPiano),
+            0x01 => Ok(Self::BrightAcousticPiano),
+            0x02 => Ok(Self::ElectricGrandPiano),
+            0x03 => Ok(Self::HonkyTonkPiano),
+            0x04 => Ok(Self::ElectricPiano1),
+            0x05 => Ok(Self::ElectricPiano2),
+            0x06 => Ok(Self::Harpsichord),
+            0x07 => Ok(Self::Clavi),
+            0x08 => Ok(Self::Celesta),
+            0x09 => Ok(Self::Glockenspiel),
+            0x0A => Ok(Self::MusicBox),
+            0x0B => Ok(Self::Vibraphone),
+            0x0C => Ok(Self::Marimba),
+            0x0D => Ok(Self::Xylophone),
+            0x0E => Ok(Self::TubularBells),
+            0x0F => Ok(Self::Dulcimer),
+            0x10 => Ok(Self::DrawbarOrgan),
+            0x11 => Ok(Self::PercussiveOrgan),
+            0x12 => Ok(Self::RockOrgan),
+            0x13 => Ok(Self::ChurchOrgan),
+            0x14 => Ok(Self::ReedOrgan),
+            0x15 => Ok(Self::Accordion),
+            0x16 => Ok(Self::Harmonica),
+            0x17 => Ok(Self::TangoAccordion),
+            0x18 => Ok(Self::AcousticGuitarNylon),
+            0x19 => Ok(Self::AcousticGuitarSteel),
+            0x1A => Ok(Self::ElectricGuitarJazz),
+            0x1B => Ok(Self::ElectricGuitarClean),
+            0x1C => Ok(Self::ElectricGuitarMuted),
+            0x1D => Ok(Self::OverdrivenGuitar),
+            0x1E => Ok(Self::DistortionGuitar),
+            0x1F => Ok(Self::GuitarHarmonics),
+            0x20 => Ok(Self::AcousticBass),
+            0x21 => Ok(Self::ElectricBassFinger),
+            0x22 => Ok(Self::ElectricBassPick),
+            0x23 => Ok(Self::FretlessBass),
+            0x24 => Ok(Self::SlapBass1),
+            0x25 => Ok(Self::SlapBass2),
+            0x26 => Ok(Self::SynthBass1),
+            0x27 => Ok(Self::SynthBass2),
+            0x28 => Ok(Self::Violin),
+            0x29 => Ok(Self::Viola),
+            0x2A => Ok(Self::Cello),
+            0x2B => Ok(Self::Contrabass),
+            0x2C => Ok(Self::TremoloStrings),
+            0x2D => Ok(Self::PizzicatoStrings),
+            0x2E => Ok(Self::OrchestralHarp),
+            0x2F => Ok(Self::Timpani),
+            0x30 => Ok(Self::StringEnsemble1),
+            0x31 => Ok(Self::StringEnsemble2),
+            0x32 => Ok(Self::SynthStrings1),
+            0x33 => Ok(Self::SynthStrings2),
+            0x34 => Ok(Self::ChoirAahs),
+            0x35 => Ok(Self::VoiceOohs),
+            0x36 => Ok(Self::SynthVoice),
+            0x37 => Ok(Self::OrchestraHit),
+            0x38 => Ok(Self::Trumpet),
+            0x39 => Ok(Self::Trombone),
+            0x3A => Ok(Self::Tuba),
+            0x3B => Ok(Self::MutedTrumpet),
+            0x3C => Ok(Self::FrenchHorn),
+            0x3D => Ok(Self::BrassSection),
+            0x3E => Ok(Self::SynthBrass1),
+            0x3F => Ok(Self::SynthBrass2),
+            0x40 => Ok(Self::SopranoSax),
+            0x41 => Ok(Self::AltoSax),
+            0x42 => Ok(Self::TenorSax),
+            0x43 => Ok(Self::BaritoneSax),
+            0x44 => Ok(Self::Oboe),
+            0x45 => Ok(Self::EnglishHorn),
+            0x46 => Ok(Self::Bassoon),
+            0x47 => Ok(Self::Clarinet),
+            0x48 => Ok(Self::Piccolo),
+            0x49 => Ok(Self::Flute),
+            0x4A => Ok(Self::Recorder),
+            0x4B => Ok(Self::PanFlute),
+            0x4C => Ok(Self::BlownBottle),
+            0x4D => Ok(Self::Shakuhachi),
+            0x4E => Ok(Self::Whistle),
+            0x4F => Ok(Self::Ocarina),
+            0x50 => Ok(Self::Lead1Square),
+            0x51 => Ok(Self::Lead2Sawtooth),
+            0x52 => Ok(Self::Lead3Calliope),
+            0x53 => Ok(Self::Lead4Chiff),
+            0x54 => Ok(Self::Lead5Charang),
+            0x55 => Ok(Self::Lead6Voice),
+            0x56 => Ok(Self::Lead7Fifths),
+            0x57 => Ok(Self::Lead8BassLead),
+            0x58 => Ok(Self::Pad1NewAge),
+            0x59 => Ok(Self::Pad2Warm),
+            0x5A => Ok(Self::Pad3Polysynth),
+            0x5B => Ok(Self::Pad4Choir),
+            0x5C => Ok(Self::Pad5Bowed),
+            0x5D => Ok(Self::Pad6Metallic),
+            0x5E => Ok(Self::Pad7Halo),
+            0x5F => Ok(Self::Pad8Sweep),
+            0x60 => Ok(Self::Fx1Rain),
+            0x61 => Ok(Self::Fx2Soundtrack),
+            0x62 => Ok(Self::Fx3Crystal),
+            0x63 => Ok(Self::Fx4Atmosphere),
+            0x64 => Ok(Self::Fx5Brightness),
+            0x65 => Ok(Self::Fx6Goblins),
+            0x66 => Ok(Self::Fx7Echoes),
+            0x67 => Ok(Self::Fx8SciFi),
+            0x68 => Ok(Self::Sitar),
+            0x69 => Ok(Self::Banjo),
+            0x6A => Ok(Self::Shamisen),
+            0x6B => Ok(Self::Koto),
+            0x6C => Ok(Self::Kalimba),
+            0x6D => Ok(Self::Bagpipe),
+            0x6E => Ok(Self::Fiddle),
+            0x6F => Ok(Self::Shanai),
+            0x70 => Ok(Self::TinkleBell),
+            0x71 => Ok(Self::Agogo),
+            0x72 => Ok(Self::SteelDrums),
+            0x73 => Ok(Self::Woodblock),
+            0x74 => Ok(Self::TaikoDrum),
+            0x75 => Ok(Self::MelodicTom),
+            0x76 => Ok(Self::SynthDrum),
+            0x77 => Ok(Self::ReverseCymbal),
+            0x78 => Ok(Self::GuitarFretNoise),
+            0x79 => Ok(Self::BreathNoise),
+            0x7A => Ok(Self::Seashore),
+            0x7B => Ok(Self::BirdTweet),
+            0x7C => Ok(Self::TelephoneRing),
+            0x7D => Ok(Self::Helicopter),
+            0x7E => Ok(Self::Applause),
+            0x7F => Ok(Self::Gunshot),
+            128..=255 => Err(GmProgramError::NotAProgram(v)),
+        }
+    }
+}
+
+impl From<GmProgram> for u8 {
+    fn from(program: GmProgram) -> Self {
+        program as u8
+    }
+}
+
+impl Display for GmProgram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl GmProgram {
+    /// Instrument name as listed in the GM spec.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AcousticGrandPiano => "Acoustic Grand Piano",
+            Self::BrightAcousticPiano => "Bright Acoustic Piano",
+            Self::ElectricGrandPiano => "Electric Grand Piano",
+            Self::HonkyTonkPiano => "Honky-tonk Piano",
+            Self::ElectricPiano1 => "Electric Piano 1",
+            Self::ElectricPiano2 => "Electric Piano 2",
+            Self::Harpsichord => "Harpsichord",
+            Self::Clavi => "Clavi",
+            Self::Celesta => "Celesta",
+            Self::Glockenspiel => "Glockenspiel",
+            Self::MusicBox => "Music Box",
+            Self::Vibraphone => "Vibraphone",
+            Self::Marimba => "Marimba",
+            Self::Xylophone => "Xylophone",
+            Self::TubularBells => "Tubular Bells",
+            Self::Dulcimer => "Dulcimer",
+            Self::DrawbarOrgan => "Drawbar Organ",
+            Self::PercussiveOrgan => "Percussive Organ",
+            Self::RockOrgan => "Rock Organ",
+            Self::ChurchOrgan => "Church Organ",
+            Self::ReedOrgan => "Reed Organ",
+            Self::Accordion => "Accordion",
+            Self::Harmonica => "Harmonica",
+            Self::TangoAccordion => "Tango Accordion",
+            Self::AcousticGuitarNylon => "Acoustic Guitar (nylon)",
+            Self::AcousticGuitarSteel => "Acoustic Guitar (steel)",
+            Self::ElectricGuitarJazz => "Electric Guitar (jazz)",
+            Self::ElectricGuitarClean => "Electric Guitar (clean)",
+            Self::ElectricGuitarMuted => "Electric Guitar (muted)",
+            Self::OverdrivenGuitar => "Overdriven Guitar",
+            Self::DistortionGuitar => "Distortion Guitar",
+            Self::GuitarHarmonics => "Guitar Harmonics",
+            Self::AcousticBass => "Acoustic Bass",
+            Self::ElectricBassFinger => "Electric Bass (finger)",
+            Self::ElectricBassPick => "Electric Bass (pick)",
+            Self::FretlessBass => "Fretless Bass",
+            Self::SlapBass1 => "Slap Bass 1",
+            Self::SlapBass2 => "Slap Bass 2",
+            Self::SynthBass1 => "Synth Bass 1",
+            Self::SynthBass2 => "Synth Bass 2",
+            Self::Violin => "Violin",
+            Self::Viola => "Viola",
+            Self::Cello => "Cello",
+            Self::Contrabass => "Contrabass",
+            Self::TremoloStrings => "Tremolo Strings",
+            Self::PizzicatoStrings => "Pizzicato Strings",
+            Self::OrchestralHarp => "Orchestral Harp",
+            Self::Timpani => "Timpani",
+            Self::StringEnsemble1 => "String Ensemble 1",
+            Self::StringEnsemble2 => "String Ensemble 2",
+            Self::SynthStrings1 => "Synth Strings 1",
+            Self::SynthStrings2 => "Synth Strings 2",
+            Self::ChoirAahs => "Choir Aahs",
+            Self::VoiceOohs => "Voice Oohs",
+            Self::SynthVoice => "Synth Voice",
+            Self::OrchestraHit => "Orchestra Hit",
+            Self::Trumpet => "Trumpet",
+            Self::Trombone => "Trombone",
+            Self::Tuba => "Tuba",
+            Self::MutedTrumpet => "Muted Trumpet",
+            Self::FrenchHorn => "French Horn",
+            Self::BrassSection => "Brass Section",
+            Self::SynthBrass1 => "Synth Brass 1",
+            Self::SynthBrass2 => "Synth Brass 2",
+            Self::SopranoSax => "Soprano Sax",
+            Self::AltoSax => "Alto Sax",
+            Self::TenorSax => "Tenor Sax",
+            Self::BaritoneSax => "Baritone Sax",
+            Self::Oboe => "Oboe",
+            Self::EnglishHorn => "English Horn",
+            Self::Bassoon => "Bassoon",
+            Self::Clarinet => "Clarinet",
+            Self::Piccolo => "Piccolo",
+            Self::Flute => "Flute",
+            Self::Recorder => "Recorder",
+            Self::PanFlute => "Pan Flute",
+            Self::BlownBottle => "Blown Bottle",
+            Self::Shakuhachi => "Shakuhachi",
+            Self::Whistle => "Whistle",
+            Self::Ocarina => "Ocarina",
+            Self::Lead1Square => "Lead 1 (square)",
+            Self::Lead2Sawtooth => "Lead 2 (sawtooth)",
+            Self::Lead3Calliope => "Lead 3 (calliope)",
+            Self::Lead4Chiff => "Lead 4 (chiff)",
+            Self::Lead5Charang => "Lead 5 (charang)",
+            Self::Lead6Voice => "Lead 6 (voice)",
+            Self::Lead7Fifths => "Lead 7 (fifths)",
+            Self::Lead8BassLead => "Lead 8 (bass + lead)",
+            Self::Pad1NewAge => "Pad 1 (new age)",
+            Self::Pad2Warm => "Pad 2 (warm)",
+            Self::Pad3Polysynth => "Pad 3 (polysynth)",
+            Self::Pad4Choir => "Pad 4 (choir)",
+            Self::Pad5Bowed => "Pad 5 (bowed)",
+            Self::Pad6Metallic => "Pad 6 (metallic)",
+            Self::Pad7Halo => "Pad 7 (halo)",
+            Self::Pad8Sweep => "Pad 8 (sweep)",
+            Self::Fx1Rain => "FX 1 (rain)",
+            Self::Fx2Soundtrack => "FX 2 (soundtrack)",
+            Self::Fx3Crystal => "FX 3 (crystal)",
+            Self::Fx4Atmosphere => "FX 4 (atmosphere)",
+            Self::Fx5Brightness => "FX 5 (brightness)",
+            Self::Fx6Goblins => "FX 6 (goblins)",
+            Self::Fx7Echoes => "FX 7 (echoes)",
+            Self::Fx8SciFi => "FX 8 (sci-fi)",
+            Self::Sitar => "Sitar",
+            Self::Banjo => "Banjo",
+            Self::Shamisen => "Shamisen",
+            Self::Koto => "Koto",
+            Self::Kalimba => "Kalimba",
+            Self::Bagpipe => "Bagpipe",
+            Self::Fiddle => "Fiddle",
+            Self::Shanai => "Shanai",
+            Self::TinkleBell => "Tinkle Bell",
+            Self::Agogo => "Agogo",
+            Self::SteelDrums => "Steel Drums",
+            Self::Woodblock => "Woodblock",
+            Self::TaikoDrum => "Taiko Drum",
+            Self::MelodicTom => "Melodic Tom",
+            Self::SynthDrum => "Synth Drum",
+            Self::ReverseCymbal => "Reverse Cymbal",
+            Self::GuitarFretNoise => "Guitar Fret Noise",
+            Self::BreathNoise => "Breath Noise",
+            Self::Seashore => "Seashore",
+            Self::BirdTweet => "Bird Tweet",
+            Self::TelephoneRing => "Telephone Ring",
+            Self::Helicopter => "Helicopter",
+            Self::Applause => "Applause",
+            Self::Gunshot => "Gunshot",
+        }
+    }
+
+    /// Instrument family. Each family is a block of 8 consecutive programs.
+    pub fn family(&self) -> GmFamily {
+        match *self as u8 >> 3 {
+            0x0 => GmFamily::Piano,
+            0x1 => GmFamily::ChromaticPercussion,
+            0x2 => GmFamily::Organ,
+            0x3 => GmFamily::Guitar,
+            0x4 => GmFamily::Bass,
+            0x5 => GmFamily::Strings,
+            0x6 => GmFamily::Ensemble,
+            0x7 => GmFamily::Brass,
+            0x8 => GmFamily::Reed,
+            0x9 => GmFamily::Pipe,
+            0xA => GmFamily::SynthLead,
+            0xB => GmFamily::SynthPad,
+            0xC => GmFamily::SynthEffects,
+            0xD => GmFamily::Ethnic,
+            0xE => GmFamily::Percussive,
+            0xF => GmFamily::SoundEffects,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Name of a GM2 variation of this program, selected with bank MSB [`GM2_MELODIC_BANK_MSB`]
+    /// and the given bank LSB. Variation 0 is the GM instrument itself.
+    /// Returns `None` if GM2 does not define the variation.
+    pub fn gm2_variation(&self, bank_lsb: u8) -> Option<&'static str> {
+        if bank_lsb == 0 {
+            return Some(self.name());
+        }
+        let variations: &[&'static str] = match self {
+            Self::AcousticGrandPiano => &["Wide Acoustic Grand", "Dark Acoustic Grand"],
+            Self::BrightAcousticPiano => &["Wide Bright Acoustic"],
+            Self::ElectricGrandPiano => &["Wide Electric Grand"],
+            Self::HonkyTonkPiano => &["Wide Honky-tonk"],
+            Self::ElectricPiano1 => &[
+                "Detuned Electric Piano 1",
+                "Electric Piano 1 (velocity mix)",
+                "60's Electric Piano",
+            ],
+            Self::ElectricPiano2 => &[
+                "Detuned Electric Piano 2",
+                "Electric Piano 2 (velocity mix)",
+                "EP Legend",
+                "EP Phase",
+            ],
+            Self::Harpsichord => &[
+                "Harpsichord (octave mix)",
+                "Harpsichord (wide)",
+                "Harpsichord (with key off)",
+            ],
+            Self::Clavi => &["Pulse Clavi"],
+            Self::Vibraphone => &["Wet Vibraphone"],
+            Self::Marimba => &["Marimba (wide)"],
+            Self::TubularBells => &["Church Bell", "Carillon"],
+            Self::DrawbarOrgan => &[
+                "Detuned Drawbar Organ",
+                "Italian 60's Organ",
+                "Drawbar Organ 2",
+            ],
+            Self::PercussiveOrgan => &["Detuned Percussive Organ", "Percussive Organ 2"],
+            Self::ChurchOrgan => &["Church Organ (octave mix)", "Detuned Church Organ"],
+            Self::ReedOrgan => &["Puff Organ"],
+            Self::Accordion => &["Accordion 2"],
+            Self::AcousticGuitarNylon => &[
+                "Ukulele",
+                "Acoustic Guitar (nylon + key off)",
+                "Acoustic Guitar (nylon 2)",
+            ],
+            Self::AcousticGuitarSteel => &[
+                "12-Strings Guitar",
+                "Mandolin",
+                "Steel Guitar with Body Sound",
+            ],
+            Self::ElectricGuitarJazz => &["Electric Guitar (pedal steel)"],
+            Self::ElectricGuitarClean => &["Electric Guitar (detuned clean)", "Mid Tone Guitar"],
+            Self::ElectricGuitarMuted => &[
+                "Electric Guitar (funky cutting)",
+                "Electric Guitar (muted velo-sw)",
+                "Jazz Man",
+            ],
+            Self::OverdrivenGuitar => &["Guitar Pinch"],
+            Self::DistortionGuitar => &[
+                "Distortion Guitar (with feedback)",
+                "Distorted Rhythm Guitar",
+            ],
+            Self::GuitarHarmonics => &["Guitar Feedback"],
+            Self::ElectricBassFinger => &["Finger Slap Bass"],
+            Self::SynthBass1 => &[
+                "Synth Bass (warm)",
+                "Synth Bass 3 (resonance)",
+                "Clavi Bass",
+                "Hammer",
+            ],
+            Self::SynthBass2 => &[
+                "Synth Bass 4 (attack)",
+                "Synth Bass (rubber)",
+                "Attack Pulse",
+            ],
+            Self::Violin => &["Violin (slow attack)"],
+            Self::OrchestralHarp => &["Yang Chin"],
+            Self::StringEnsemble1 => &["Strings and Brass", "60s Strings"],
+            Self::SynthStrings1 => &["Synth Strings 3"],
+            Self::ChoirAahs => &["Choir Aahs 2"],
+            Self::VoiceOohs => &["Humming"],
+            Self::SynthVoice => &["Analog Voice"],
+            Self::OrchestraHit => &["Bass Hit Plus", "6th Hit", "Euro Hit"],
+            Self::Trumpet => &["Dark Trumpet Soft"],
+            Self::Trombone => &["Trombone 2", "Bright Trombone"],
+            Self::MutedTrumpet => &["Muted Trumpet 2"],
+            Self::FrenchHorn => &["French Horn 2 (warm)"],
+            Self::BrassSection => &["Brass Section 2 (octave mix)"],
+            Self::SynthBrass1 => &["Synth Brass 3", "Analog Synth Brass 1", "Jump Brass"],
+            Self::SynthBrass2 => &["Synth Brass 4", "Analog Synth Brass 2"],
+            Self::Lead1Square => &["Square Wave", "Sine Wave"],
+            Self::Lead2Sawtooth => &["Saw Wave", "Doctor Solo", "Natural Lead", "Sequenced Saw"],
+            Self::Lead5Charang => &["Wire Lead"],
+            Self::Lead8BassLead => &["Delayed Lead"],
+            Self::Pad2Warm => &["Sine Pad"],
+            Self::Pad4Choir => &["Itopia"],
+            Self::Fx3Crystal => &["Synth Mallet"],
+            Self::Fx7Echoes => &["Echo Bell", "Echo Pan"],
+            Self::Sitar => &["Sitar 2 (bend)"],
+            Self::Koto => &["Taisho Koto"],
+            Self::Woodblock => &["Castanets"],
+            Self::TaikoDrum => &["Concert Bass Drum"],
+            Self::MelodicTom => &["Melodic Tom 2 (power)"],
+            Self::SynthDrum => &["Rhythm Box Tom", "Electric Drum"],
+            Self::GuitarFretNoise => &["Guitar Cutting Noise", "Acoustic Bass String Slap"],
+            Self::BreathNoise => &["Flute Key Click"],
+            Self::Seashore => &["Rain", "Thunder", "Wind", "Stream", "Bubble"],
+            Self::BirdTweet => &["Dog", "Horse Gallop", "Bird Tweet 2"],
+            Self::TelephoneRing => &[
+                "Telephone Ring 2",
+                "Door Creaking",
+                "Door",
+                "Scratch",
+                "Wind Chime",
+            ],
+            Self::Helicopter => &[
+                "Car Engine",
+                "Car Stop",
+                "Car Pass",
+                "Car Crash",
+                "Siren",
+                "Train",
+                "Jetplane",
+                "Starship",
+                "Burst Noise",
+            ],
+            Self::Applause => &["Laughing", "Screaming", "Punch", "Heart Beat", "Footsteps"],
+            Self::Gunshot => &["Machine Gun", "Lasergun", "Explosion"],
+            _ => &[],
+        };
+        variations.get(bank_lsb as usize - 1).copied()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GmFamily {
+    Piano,
+    ChromaticPercussion,
+    Organ,
+    Guitar,
+    Bass,
+    Strings,
+    Ensemble,
+    Brass,
+    Reed,
+    Pipe,
+    SynthLead,
+    SynthPad,
+    SynthEffects,
+    Ethnic,
+    Percussive,
+    SoundEffects,
+}
+
+impl Display for GmFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Piano => write!(f, "Piano"),
+            Self::ChromaticPercussion => write!(f, "Chromatic Percussion"),
+            Self::Organ => write!(f, "Organ"),
+            Self::Guitar => write!(f, "Guitar"),
+            Self::Bass => write!(f, "Bass"),
+            Self::Strings => write!(f, "Strings"),
+            Self::Ensemble => write!(f, "Ensemble"),
+            Self::Brass => write!(f, "Brass"),
+            Self::Reed => write!(f, "Reed"),
+            Self::Pipe => write!(f, "Pipe"),
+            Self::SynthLead => write!(f, "Synth Lead"),
+            Self::SynthPad => write!(f, "Synth Pad"),
+            Self::SynthEffects => write!(f, "Synth Effects"),
+            Self::Ethnic => write!(f, "Ethnic"),
+            Self::Percussive => write!(f, "Percussive"),
+            Self::SoundEffects => write!(f, "Sound Effects"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_programs_roundtrip() {
+        for i in 0..128 {
+            let program = GmProgram::try_from(i).unwrap();
+            assert_eq!(u8::from(program), i);
+        }
+        assert!(GmProgram::try_from(128).is_err());
+    }
+
+    #[test]
+    fn test_families() {
+        assert_eq!(GmProgram::AcousticGrandPiano.family(), GmFamily::Piano);
+        assert_eq!(GmProgram::Dulcimer.family(), GmFamily::ChromaticPercussion);
+        assert_eq!(GmProgram::Violin.family(), GmFamily::Strings);
+        assert_eq!(GmProgram::Gunshot.family(), GmFamily::SoundEffects);
+    }
+
+    #[test]
+    fn test_gm2_variations() {
+        let piano = GmProgram::AcousticGrandPiano;
+        assert_eq!(piano.gm2_variation(0), Some("Acoustic Grand Piano"));
+        assert_eq!(piano.gm2_variation(2), Some("Dark Acoustic Grand"));
+        assert_eq!(piano.gm2_variation(3), None);
+        assert_eq!(GmProgram::Helicopter.gm2_variation(9), Some("Burst Noise"));
+        assert_eq!(GmProgram::Celesta.gm2_variation(1), None);
+    }
+}
@@ -0,0 +1,248 @@
+//! Incremental parser for live MIDI byte streams
+//!
+//! Unlike file tracks, a wire stream has no delta times or meta events, and bytes arrive in
+//! arbitrary chunks. Real-time messages may appear anywhere, even in the middle of another
+//! message, and a SysEx message may be ended by any status byte instead of End of Exclusive.
+
+use std::collections::{vec_deque, VecDeque};
+
+use super::sysex::manufacturer::ManufacturerId;
+use crate::midifile::miditrack::midievent::MidiEvent;
+
+#[derive(Debug, Default)]
+pub struct MidiStreamParser {
+    /// Status of the message being assembled. Channel statuses are kept after the message is
+    /// complete, for running status.
+    status: Option<u8>,
+    data: Vec<u8>,
+    /// SysEx bytes after F0, while inside a SysEx message.
+    sysex: Option<Vec<u8>>,
+    events: VecDeque<MidiEvent>,
+}
+
+impl MidiStreamParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a chunk of bytes and iterate over the completed events. An incomplete message at
+    /// the end of the chunk is kept and completed by the following chunks.
+    pub fn feed(&mut self, bytes: &[u8]) -> vec_deque::Drain<'_, MidiEvent> {
+        for byte in bytes {
+            self.push(*byte);
+        }
+        self.events.drain(..)
+    }
+
+    /// Forget running status and any partially received message.
+    pub fn reset(&mut self) {
+        self.status = None;
+        self.data.clear();
+        self.sysex = None;
+    }
+
+    fn push(&mut self, byte: u8) {
+        match byte {
+            // Real-time messages don't interrupt anything.
+            0xF8..=0xFF => self.events.push_back(realtime_event(byte)),
+            0xF7 => match self.sysex.take() {
+                Some(sysex) => self.finish_sysex(sysex),
+                None => {
+                    // A stray end of exclusive is still a system common message.
+                    self.status = None;
+                    self.data.clear();
+                    self.events.push_back(MidiEvent::EndOfExclusive);
+                }
+            },
+            0x80..=0xF6 => {
+                if let Some(sysex) = self.sysex.take() {
+                    self.finish_sysex(sysex);
+                }
+                self.start_message(byte);
+            }
+            _ => {
+                if let Some(sysex) = &mut self.sysex {
+                    sysex.push(byte);
+                    return;
+                }
+                // Data without a status, e.g. after a system common message. Nothing to do.
+                let Some(status) = self.status else {
+                    return;
+                };
+                self.data.push(byte);
+                if self.data.len() == data_len(status) {
+                    self.finish_message(status);
+                }
+            }
+        }
+    }
+
+    fn start_message(&mut self, status: u8) {
+        self.data.clear();
+        match status {
+            0xF0 => {
+                self.status = None;
+                self.sysex = Some(vec![]);
+            }
+            0xF4 | 0xF5 => {
+                self.status = None;
+                self.events.push_back(MidiEvent::Undefined { status });
+            }
+            0xF6 => {
+                self.status = None;
+                self.events.push_back(MidiEvent::TuneRequest);
+            }
+            _ => self.status = Some(status),
+        }
+    }
+
+    fn finish_message(&mut self, status: u8) {
+        let mut bytes = vec![status];
+        bytes.append(&mut self.data);
+
+        // Message is complete and valid, so this can't fail.
+        if let Ok(event) = MidiEvent::read(&mut bytes.as_slice()) {
+            self.events.push_back(event);
+        }
+
+        // System common messages cancel running status.
+        if status >= 0xF0 {
+            self.status = None;
+        }
+    }
+
+    fn finish_sysex(&mut self, sysex: Vec<u8>) {
+        // A SysEx message without a manufacturer id is dropped.
+        if let Some((id, data)) = ManufacturerId::parse(&sysex) {
+            self.events.push_back(MidiEvent::SysEx {
+                id,
+                data: data.to_vec(),
+            });
+        }
+    }
+}
+
+/// Number of data bytes following a status byte.
+fn data_len(status: u8) -> usize {
+    match status & 0xF0 {
+        0xC0 | 0xD0 => 1,
+        0xF0 => match status {
+            0xF1 | 0xF3 => 1,
+            0xF2 => 2,
+            _ => 0,
+        },
+        _ => 2,
+    }
+}
+
+fn realtime_event(status: u8) -> MidiEvent {
+    match status {
+        0xF8 => MidiEvent::TimingClock,
+        0xFA => MidiEvent::Start,
+        0xFB => MidiEvent::Continue,
+        0xFC => MidiEvent::Stop,
+        0xFE => MidiEvent::ActiveSensing,
+        0xFF => MidiEvent::SystemReset,
+        _ => MidiEvent::Undefined { status },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::{channels::MidiChannel, keys::MidiKey};
+
+    #[test]
+    fn test_running_status() {
+        let mut parser = MidiStreamParser::new();
+        let events: Vec<_> = parser.feed(&[0x91, 0x3C, 0x40, 0x3E, 0x41]).collect();
+        assert_eq!(
+            events,
+            vec![
+                MidiEvent::NoteOn {
+                    channel: MidiChannel::Ch2,
+                    key: MidiKey::C5,
+                    vel: 0x40
+                },
+                MidiEvent::NoteOn {
+                    channel: MidiChannel::Ch2,
+                    key: MidiKey::D5,
+                    vel: 0x41
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_across_chunks() {
+        let mut parser = MidiStreamParser::new();
+        assert_eq!(parser.feed(&[0xE0, 0x00]).count(), 0);
+        let events: Vec<_> = parser.feed(&[0x40]).collect();
+        assert_eq!(
+            events,
+            vec![MidiEvent::PitchBend {
+                channel: MidiChannel::Ch1,
+                value: 0x2000
+            }]
+        );
+    }
+
+    #[test]
+    fn test_realtime_interleaved() {
+        let mut parser = MidiStreamParser::new();
+        let events: Vec<_> = parser
+            .feed(&[
+                0xB0, 0x07, 0xF8, 0x64, 0xF0, 0x7E, 0xFE, 0x7F, 0x09, 0x01, 0xF7,
+            ])
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                MidiEvent::TimingClock,
+                MidiEvent::ControlChange {
+                    channel: MidiChannel::Ch1,
+                    control: 0x07,
+                    value: 0x64
+                },
+                MidiEvent::ActiveSensing,
+                MidiEvent::SysEx {
+                    id: ManufacturerId::UNIVERSAL_NON_REALTIME,
+                    data: vec![0x7F, 0x09, 0x01]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sysex_ended_by_status() {
+        let mut parser = MidiStreamParser::new();
+        let events: Vec<_> = parser.feed(&[0xF0, 0x43, 0x10, 0xC0, 0x05]).collect();
+        assert_eq!(
+            events,
+            vec![
+                MidiEvent::SysEx {
+                    id: ManufacturerId::YAMAHA,
+                    data: vec![0x10]
+                },
+                MidiEvent::ProgramChange {
+                    channel: MidiChannel::Ch1,
+                    program: 0x05
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_system_common_cancels_running_status() {
+        let mut parser = MidiStreamParser::new();
+        let events: Vec<_> = parser
+            .feed(&[0x90, 0x3C, 0x40, 0xF3, 0x01, 0x3C, 0x40])
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1], MidiEvent::SongSelect { song: 0x01 });
+
+        let events: Vec<_> = parser.feed(&[0x90, 0x3C, 0x40, 0xF7, 0x3C, 0x40]).collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1], MidiEvent::EndOfExclusive);
+    }
+}
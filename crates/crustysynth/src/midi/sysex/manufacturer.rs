@@ -0,0 +1,217 @@
+//! SysEx manufacturer ids
+//!
+//! Most ids are a single byte. 0x00 introduces an extended id, which is followed by two more
+//! bytes.
+
+use std::{
+    fmt::{Debug, Display},
+    io,
+};
+
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ManufacturerId {
+    /// One byte id, 0x01-0x7F
+    Standard(u8),
+    /// Three byte id. The leading 0x00 is implied.
+    Extended(u8, u8),
+}
+
+impl ManufacturerId {
+    pub const SEQUENTIAL: Self = Self::Standard(0x01);
+    pub const MOOG: Self = Self::Standard(0x04);
+    pub const ENSONIQ: Self = Self::Standard(0x0F);
+    pub const KAWAI: Self = Self::Standard(0x40);
+    pub const ROLAND: Self = Self::Standard(0x41);
+    pub const KORG: Self = Self::Standard(0x42);
+    pub const YAMAHA: Self = Self::Standard(0x43);
+    pub const CASIO: Self = Self::Standard(0x44);
+    pub const AKAI: Self = Self::Standard(0x47);
+    pub const NON_COMMERCIAL: Self = Self::Standard(0x7D);
+    pub const UNIVERSAL_NON_REALTIME: Self = Self::Standard(0x7E);
+    pub const UNIVERSAL_REALTIME: Self = Self::Standard(0x7F);
+
+    /// Read an id from a buffer.
+    pub fn read<R>(reader: &mut R) -> Result<Self, io::Error>
+    where
+        R: io::Read,
+    {
+        let mut buf = [0_u8];
+        reader.read_exact(&mut buf)?;
+        if buf[0] != 0x00 {
+            return Ok(Self::Standard(buf[0] & 0x7F));
+        }
+        let mut buf = [0_u8; 2];
+        reader.read_exact(&mut buf)?;
+        Ok(Self::Extended(buf[0] & 0x7F, buf[1] & 0x7F))
+    }
+
+    /// Split an id from the start of `data`. Returns `None` if data is too short.
+    pub fn parse(data: &[u8]) -> Option<(Self, &[u8])> {
+        match data {
+            [0x00, b1, b2, rest @ ..] => Some((Self::Extended(*b1, *b2), rest)),
+            [0x00, ..] | [] => None,
+            [b0, rest @ ..] => Some((Self::Standard(*b0), rest)),
+        }
+    }
+
+    /// Id as it appears in a SysEx message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Standard(b0) => vec![*b0],
+            Self::Extended(b1, b2) => vec![0x00, *b1, *b2],
+        }
+    }
+
+    /// Manufacturer name, if the id is known.
+    pub fn name(&self) -> Option<&'static str> {
+        let name = match self {
+            Self::Standard(id) => match id {
+                0x01 => "Sequential Circuits",
+                0x02 => "Big Briar",
+                0x03 => "Octave-Plateau",
+                0x04 => "Moog",
+                0x05 => "Passport Designs",
+                0x06 => "Lexicon",
+                0x07 => "Kurzweil",
+                0x08 => "Fender",
+                0x09 => "Gulbransen",
+                0x0A => "AKG Acoustics",
+                0x0B => "Voyce Music",
+                0x0C => "Waveframe",
+                0x0D => "ADA Signal Processors",
+                0x0E => "Garfield Electronics",
+                0x0F => "Ensoniq",
+                0x10 => "Oberheim",
+                0x11 => "Apple",
+                0x12 => "Grey Matter Response",
+                0x13 => "Digidesign",
+                0x14 => "Palm Tree Instruments",
+                0x15 => "JLCooper Electronics",
+                0x16 => "Lowrey",
+                0x17 => "Adams-Smith",
+                0x18 => "E-mu",
+                0x19 => "Harmony Systems",
+                0x1A => "ART",
+                0x1B => "Baldwin",
+                0x1C => "Eventide",
+                0x1D => "Inventronics",
+                0x1F => "Clarity",
+                0x20 => "Passac",
+                0x21 => "SIEL",
+                0x22 => "Synthaxe",
+                0x24 => "Hohner",
+                0x25 => "Twister",
+                0x26 => "Solton",
+                0x27 => "Jellinghaus MS",
+                0x28 => "Southworth Music Systems",
+                0x29 => "PPG",
+                0x2A => "JEN",
+                0x2B => "Solid State Logic",
+                0x2C => "Audio Veritrieb",
+                0x2F => "Elka",
+                0x30 => "Dynacord",
+                0x31 => "Viscount",
+                0x33 => "Clavia",
+                0x34 => "Audio Architecture",
+                0x35 => "GeneralMusic",
+                0x39 => "Soundcraft",
+                0x3B => "Wersi",
+                0x3C => "Avab Elektronik",
+                0x3D => "Digigram",
+                0x3E => "Waldorf",
+                0x3F => "Quasimidi",
+                0x40 => "Kawai",
+                0x41 => "Roland",
+                0x42 => "Korg",
+                0x43 => "Yamaha",
+                0x44 => "Casio",
+                0x46 => "Kamiya Studio",
+                0x47 => "Akai",
+                0x48 => "Victor",
+                0x4B => "Fujitsu",
+                0x4C => "Sony",
+                0x4E => "Teac",
+                0x50 => "Matsushita",
+                0x51 => "Fostex",
+                0x52 => "Zoom",
+                0x7D => "Non-commercial",
+                0x7E => "Universal Non-Real Time",
+                0x7F => "Universal Real Time",
+                _ => return None,
+            },
+            Self::Extended(b1, b2) => match (b1, b2) {
+                (0x00, 0x0E) => "Alesis",
+                (0x00, 0x1B) => "Peavey",
+                (0x00, 0x3B) => "Mark of the Unicorn",
+                (0x00, 0x41) => "Microsoft",
+                (0x01, 0x05) => "M-Audio",
+                (0x20, 0x1F) => "TC Electronic",
+                (0x20, 0x29) => "Novation",
+                (0x20, 0x32) => "Behringer",
+                (0x20, 0x33) => "Access",
+                (0x20, 0x3C) => "Elektron",
+                (0x20, 0x6B) => "Arturia",
+                (0x20, 0x76) => "Teenage Engineering",
+                (0x21, 0x09) => "Native Instruments",
+                _ => return None,
+            },
+        };
+        Some(name)
+    }
+}
+
+impl Display for ManufacturerId {
+    /// Name if known, hex id otherwise.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.name(), self) {
+            (Some(name), _) => write!(f, "{name}"),
+            (None, Self::Standard(b0)) => write!(f, "{b0:#04x}"),
+            (None, Self::Extended(b1, b2)) => write!(f, "0x00 {b1:#04x} {b2:#04x}"),
+        }
+    }
+}
+
+impl Debug for ManufacturerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes = match self {
+            Self::Standard(b0) => format!("{b0:#04x}"),
+            Self::Extended(b1, b2) => format!("0x00 {b1:#04x} {b2:#04x}"),
+        };
+        match self.name() {
+            Some(name) => write!(f, "{name} ({bytes})"),
+            None => write!(f, "{bytes}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midifile::miditrack::midievent::MidiEvent;
+
+    #[test]
+    fn test_parse() {
+        let (id, rest) = ManufacturerId::parse(&[0x41, 0x10]).unwrap();
+        assert_eq!(id, ManufacturerId::ROLAND);
+        assert_eq!(rest, &[0x10]);
+
+        let (id, rest) = ManufacturerId::parse(&[0x00, 0x20, 0x33, 0x01]).unwrap();
+        assert_eq!(id, ManufacturerId::Extended(0x20, 0x33));
+        assert_eq!(id.name(), Some("Access"));
+        assert_eq!(rest, &[0x01]);
+
+        assert!(ManufacturerId::parse(&[0x00, 0x20]).is_none());
+    }
+
+    #[test]
+    fn test_sysex_event_extended_id() {
+        let bytes = [0xF0, 0x05, 0x00, 0x20, 0x33, 0x01, 0xF7];
+        let event = MidiEvent::read(&mut bytes.as_slice()).unwrap();
+        let MidiEvent::SysEx { id, data } = event else {
+            panic!("Unexpected event: {event:?}");
+        };
+        assert_eq!(id, ManufacturerId::Extended(0x20, 0x33));
+        assert_eq!(data, vec![0x01]);
+        assert_eq!(format!("{id:?}"), "Access (0x00 0x20 0x33)");
+    }
+}
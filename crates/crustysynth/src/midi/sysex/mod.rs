@@ -0,0 +1,291 @@
+//! System exclusive message decoding
+//!
+//! SysEx events carry manufacturer specific data. The decoders here recognise the messages
+//! commonly found in MIDI files and turn them into typed parameters.
+
+pub mod manufacturer;
+pub mod roland;
+pub mod universal;
+pub mod yamaha;
+
+use std::{error::Error, fmt::Display};
+
+use manufacturer::ManufacturerId;
+use roland::{GsParameter, RolandDt1};
+use universal::{UniversalNonRealTime, UniversalRealTime};
+use yamaha::{XgParameter, YamahaParameterChange};
+
+#[derive(Debug)]
+pub enum SysExError {
+    Truncated,
+    ChecksumMismatch { expected: u8, actual: u8 },
+}
+impl Error for SysExError {}
+impl Display for SysExError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "SysEx message is too short."),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "SysEx checksum mismatch. Expected {expected:#04x}, but got {actual:#04x}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SysExMessage {
+    UniversalNonRealTime {
+        device_id: u8,
+        message: UniversalNonRealTime,
+    },
+    UniversalRealTime {
+        device_id: u8,
+        message: UniversalRealTime,
+    },
+    /// Roland GS data set (DT1)
+    RolandGs {
+        device_id: u8,
+        parameters: Vec<GsParameter>,
+    },
+    /// Yamaha XG parameter change
+    YamahaXg {
+        device_id: u8,
+        parameters: Vec<XgParameter>,
+    },
+    /// Valid, but not a message we know how to decode.
+    Other,
+}
+
+impl SysExMessage {
+    /// Decode SysEx event contents. `data` is everything after the manufacturer id. A trailing
+    /// End of Exclusive byte is allowed.
+    pub fn decode(id: ManufacturerId, data: &[u8]) -> Result<Self, SysExError> {
+        let data = strip_eox(data);
+
+        match id {
+            ManufacturerId::UNIVERSAL_NON_REALTIME => {
+                let (device_id, message) = universal::parse_non_realtime(data)?;
+                Ok(Self::UniversalNonRealTime { device_id, message })
+            }
+            ManufacturerId::UNIVERSAL_REALTIME => {
+                let (device_id, message) = universal::parse_realtime(data)?;
+                Ok(Self::UniversalRealTime { device_id, message })
+            }
+            ManufacturerId::ROLAND => {
+                let Some(dt1) = RolandDt1::parse(data)? else {
+                    return Ok(Self::Other);
+                };
+                if dt1.model_id != roland::GS_MODEL_ID {
+                    return Ok(Self::Other);
+                }
+                Ok(Self::RolandGs {
+                    device_id: dt1.device_id,
+                    parameters: roland::decode_gs(dt1.address, &dt1.data),
+                })
+            }
+            ManufacturerId::YAMAHA => {
+                let Some(change) = YamahaParameterChange::parse(data)? else {
+                    return Ok(Self::Other);
+                };
+                if change.model_id != yamaha::XG_MODEL_ID {
+                    return Ok(Self::Other);
+                }
+                Ok(Self::YamahaXg {
+                    device_id: change.device_id,
+                    parameters: yamaha::decode_xg(change.address, &change.data),
+                })
+            }
+            _ => Ok(Self::Other),
+        }
+    }
+}
+
+fn strip_eox(data: &[u8]) -> &[u8] {
+    match data.split_last() {
+        Some((0xF7, rest)) => rest,
+        _ => data,
+    }
+}
+
+/// Convert a 3-byte address of 7-bit bytes to a linear offset.
+fn address_to_linear(address: u32) -> u32 {
+    ((address >> 16) & 0x7F) << 14 | ((address >> 8) & 0x7F) << 7 | (address & 0x7F)
+}
+
+/// Convert a linear offset back to a 3-byte address of 7-bit bytes.
+fn linear_to_address(linear: u32) -> u32 {
+    ((linear >> 14) & 0x7F) << 16 | ((linear >> 7) & 0x7F) << 8 | (linear & 0x7F)
+}
+
+/// Advance a 3-byte address of 7-bit bytes. Carries over into the next byte at 0x80.
+fn advance_address(address: u32, count: usize) -> u32 {
+    linear_to_address(address_to_linear(address) + count as u32)
+}
+
+/// Combine nibbles (lowest 4 bits of each byte) into a value, most significant first.
+fn nibbles_to_u16(nibbles: &[u8]) -> u16 {
+    nibbles
+        .iter()
+        .fold(0, |acc, nibble| (acc << 4) | (*nibble & 0x0F) as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::keys::MidiKey;
+    use roland::{GsPartParameter, GsRhythmMode};
+    use universal::{MmcCommand, NoteTuning};
+    use yamaha::{XgPartMode, XgPartParameter};
+
+    #[test]
+    fn test_gm_system_on() {
+        let data = [0x7F, 0x09, 0x01, 0xF7];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x7E), &data).unwrap();
+        assert_eq!(
+            message,
+            SysExMessage::UniversalNonRealTime {
+                device_id: 0x7F,
+                message: UniversalNonRealTime::GmSystemOn
+            }
+        );
+    }
+
+    #[test]
+    fn test_master_volume() {
+        let data = [0x7F, 0x04, 0x01, 0x7F, 0x7F, 0xF7];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x7F), &data).unwrap();
+        assert_eq!(
+            message,
+            SysExMessage::UniversalRealTime {
+                device_id: 0x7F,
+                message: UniversalRealTime::MasterVolume(0x3FFF)
+            }
+        );
+    }
+
+    #[test]
+    fn test_single_note_tuning() {
+        // Retune key 69 to 69 + half a semitone; key 70 unchanged.
+        let data = [
+            0x7F, 0x08, 0x02, 0x00, 0x02, 0x45, 0x45, 0x40, 0x00, 0x46, 0x7F, 0x7F, 0x7F, 0xF7,
+        ];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x7F), &data).unwrap();
+        let SysExMessage::UniversalRealTime {
+            message: UniversalRealTime::SingleNoteTuning { changes, .. },
+            ..
+        } = message
+        else {
+            panic!("Unexpected message: {message:?}");
+        };
+        let tuning = NoteTuning {
+            semitone: 0x45,
+            fraction: 0x2000,
+        };
+        assert_eq!(
+            changes,
+            vec![(MidiKey::A5, Some(tuning)), (MidiKey::AS5, None)]
+        );
+        assert!((tuning.frequency() - 452.893).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_mmc() {
+        let data = [0x7F, 0x06, 0x02, 0xF7];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x7F), &data).unwrap();
+        assert_eq!(
+            message,
+            SysExMessage::UniversalRealTime {
+                device_id: 0x7F,
+                message: UniversalRealTime::MachineControl(MmcCommand::Play)
+            }
+        );
+    }
+
+    #[test]
+    fn test_gs_reset() {
+        let data = [0x10, 0x42, 0x12, 0x40, 0x00, 0x7F, 0x00, 0x41, 0xF7];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x41), &data).unwrap();
+        assert_eq!(
+            message,
+            SysExMessage::RolandGs {
+                device_id: 0x10,
+                parameters: vec![GsParameter::Reset]
+            }
+        );
+    }
+
+    #[test]
+    fn test_gs_bad_checksum() {
+        let data = [0x10, 0x42, 0x12, 0x40, 0x00, 0x7F, 0x00, 0x40, 0xF7];
+        assert!(matches!(
+            SysExMessage::decode(ManufacturerId::Standard(0x41), &data),
+            Err(SysExError::ChecksumMismatch {
+                expected: 0x41,
+                actual: 0x40
+            })
+        ));
+    }
+
+    #[test]
+    fn test_gs_use_for_rhythm_part() {
+        // Part 11 (block 0xA) to drum map 1
+        let data = [0x10, 0x42, 0x12, 0x40, 0x1A, 0x15, 0x01, 0x10, 0xF7];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x41), &data).unwrap();
+        assert_eq!(
+            message,
+            SysExMessage::RolandGs {
+                device_id: 0x10,
+                parameters: vec![GsParameter::Part {
+                    part: 10,
+                    parameter: GsPartParameter::UseForRhythmPart(GsRhythmMode::Map1)
+                }]
+            }
+        );
+    }
+
+    #[test]
+    fn test_xg_system_on() {
+        let data = [0x10, 0x4C, 0x00, 0x00, 0x7E, 0x00, 0xF7];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x43), &data).unwrap();
+        assert_eq!(
+            message,
+            SysExMessage::YamahaXg {
+                device_id: 0,
+                parameters: vec![XgParameter::SystemOn]
+            }
+        );
+    }
+
+    #[test]
+    fn test_xg_part_mode_and_reverb() {
+        let data = [0x10, 0x4C, 0x08, 0x03, 0x07, 0x02, 0xF7];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x43), &data).unwrap();
+        assert_eq!(
+            message,
+            SysExMessage::YamahaXg {
+                device_id: 0,
+                parameters: vec![XgParameter::Part {
+                    part: 3,
+                    parameter: XgPartParameter::PartMode(XgPartMode::DrumS1)
+                }]
+            }
+        );
+
+        let data = [0x10, 0x4C, 0x02, 0x01, 0x00, 0x01, 0x10, 0xF7];
+        let message = SysExMessage::decode(ManufacturerId::Standard(0x43), &data).unwrap();
+        assert_eq!(
+            message,
+            SysExMessage::YamahaXg {
+                device_id: 0,
+                parameters: vec![XgParameter::ReverbType { msb: 1, lsb: 0x10 }]
+            }
+        );
+    }
+
+    #[test]
+    fn test_address_carry() {
+        assert_eq!(advance_address(0x40_00_7F, 1), 0x40_01_00);
+        assert_eq!(advance_address(0x40_7F_7F, 2), 0x41_00_01);
+    }
+}
@@ -0,0 +1,252 @@
+//! Roland SysEx: Data set (DT1) messages and GS parameters
+//!
+//! DT1 layout after the manufacturer id:
+//! `device id, model id, 0x12, address (3 bytes), data..., checksum`
+
+use super::{advance_address, nibbles_to_u16, SysExError};
+
+pub const GS_MODEL_ID: u8 = 0x42;
+const COMMAND_DT1: u8 = 0x12;
+
+/// Roland data set message.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RolandDt1 {
+    pub device_id: u8,
+    pub model_id: u8,
+    /// Three 7-bit address bytes, packed as `0x00hhmmll`.
+    pub address: u32,
+    pub data: Vec<u8>,
+}
+
+impl RolandDt1 {
+    /// Parse a DT1 message. Returns `None` for other Roland commands.
+    /// `data` is everything after the manufacturer id, without End of Exclusive.
+    pub fn parse(data: &[u8]) -> Result<Option<Self>, SysExError> {
+        if data.len() < 3 {
+            return Err(SysExError::Truncated);
+        }
+        if data[2] != COMMAND_DT1 {
+            return Ok(None);
+        }
+        // Address, at least one data byte and the checksum.
+        if data.len() < 3 + 3 + 1 + 1 {
+            return Err(SysExError::Truncated);
+        }
+
+        let (body, checksum_byte) = data[3..].split_at(data.len() - 4);
+        let expected = checksum(body);
+        if expected != checksum_byte[0] {
+            return Err(SysExError::ChecksumMismatch {
+                expected,
+                actual: checksum_byte[0],
+            });
+        }
+
+        Ok(Some(Self {
+            device_id: data[0],
+            model_id: data[1],
+            address: (body[0] as u32) << 16 | (body[1] as u32) << 8 | body[2] as u32,
+            data: body[3..].to_vec(),
+        }))
+    }
+}
+
+/// Roland checksum over address and data bytes. The sum of all bytes including the checksum is
+/// a multiple of 128.
+pub fn checksum(bytes: &[u8]) -> u8 {
+    let sum = bytes.iter().fold(0_u8, |acc, b| acc.wrapping_add(*b)) & 0x7F;
+    (0x80 - sum) & 0x7F
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GsRhythmMode {
+    Off,
+    Map1,
+    Map2,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GsParameter {
+    /// GS Reset
+    Reset,
+    /// SC-88 system mode set. 0 = Mode 1 (double module), 1 = Mode 2 (single module).
+    SystemMode(u8),
+    /// Master tune in 0.1 cent units, relative to A440.
+    MasterTune(i16),
+    MasterVolume(u8),
+    /// Master key shift in semitones.
+    MasterKeyShift(i8),
+    MasterPan(u8),
+    ReverbMacro(u8),
+    ReverbCharacter(u8),
+    ReverbPreLpf(u8),
+    ReverbLevel(u8),
+    ReverbTime(u8),
+    ReverbDelayFeedback(u8),
+    ChorusMacro(u8),
+    ChorusPreLpf(u8),
+    ChorusLevel(u8),
+    ChorusFeedback(u8),
+    ChorusDelay(u8),
+    ChorusRate(u8),
+    ChorusDepth(u8),
+    ChorusSendToReverb(u8),
+    /// SC-88Pro insertion effect type.
+    EfxType {
+        msb: u8,
+        lsb: u8,
+    },
+    /// Part parameter. Part is zero-based: part 0 receives channel 1 by default.
+    Part {
+        part: u8,
+        parameter: GsPartParameter,
+    },
+    Unknown {
+        address: u32,
+        value: u8,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GsPartParameter {
+    ToneNumber {
+        bank: u8,
+        program: u8,
+    },
+    /// Receive channel. 0-15, or 16 for off.
+    RxChannel(u8),
+    /// false = mono, true = poly
+    Poly(bool),
+    UseForRhythmPart(GsRhythmMode),
+    /// Pitch key shift in semitones.
+    PitchKeyShift(i8),
+    PartLevel(u8),
+    VelocitySenseDepth(u8),
+    VelocitySenseOffset(u8),
+    /// Part pan. 0 = random, 0x40 = center.
+    PartPan(u8),
+    KeyRangeLow(u8),
+    KeyRangeHigh(u8),
+    ChorusSendLevel(u8),
+    ReverbSendLevel(u8),
+    VibratoRate(u8),
+    VibratoDepth(u8),
+    VibratoDelay(u8),
+    TvfCutoff(u8),
+    TvfResonance(u8),
+    EnvelopeAttack(u8),
+    EnvelopeDecay(u8),
+    EnvelopeRelease(u8),
+    Unknown {
+        offset: u8,
+        value: u8,
+    },
+}
+
+/// Decode GS parameters written by a DT1 message. A message may write several consecutive
+/// addresses, so this can return more than one parameter.
+pub fn decode_gs(mut address: u32, mut data: &[u8]) -> Vec<GsParameter> {
+    let mut parameters = vec![];
+    while !data.is_empty() {
+        let (parameter, len) = decode_gs_parameter(address, data);
+        parameters.push(parameter);
+        address = advance_address(address, len);
+        data = &data[len..];
+    }
+    parameters
+}
+
+/// Decode one parameter at the start of `data`. Returns the parameter and how many bytes it used.
+fn decode_gs_parameter(address: u32, data: &[u8]) -> (GsParameter, usize) {
+    let value = data[0];
+    let [_, hi, mid, lo] = address.to_be_bytes();
+
+    let parameter = match (hi, mid, lo) {
+        (0x40, 0x00, 0x7F) => GsParameter::Reset,
+        (0x00, 0x00, 0x7F) => GsParameter::SystemMode(value),
+        (0x40, 0x00, 0x00) if data.len() >= 4 => {
+            let tune = nibbles_to_u16(&data[..4]) as i16 - 0x400;
+            return (GsParameter::MasterTune(tune), 4);
+        }
+        (0x40, 0x00, 0x04) => GsParameter::MasterVolume(value),
+        (0x40, 0x00, 0x05) => GsParameter::MasterKeyShift(value as i8 - 0x40),
+        (0x40, 0x00, 0x06) => GsParameter::MasterPan(value),
+        (0x40, 0x01, 0x30) => GsParameter::ReverbMacro(value),
+        (0x40, 0x01, 0x31) => GsParameter::ReverbCharacter(value),
+        (0x40, 0x01, 0x32) => GsParameter::ReverbPreLpf(value),
+        (0x40, 0x01, 0x33) => GsParameter::ReverbLevel(value),
+        (0x40, 0x01, 0x34) => GsParameter::ReverbTime(value),
+        (0x40, 0x01, 0x35) => GsParameter::ReverbDelayFeedback(value),
+        (0x40, 0x01, 0x38) => GsParameter::ChorusMacro(value),
+        (0x40, 0x01, 0x39) => GsParameter::ChorusPreLpf(value),
+        (0x40, 0x01, 0x3A) => GsParameter::ChorusLevel(value),
+        (0x40, 0x01, 0x3B) => GsParameter::ChorusFeedback(value),
+        (0x40, 0x01, 0x3C) => GsParameter::ChorusDelay(value),
+        (0x40, 0x01, 0x3D) => GsParameter::ChorusRate(value),
+        (0x40, 0x01, 0x3E) => GsParameter::ChorusDepth(value),
+        (0x40, 0x01, 0x3F) => GsParameter::ChorusSendToReverb(value),
+        (0x40, 0x03, 0x00) if data.len() >= 2 => {
+            let parameter = GsParameter::EfxType {
+                msb: data[0],
+                lsb: data[1],
+            };
+            return (parameter, 2);
+        }
+        (0x40, 0x10..=0x1F, _) => {
+            let part = block_to_part(mid & 0x0F);
+            let (parameter, len) = decode_part_parameter(lo, data);
+            return (GsParameter::Part { part, parameter }, len);
+        }
+        _ => GsParameter::Unknown { address, value },
+    };
+    (parameter, 1)
+}
+
+fn decode_part_parameter(offset: u8, data: &[u8]) -> (GsPartParameter, usize) {
+    let value = data[0];
+
+    let parameter = match offset {
+        0x00 if data.len() >= 2 => {
+            let parameter = GsPartParameter::ToneNumber {
+                bank: data[0],
+                program: data[1],
+            };
+            return (parameter, 2);
+        }
+        0x02 => GsPartParameter::RxChannel(value),
+        0x13 => GsPartParameter::Poly(value != 0),
+        0x15 => match value {
+            1 => GsPartParameter::UseForRhythmPart(GsRhythmMode::Map1),
+            2 => GsPartParameter::UseForRhythmPart(GsRhythmMode::Map2),
+            _ => GsPartParameter::UseForRhythmPart(GsRhythmMode::Off),
+        },
+        0x16 => GsPartParameter::PitchKeyShift(value as i8 - 0x40),
+        0x19 => GsPartParameter::PartLevel(value),
+        0x1A => GsPartParameter::VelocitySenseDepth(value),
+        0x1B => GsPartParameter::VelocitySenseOffset(value),
+        0x1C => GsPartParameter::PartPan(value),
+        0x1D => GsPartParameter::KeyRangeLow(value),
+        0x1E => GsPartParameter::KeyRangeHigh(value),
+        0x21 => GsPartParameter::ChorusSendLevel(value),
+        0x22 => GsPartParameter::ReverbSendLevel(value),
+        0x30 => GsPartParameter::VibratoRate(value),
+        0x31 => GsPartParameter::VibratoDepth(value),
+        0x32 => GsPartParameter::TvfCutoff(value),
+        0x33 => GsPartParameter::TvfResonance(value),
+        0x34 => GsPartParameter::EnvelopeAttack(value),
+        0x35 => GsPartParameter::EnvelopeDecay(value),
+        0x36 => GsPartParameter::EnvelopeRelease(value),
+        0x37 => GsPartParameter::VibratoDelay(value),
+        _ => GsPartParameter::Unknown { offset, value },
+    };
+    (parameter, 1)
+}
+
+/// GS part blocks are ordered 10, 1-9, 11-16.
+fn block_to_part(block: u8) -> u8 {
+    match block {
+        0x0 => 9,
+        0x1..=0x9 => block - 1,
+        _ => block,
+    }
+}
@@ -0,0 +1,336 @@
+//! Universal SysEx: Non-realtime (0x7E) and realtime (0x7F) messages
+//!
+//! Layout after the id: `device id, sub-id #1, sub-id #2, data...`
+//! Device id 0x7F is "all call".
+
+use super::{manufacturer::ManufacturerId, SysExError};
+use crate::midi::{keys::MidiKey, timecode::SmpteTimecode};
+
+const NON_REALTIME_ID: u8 = 0x7E;
+
+/// MIDI Tuning Standard frequency: a semitone plus a 14-bit fraction of the next semitone.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NoteTuning {
+    pub semitone: u8,
+    /// Units of 100/16384 cents
+    pub fraction: u16,
+}
+
+impl NoteTuning {
+    /// Parse 3 bytes of frequency data. Returns `None` for 7F 7F 7F, which means "no change".
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data[..3] == [0x7F, 0x7F, 0x7F] {
+            return None;
+        }
+        Some(Self {
+            semitone: data[0] & 0x7F,
+            fraction: ((data[1] & 0x7F) as u16) << 7 | (data[2] & 0x7F) as u16,
+        })
+    }
+
+    /// Frequency in Hz, with key 69 (A) at 440 Hz.
+    pub fn frequency(&self) -> f64 {
+        let semitones = self.semitone as f64 + self.fraction as f64 / 16384.0;
+        440.0 * 2_f64.powf((semitones - 69.0) / 12.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum UniversalNonRealTime {
+    GmSystemOn,
+    GmSystemOff,
+    Gm2SystemOn,
+    DeviceInquiryRequest,
+    DeviceInquiryReply {
+        manufacturer: ManufacturerId,
+        family: u16,
+        member: u16,
+        version: [u8; 4],
+    },
+    TuningBulkDumpRequest {
+        program: u8,
+    },
+    /// Tuning for every key. `None` means the key is not retuned.
+    TuningBulkDump {
+        bank: Option<u8>,
+        program: u8,
+        name: String,
+        tunings: Vec<Option<NoteTuning>>,
+    },
+    SingleNoteTuning {
+        bank: u8,
+        program: u8,
+        changes: Vec<(MidiKey, Option<NoteTuning>)>,
+    },
+    Other {
+        sub_id1: u8,
+        sub_id2: u8,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum UniversalRealTime {
+    /// MTC full frame, sent when jumping to a new position.
+    MtcFullFrame(SmpteTimecode),
+    /// 14-bit volume
+    MasterVolume(u16),
+    /// 14-bit balance. 0x2000 is center.
+    MasterBalance(u16),
+    /// Fine tuning in units of 100/8192 cents, relative to A440.
+    MasterFineTuning(i16),
+    /// Coarse tuning in semitones.
+    MasterCoarseTuning(i8),
+    SingleNoteTuning {
+        bank: Option<u8>,
+        program: u8,
+        changes: Vec<(MidiKey, Option<NoteTuning>)>,
+    },
+    MachineControl(MmcCommand),
+    Other {
+        sub_id1: u8,
+        sub_id2: u8,
+    },
+}
+
+/// MIDI Machine Control command
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MmcCommand {
+    Stop,
+    Play,
+    DeferredPlay,
+    FastForward,
+    Rewind,
+    RecordStrobe,
+    RecordExit,
+    RecordPause,
+    Pause,
+    Eject,
+    Chase,
+    CommandErrorReset,
+    Reset,
+    Locate {
+        hours: u8,
+        minutes: u8,
+        seconds: u8,
+        frames: u8,
+        subframes: u8,
+    },
+    Other {
+        command: u8,
+        data: Vec<u8>,
+    },
+}
+
+/// Parse a non-realtime message. Returns device id and message.
+/// `data` is everything after the id, without End of Exclusive.
+pub fn parse_non_realtime(data: &[u8]) -> Result<(u8, UniversalNonRealTime), SysExError> {
+    let (device_id, sub_id1, sub_id2, body) = split_header(data)?;
+
+    let message = match (sub_id1, sub_id2) {
+        (0x09, 0x01) => UniversalNonRealTime::GmSystemOn,
+        (0x09, 0x02) => UniversalNonRealTime::GmSystemOff,
+        (0x09, 0x03) => UniversalNonRealTime::Gm2SystemOn,
+        (0x06, 0x01) => UniversalNonRealTime::DeviceInquiryRequest,
+        (0x06, 0x02) => {
+            let Some((manufacturer, rest)) = ManufacturerId::parse(body) else {
+                return Err(SysExError::Truncated);
+            };
+            if rest.len() < 8 {
+                return Err(SysExError::Truncated);
+            }
+            UniversalNonRealTime::DeviceInquiryReply {
+                manufacturer,
+                family: u14_le(rest[0], rest[1]),
+                member: u14_le(rest[2], rest[3]),
+                version: rest[4..8].try_into().unwrap(),
+            }
+        }
+        (0x08, 0x00) => {
+            let program = *body.first().ok_or(SysExError::Truncated)?;
+            UniversalNonRealTime::TuningBulkDumpRequest { program }
+        }
+        (0x08, 0x01) => {
+            // program, name, 128 tunings, checksum
+            if body.len() < 1 + 16 + 128 * 3 + 1 {
+                return Err(SysExError::Truncated);
+            }
+            validate_checksum(NON_REALTIME_ID, data, 1 + 16 + 128 * 3)?;
+            UniversalNonRealTime::TuningBulkDump {
+                bank: None,
+                program: body[0],
+                name: parse_name(&body[1..17]),
+                tunings: body[17..17 + 128 * 3]
+                    .chunks_exact(3)
+                    .map(NoteTuning::parse)
+                    .collect(),
+            }
+        }
+        (0x08, 0x04) => {
+            // bank, program, name, 128 tunings, checksum
+            if body.len() < 2 + 16 + 128 * 3 + 1 {
+                return Err(SysExError::Truncated);
+            }
+            validate_checksum(NON_REALTIME_ID, data, 2 + 16 + 128 * 3)?;
+            UniversalNonRealTime::TuningBulkDump {
+                bank: Some(body[0]),
+                program: body[1],
+                name: parse_name(&body[2..18]),
+                tunings: body[18..18 + 128 * 3]
+                    .chunks_exact(3)
+                    .map(NoteTuning::parse)
+                    .collect(),
+            }
+        }
+        (0x08, 0x07) => {
+            if body.len() < 3 {
+                return Err(SysExError::Truncated);
+            }
+            UniversalNonRealTime::SingleNoteTuning {
+                bank: body[0],
+                program: body[1],
+                changes: parse_note_changes(body[2], &body[3..])?,
+            }
+        }
+        _ => UniversalNonRealTime::Other { sub_id1, sub_id2 },
+    };
+
+    Ok((device_id, message))
+}
+
+/// Parse a realtime message. Returns device id and message.
+/// `data` is everything after the id, without End of Exclusive.
+pub fn parse_realtime(data: &[u8]) -> Result<(u8, UniversalRealTime), SysExError> {
+    let (device_id, sub_id1, sub_id2, body) = split_header(data)?;
+
+    let message = match (sub_id1, sub_id2) {
+        (0x01, 0x01) => {
+            if body.len() < 4 {
+                return Err(SysExError::Truncated);
+            }
+            UniversalRealTime::MtcFullFrame(SmpteTimecode::from_full_frame(
+                body[..4].try_into().unwrap(),
+            ))
+        }
+        (0x04, 0x01..=0x04) => {
+            if body.len() < 2 {
+                return Err(SysExError::Truncated);
+            }
+            let value = u14_le(body[0], body[1]);
+            match sub_id2 {
+                0x01 => UniversalRealTime::MasterVolume(value),
+                0x02 => UniversalRealTime::MasterBalance(value),
+                0x03 => UniversalRealTime::MasterFineTuning(value as i16 - 0x2000),
+                _ => UniversalRealTime::MasterCoarseTuning((body[1] & 0x7F) as i8 - 0x40),
+            }
+        }
+        (0x08, 0x02) => {
+            if body.len() < 2 {
+                return Err(SysExError::Truncated);
+            }
+            UniversalRealTime::SingleNoteTuning {
+                bank: None,
+                program: body[0],
+                changes: parse_note_changes(body[1], &body[2..])?,
+            }
+        }
+        (0x08, 0x07) => {
+            if body.len() < 3 {
+                return Err(SysExError::Truncated);
+            }
+            UniversalRealTime::SingleNoteTuning {
+                bank: Some(body[0]),
+                program: body[1],
+                changes: parse_note_changes(body[2], &body[3..])?,
+            }
+        }
+        (0x06, _) => UniversalRealTime::MachineControl(parse_mmc(sub_id2, body)?),
+        _ => UniversalRealTime::Other { sub_id1, sub_id2 },
+    };
+
+    Ok((device_id, message))
+}
+
+fn parse_mmc(command: u8, data: &[u8]) -> Result<MmcCommand, SysExError> {
+    let command = match command {
+        0x01 => MmcCommand::Stop,
+        0x02 => MmcCommand::Play,
+        0x03 => MmcCommand::DeferredPlay,
+        0x04 => MmcCommand::FastForward,
+        0x05 => MmcCommand::Rewind,
+        0x06 => MmcCommand::RecordStrobe,
+        0x07 => MmcCommand::RecordExit,
+        0x08 => MmcCommand::RecordPause,
+        0x09 => MmcCommand::Pause,
+        0x0A => MmcCommand::Eject,
+        0x0B => MmcCommand::Chase,
+        0x0C => MmcCommand::CommandErrorReset,
+        0x0D => MmcCommand::Reset,
+        // Locate target: count (6), sub-command 01, hr mn sc fr ff
+        0x44 if data.get(1) == Some(&0x01) => {
+            if data.len() < 7 {
+                return Err(SysExError::Truncated);
+            }
+            MmcCommand::Locate {
+                // Upper bits of the hour byte carry the frame rate.
+                hours: data[2] & 0x1F,
+                minutes: data[3],
+                seconds: data[4],
+                frames: data[5],
+                subframes: data[6],
+            }
+        }
+        _ => MmcCommand::Other {
+            command,
+            data: data.to_vec(),
+        },
+    };
+    Ok(command)
+}
+
+fn split_header(data: &[u8]) -> Result<(u8, u8, u8, &[u8]), SysExError> {
+    match data {
+        [device_id, sub_id1, sub_id2, body @ ..] => Ok((*device_id, *sub_id1, *sub_id2, body)),
+        _ => Err(SysExError::Truncated),
+    }
+}
+
+fn parse_note_changes(
+    count: u8,
+    data: &[u8],
+) -> Result<Vec<(MidiKey, Option<NoteTuning>)>, SysExError> {
+    let count = count as usize;
+    if data.len() < count * 4 {
+        return Err(SysExError::Truncated);
+    }
+    Ok(data[..count * 4]
+        .chunks_exact(4)
+        .map(|change| {
+            let key = MidiKey::try_from(change[0] & 0x7F).unwrap();
+            (key, NoteTuning::parse(&change[1..]))
+        })
+        .collect())
+}
+
+/// MTS dumps end with an XOR checksum of everything from the id to the last data byte.
+fn validate_checksum(id: u8, data: &[u8], body_len: usize) -> Result<(), SysExError> {
+    let header_len = 3;
+    let checked = &data[..header_len + body_len];
+    let expected = checked.iter().fold(id, |acc, b| acc ^ b) & 0x7F;
+    let actual = data[header_len + body_len];
+    if expected != actual {
+        return Err(SysExError::ChecksumMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+fn parse_name(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| (b & 0x7F) as char)
+        .collect::<String>()
+        .trim_end()
+        .to_owned()
+}
+
+fn u14_le(lsb: u8, msb: u8) -> u16 {
+    ((msb & 0x7F) as u16) << 7 | (lsb & 0x7F) as u16
+}
@@ -0,0 +1,215 @@
+//! Yamaha SysEx: Parameter change messages and XG parameters
+//!
+//! Parameter change layout after the manufacturer id:
+//! `0x1n (n = device number), model id, address (3 bytes), data...`
+
+use super::{advance_address, nibbles_to_u16, SysExError};
+
+pub const XG_MODEL_ID: u8 = 0x4C;
+const PARAMETER_CHANGE: u8 = 0x10;
+
+/// Yamaha parameter change message.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct YamahaParameterChange {
+    pub device_id: u8,
+    pub model_id: u8,
+    /// Three 7-bit address bytes, packed as `0x00hhmmll`.
+    pub address: u32,
+    pub data: Vec<u8>,
+}
+
+impl YamahaParameterChange {
+    /// Parse a parameter change message. Returns `None` for other message types, such as bulk
+    /// dumps. `data` is everything after the manufacturer id, without End of Exclusive.
+    pub fn parse(data: &[u8]) -> Result<Option<Self>, SysExError> {
+        if data.is_empty() {
+            return Err(SysExError::Truncated);
+        }
+        if data[0] & 0xF0 != PARAMETER_CHANGE {
+            return Ok(None);
+        }
+        // Model, address and at least one data byte.
+        if data.len() < 1 + 1 + 3 + 1 {
+            return Err(SysExError::Truncated);
+        }
+
+        Ok(Some(Self {
+            device_id: data[0] & 0x0F,
+            model_id: data[1],
+            address: (data[2] as u32) << 16 | (data[3] as u32) << 8 | data[4] as u32,
+            data: data[5..].to_vec(),
+        }))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum XgPartMode {
+    Normal,
+    Drum,
+    DrumS1,
+    DrumS2,
+    DrumS3,
+    DrumS4,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum XgParameter {
+    /// XG System On
+    SystemOn,
+    AllParameterReset,
+    /// Master tune in 0.1 cent units, relative to A440.
+    MasterTune(i16),
+    MasterVolume(u8),
+    MasterAttenuator(u8),
+    /// Transpose in semitones.
+    Transpose(i8),
+    ReverbType {
+        msb: u8,
+        lsb: u8,
+    },
+    ReverbReturn(u8),
+    ChorusType {
+        msb: u8,
+        lsb: u8,
+    },
+    ChorusReturn(u8),
+    VariationType {
+        msb: u8,
+        lsb: u8,
+    },
+    /// Multi part parameter. Part is zero-based: part 0 receives channel 1 by default.
+    Part {
+        part: u8,
+        parameter: XgPartParameter,
+    },
+    Unknown {
+        address: u32,
+        value: u8,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum XgPartParameter {
+    BankSelectMsb(u8),
+    BankSelectLsb(u8),
+    ProgramNumber(u8),
+    /// Receive channel. 0-15, or 0x7F for off.
+    RxChannel(u8),
+    /// false = mono, true = poly
+    Poly(bool),
+    PartMode(XgPartMode),
+    /// Note shift in semitones.
+    NoteShift(i8),
+    Volume(u8),
+    /// Pan. 0 = random, 0x40 = center.
+    Pan(u8),
+    DryLevel(u8),
+    ChorusSend(u8),
+    ReverbSend(u8),
+    VariationSend(u8),
+    VibratoRate(u8),
+    VibratoDepth(u8),
+    VibratoDelay(u8),
+    FilterCutoff(u8),
+    FilterResonance(u8),
+    EgAttack(u8),
+    EgDecay(u8),
+    EgRelease(u8),
+    Unknown {
+        offset: u8,
+        value: u8,
+    },
+}
+
+/// Decode XG parameters written by a parameter change message.
+pub fn decode_xg(mut address: u32, mut data: &[u8]) -> Vec<XgParameter> {
+    let mut parameters = vec![];
+    while !data.is_empty() {
+        let (parameter, len) = decode_xg_parameter(address, data);
+        parameters.push(parameter);
+        address = advance_address(address, len);
+        data = &data[len..];
+    }
+    parameters
+}
+
+/// Decode one parameter at the start of `data`. Returns the parameter and how many bytes it used.
+fn decode_xg_parameter(address: u32, data: &[u8]) -> (XgParameter, usize) {
+    let value = data[0];
+    let [_, hi, mid, lo] = address.to_be_bytes();
+
+    let parameter = match (hi, mid, lo) {
+        (0x00, 0x00, 0x7E) => XgParameter::SystemOn,
+        (0x00, 0x00, 0x7F) => XgParameter::AllParameterReset,
+        (0x00, 0x00, 0x00) if data.len() >= 4 => {
+            let tune = nibbles_to_u16(&data[..4]) as i16 - 0x400;
+            return (XgParameter::MasterTune(tune), 4);
+        }
+        (0x00, 0x00, 0x04) => XgParameter::MasterVolume(value),
+        (0x00, 0x00, 0x05) => XgParameter::MasterAttenuator(value),
+        (0x00, 0x00, 0x06) => XgParameter::Transpose(value as i8 - 0x40),
+        (0x02, 0x01, 0x00) if data.len() >= 2 => {
+            let parameter = XgParameter::ReverbType {
+                msb: data[0],
+                lsb: data[1],
+            };
+            return (parameter, 2);
+        }
+        (0x02, 0x01, 0x0C) => XgParameter::ReverbReturn(value),
+        (0x02, 0x01, 0x20) if data.len() >= 2 => {
+            let parameter = XgParameter::ChorusType {
+                msb: data[0],
+                lsb: data[1],
+            };
+            return (parameter, 2);
+        }
+        (0x02, 0x01, 0x2C) => XgParameter::ChorusReturn(value),
+        (0x02, 0x01, 0x40) if data.len() >= 2 => {
+            let parameter = XgParameter::VariationType {
+                msb: data[0],
+                lsb: data[1],
+            };
+            return (parameter, 2);
+        }
+        (0x08, part, offset) => XgParameter::Part {
+            part,
+            parameter: decode_part_parameter(offset, value),
+        },
+        _ => XgParameter::Unknown { address, value },
+    };
+    (parameter, 1)
+}
+
+fn decode_part_parameter(offset: u8, value: u8) -> XgPartParameter {
+    match offset {
+        0x01 => XgPartParameter::BankSelectMsb(value),
+        0x02 => XgPartParameter::BankSelectLsb(value),
+        0x03 => XgPartParameter::ProgramNumber(value),
+        0x04 => XgPartParameter::RxChannel(value),
+        0x05 => XgPartParameter::Poly(value != 0),
+        0x07 => match value {
+            1 => XgPartParameter::PartMode(XgPartMode::Drum),
+            2 => XgPartParameter::PartMode(XgPartMode::DrumS1),
+            3 => XgPartParameter::PartMode(XgPartMode::DrumS2),
+            4 => XgPartParameter::PartMode(XgPartMode::DrumS3),
+            5 => XgPartParameter::PartMode(XgPartMode::DrumS4),
+            _ => XgPartParameter::PartMode(XgPartMode::Normal),
+        },
+        0x08 => XgPartParameter::NoteShift(value as i8 - 0x40),
+        0x0B => XgPartParameter::Volume(value),
+        0x0E => XgPartParameter::Pan(value),
+        0x11 => XgPartParameter::DryLevel(value),
+        0x12 => XgPartParameter::ChorusSend(value),
+        0x13 => XgPartParameter::ReverbSend(value),
+        0x14 => XgPartParameter::VariationSend(value),
+        0x15 => XgPartParameter::VibratoRate(value),
+        0x16 => XgPartParameter::VibratoDepth(value),
+        0x17 => XgPartParameter::VibratoDelay(value),
+        0x18 => XgPartParameter::FilterCutoff(value),
+        0x19 => XgPartParameter::FilterResonance(value),
+        0x1A => XgPartParameter::EgAttack(value),
+        0x1B => XgPartParameter::EgDecay(value),
+        0x1C => XgPartParameter::EgRelease(value),
+        _ => XgPartParameter::Unknown { offset, value },
+    }
+}
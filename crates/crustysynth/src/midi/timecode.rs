@@ -0,0 +1,259 @@
+//! MIDI Time Code
+//!
+//! MTC sends SMPTE time as eight quarter frame messages, each carrying one nibble. It takes two
+//! frames to transmit a complete timecode.
+
+use std::fmt::Display;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MtcFrameRate {
+    Fps24 = 0,
+    Fps25 = 1,
+    /// 29.97 fps, drop frame
+    Fps30Drop = 2,
+    Fps30 = 3,
+}
+
+impl From<u8> for MtcFrameRate {
+    /// Get rate from the 2-bit rate field. Other bits are ignored.
+    fn from(v: u8) -> Self {
+        match v & 0x03 {
+            0 => Self::Fps24,
+            1 => Self::Fps25,
+            2 => Self::Fps30Drop,
+            3 => Self::Fps30,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl MtcFrameRate {
+    pub fn frames_per_second(&self) -> f64 {
+        match self {
+            Self::Fps24 => 24.0,
+            Self::Fps25 => 25.0,
+            Self::Fps30Drop => 30000.0 / 1001.0,
+            Self::Fps30 => 30.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct SmpteTimecode {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+    pub rate: MtcFrameRate,
+}
+
+impl Display for SmpteTimecode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let separator = match self.rate {
+            MtcFrameRate::Fps30Drop => ';',
+            _ => ':',
+        };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{separator}{:02}",
+            self.hours, self.minutes, self.seconds, self.frames
+        )
+    }
+}
+
+impl SmpteTimecode {
+    /// Parse the 4 bytes used by full frame messages: `0rrhhhhh mm ss ff`.
+    pub fn from_full_frame(data: [u8; 4]) -> Self {
+        Self {
+            hours: data[0] & 0x1F,
+            minutes: data[1] & 0x3F,
+            seconds: data[2] & 0x3F,
+            frames: data[3] & 0x1F,
+            rate: MtcFrameRate::from(data[0] >> 5),
+        }
+    }
+
+    /// Position in seconds. Drop frame timecode is converted to real time.
+    pub fn as_seconds(&self) -> f64 {
+        let seconds = self.hours as u32 * 3600 + self.minutes as u32 * 60 + self.seconds as u32;
+        match self.rate {
+            MtcFrameRate::Fps30Drop => {
+                // Frame numbers 0 and 1 are skipped every minute, except every tenth minute.
+                let total_minutes = self.hours as u32 * 60 + self.minutes as u32;
+                let dropped = 2 * (total_minutes - total_minutes / 10);
+                let frame_number = seconds * 30 + self.frames as u32 - dropped;
+                frame_number as f64 / self.rate.frames_per_second()
+            }
+            _ => seconds as f64 + self.frames as f64 / self.rate.frames_per_second(),
+        }
+    }
+}
+
+/// MTC quarter frame message (status 0xF1)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MtcQuarterFrame {
+    /// Which part of the timecode this message carries, 0-7
+    pub piece: u8,
+    /// Nibble value
+    pub value: u8,
+}
+
+impl From<u8> for MtcQuarterFrame {
+    /// Get quarter frame from its data byte: `0nnndddd`.
+    fn from(v: u8) -> Self {
+        Self {
+            piece: (v >> 4) & 0x07,
+            value: v & 0x0F,
+        }
+    }
+}
+
+impl From<MtcQuarterFrame> for u8 {
+    fn from(frame: MtcQuarterFrame) -> Self {
+        (frame.piece & 0x07) << 4 | (frame.value & 0x0F)
+    }
+}
+
+/// Reconstructs timecode from a stream of quarter frames.
+///
+/// Quarter frames may run forwards (piece 0 to 7) or backwards (7 to 0). A discontinuity in the
+/// sequence discards the partial timecode.
+#[derive(Debug, Default, Clone)]
+pub struct MtcAssembler {
+    values: [u8; 8],
+    /// Bitmask of pieces received since the last complete timecode
+    received: u8,
+    last_piece: Option<u8>,
+}
+
+impl MtcAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a quarter frame. Returns a timecode when the last piece of a sequence arrives.
+    ///
+    /// The returned timecode is the one encoded in the messages. It describes the time when the
+    /// first piece was sent, so playback is two frames ahead of it by now.
+    pub fn push(&mut self, frame: MtcQuarterFrame) -> Option<SmpteTimecode> {
+        let piece = frame.piece & 0x07;
+
+        let (forward, backward) = match self.last_piece {
+            Some(last) => (piece == (last + 1) % 8, piece == (last + 7) % 8),
+            None => (false, false),
+        };
+        if !forward && !backward {
+            self.received = 0;
+        }
+
+        self.values[piece as usize] = frame.value & 0x0F;
+        self.received |= 1 << piece;
+        self.last_piece = Some(piece);
+
+        let sequence_done = (forward && piece == 7) || (backward && piece == 0);
+        if self.received == 0xFF && sequence_done {
+            self.received = 0;
+            return Some(self.timecode());
+        }
+        None
+    }
+
+    /// Forget any partially received timecode.
+    pub fn reset(&mut self) {
+        self.received = 0;
+        self.last_piece = None;
+    }
+
+    fn timecode(&self) -> SmpteTimecode {
+        let v = &self.values;
+        SmpteTimecode {
+            frames: (v[1] & 0x01) << 4 | v[0],
+            seconds: (v[3] & 0x03) << 4 | v[2],
+            minutes: (v[5] & 0x03) << 4 | v[4],
+            hours: (v[7] & 0x01) << 4 | v[6],
+            rate: MtcFrameRate::from(v[7] >> 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quarter_frames(timecode: SmpteTimecode) -> [MtcQuarterFrame; 8] {
+        let values = [
+            timecode.frames & 0x0F,
+            timecode.frames >> 4,
+            timecode.seconds & 0x0F,
+            timecode.seconds >> 4,
+            timecode.minutes & 0x0F,
+            timecode.minutes >> 4,
+            timecode.hours & 0x0F,
+            timecode.hours >> 4 | (timecode.rate as u8) << 1,
+        ];
+        std::array::from_fn(|i| MtcQuarterFrame {
+            piece: i as u8,
+            value: values[i],
+        })
+    }
+
+    #[test]
+    fn test_assemble_forward() {
+        let timecode = SmpteTimecode {
+            hours: 17,
+            minutes: 42,
+            seconds: 59,
+            frames: 24,
+            rate: MtcFrameRate::Fps25,
+        };
+        let mut assembler = MtcAssembler::new();
+        let frames = quarter_frames(timecode);
+
+        // Start mid-sequence. Nothing complete until a full sequence has been seen.
+        for frame in &frames[3..] {
+            assert_eq!(assembler.push(*frame), None);
+        }
+        for frame in &frames[..7] {
+            assert_eq!(assembler.push(*frame), None);
+        }
+        assert_eq!(assembler.push(frames[7]), Some(timecode));
+    }
+
+    #[test]
+    fn test_assemble_backward() {
+        let timecode = SmpteTimecode {
+            hours: 1,
+            minutes: 2,
+            seconds: 3,
+            frames: 4,
+            rate: MtcFrameRate::Fps30Drop,
+        };
+        let mut assembler = MtcAssembler::new();
+        let frames = quarter_frames(timecode);
+
+        for frame in frames[1..].iter().rev() {
+            assert_eq!(assembler.push(*frame), None);
+        }
+        assert_eq!(assembler.push(frames[0]), Some(timecode));
+        assert_eq!(timecode.to_string(), "01:02:03;04");
+    }
+
+    #[test]
+    fn test_discontinuity_resets() {
+        let timecode = SmpteTimecode {
+            hours: 0,
+            minutes: 0,
+            seconds: 1,
+            frames: 0,
+            rate: MtcFrameRate::Fps24,
+        };
+        let mut assembler = MtcAssembler::new();
+        let frames = quarter_frames(timecode);
+
+        for frame in &frames[..4] {
+            assembler.push(*frame);
+        }
+        assembler.push(frames[6]);
+        assert_eq!(assembler.push(frames[7]), None);
+    }
+}
@@ -0,0 +1,388 @@
+//! MIDI Clip Files (SMF2CLIP)
+//!
+//! MIDI 2.0 clip files store a single stream of Universal MIDI Packets. Instead of chunks, the
+//! file is an 8-byte signature followed by packets, each preceded by a Delta Clockstamp:
+//!
+//! - Header: ticks per quarter note, optional configuration messages, Start of Clip
+//! - Sequence: timed messages, ending with End of Clip
+
+use std::{
+    error::Error,
+    fmt::Display,
+    fs::File,
+    io::{self, BufReader},
+};
+
+use super::{
+    miditrack::{midievent::MidiEvent, MidiTrack, MidiTrackEvent},
+    MidiFile, MidiFileFormat,
+};
+use crate::ump::{self, UmpDecoder, UmpMessage, UmpPacket, UmpProtocol};
+
+const SIGNATURE: &[u8; 8] = b"SMF2CLIP";
+const MAX_DELTA_CLOCKSTAMP: usize = 0xF_FFFF;
+
+#[derive(Debug)]
+pub enum MidiClipError {
+    IOError { source: std::io::Error },
+    NoSignature,
+    NoTicksPerQuarter,
+    SmpteDivision,
+}
+impl Error for MidiClipError {}
+impl Display for MidiClipError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IOError { source } => write!(f, "{source}"),
+            Self::NoSignature => write!(f, "Clip file did not start with SMF2CLIP."),
+            Self::NoTicksPerQuarter => write!(f, "Clip file header has no ticks per quarter."),
+            Self::SmpteDivision => write!(f, "SMPTE time division can't be used in a clip file."),
+        }
+    }
+}
+impl From<std::io::Error> for MidiClipError {
+    fn from(e: std::io::Error) -> Self {
+        Self::IOError { source: e }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MidiClip {
+    ticks_per_quarter: u16,
+    /// Configuration messages between ticks per quarter and Start of Clip
+    header: Vec<UmpPacket>,
+    clip_events: Vec<MidiClipEvent>,
+    /// Delta time of End of Clip
+    end_delta_time: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MidiClipEvent {
+    delta_time: usize,
+    packet: UmpPacket,
+}
+impl MidiClipEvent {
+    pub fn new(delta_time: usize, packet: UmpPacket) -> Self {
+        Self { delta_time, packet }
+    }
+
+    pub fn get_delta_time(&self) -> usize {
+        self.delta_time
+    }
+    pub fn get_packet(&self) -> &UmpPacket {
+        &self.packet
+    }
+}
+
+impl TryFrom<File> for MidiClip {
+    type Error = MidiClipError;
+
+    fn try_from(file: File) -> Result<Self, Self::Error> {
+        Self::read(&mut BufReader::new(file))
+    }
+}
+
+impl MidiClip {
+    pub fn new(
+        ticks_per_quarter: u16,
+        clip_events: Vec<MidiClipEvent>,
+        end_delta_time: usize,
+    ) -> Self {
+        Self {
+            ticks_per_quarter,
+            header: vec![],
+            clip_events,
+            end_delta_time,
+        }
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Self, MidiClipError>
+    where
+        R: io::Read,
+    {
+        let mut signature = [0_u8; 8];
+        reader.read_exact(&mut signature)?;
+        if &signature != SIGNATURE {
+            return Err(MidiClipError::NoSignature);
+        }
+
+        // Only used for utility and stream messages, which are always decoded.
+        let mut decoder = UmpDecoder::new();
+
+        let mut ticks_per_quarter = None;
+        let mut header = vec![];
+        loop {
+            let packet = UmpPacket::read(reader)?;
+            match decoder.decode(&packet) {
+                Some(UmpMessage::DeltaClockstamp(_)) | Some(UmpMessage::Noop) => (),
+                Some(UmpMessage::DeltaClockstampTpq(tpq)) => ticks_per_quarter = Some(tpq),
+                Some(UmpMessage::StartOfClip) => break,
+                _ => header.push(packet),
+            }
+        }
+        let ticks_per_quarter = ticks_per_quarter.ok_or(MidiClipError::NoTicksPerQuarter)?;
+
+        let mut clip_events = vec![];
+        let mut delta_time = 0;
+        loop {
+            let packet = UmpPacket::read(reader)?;
+            match decoder.decode(&packet) {
+                Some(UmpMessage::DeltaClockstamp(ticks)) => delta_time += ticks as usize,
+                Some(UmpMessage::Noop) => (),
+                Some(UmpMessage::EndOfClip) => break,
+                _ => {
+                    clip_events.push(MidiClipEvent { delta_time, packet });
+                    delta_time = 0;
+                }
+            }
+        }
+
+        Ok(Self {
+            ticks_per_quarter,
+            header,
+            clip_events,
+            end_delta_time: delta_time,
+        })
+    }
+
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), io::Error>
+    where
+        W: io::Write,
+    {
+        writer.write_all(SIGNATURE)?;
+
+        write_delta_time(writer, 0)?;
+        UmpPacket::delta_clockstamp_tpq(self.ticks_per_quarter).write(writer)?;
+        for packet in &self.header {
+            write_delta_time(writer, 0)?;
+            packet.write(writer)?;
+        }
+        write_delta_time(writer, 0)?;
+        UmpPacket::start_of_clip().write(writer)?;
+
+        for clip_event in &self.clip_events {
+            write_delta_time(writer, clip_event.delta_time)?;
+            clip_event.packet.write(writer)?;
+        }
+        write_delta_time(writer, self.end_delta_time)?;
+        UmpPacket::end_of_clip().write(writer)
+    }
+
+    /// Merge the tracks of a MIDI file into a clip. Events are placed on group 0.
+    ///
+    /// Tempo and time signature meta events become flex data messages. Other meta events have
+    /// no UMP equivalent and are left out.
+    pub fn from_midi_file(
+        midi_file: &MidiFile,
+        protocol: UmpProtocol,
+    ) -> Result<Self, MidiClipError> {
+        let division = midi_file.get_division();
+        if division & 0x8000 != 0 {
+            return Err(MidiClipError::SmpteDivision);
+        }
+
+        let mut timed_events = vec![];
+        let mut end_time = 0;
+        for track in midi_file.get_tracks() {
+            let mut time = 0;
+            for track_event in track.get_events() {
+                time += track_event.get_delta_time();
+                timed_events.push((time, track_event.get_event()));
+            }
+            end_time = end_time.max(time);
+        }
+        // Stable, so simultaneous events keep their track order.
+        timed_events.sort_by_key(|(time, _)| *time);
+
+        let mut clip_events = vec![];
+        let mut last_time = 0;
+        for (time, event) in timed_events {
+            for packet in event_to_packets(event, protocol) {
+                clip_events.push(MidiClipEvent {
+                    delta_time: time - last_time,
+                    packet,
+                });
+                last_time = time;
+            }
+        }
+
+        Ok(Self {
+            ticks_per_quarter: division,
+            header: vec![],
+            clip_events,
+            end_delta_time: end_time - last_time,
+        })
+    }
+
+    /// Convert to a single track MIDI file. Groups are not preserved, and messages without a
+    /// MIDI 1.0 equivalent are left out.
+    pub fn to_midi_file(&self) -> MidiFile {
+        let mut decoder = UmpDecoder::new();
+        let mut track_events = vec![];
+        let mut delta_time = 0;
+
+        let header = self.header.iter().map(|packet| (0, packet));
+        let sequence = self
+            .clip_events
+            .iter()
+            .map(|clip_event| (clip_event.delta_time, &clip_event.packet));
+
+        for (packet_delta, packet) in header.chain(sequence) {
+            delta_time += packet_delta;
+            let event = match decoder.decode(packet) {
+                Some(UmpMessage::Midi { event, .. }) => event,
+                Some(UmpMessage::SetTempo {
+                    ten_ns_per_quarter, ..
+                }) => MidiEvent::Meta {
+                    meta_type: 0x51,
+                    data: (ten_ns_per_quarter / 100).to_be_bytes()[1..].to_vec(),
+                },
+                Some(UmpMessage::TimeSignature {
+                    numerator,
+                    denominator,
+                    thirty_seconds_per_quarter,
+                    ..
+                }) => MidiEvent::Meta {
+                    meta_type: 0x58,
+                    // MIDI clocks per metronome click has no UMP equivalent. Use 1 per quarter.
+                    data: vec![numerator, denominator, 24, thirty_seconds_per_quarter],
+                },
+                _ => continue,
+            };
+            track_events.push(MidiTrackEvent::new(delta_time, event));
+            delta_time = 0;
+        }
+
+        let end_of_track = MidiEvent::Meta {
+            meta_type: 0x2F,
+            data: vec![],
+        };
+        track_events.push(MidiTrackEvent::new(
+            delta_time + self.end_delta_time,
+            end_of_track,
+        ));
+
+        MidiFile::new(
+            MidiFileFormat::SingleTrack,
+            self.ticks_per_quarter,
+            vec![MidiTrack::new(track_events)],
+        )
+    }
+
+    pub fn get_ticks_per_quarter(&self) -> u16 {
+        self.ticks_per_quarter
+    }
+    pub fn get_header(&self) -> &Vec<UmpPacket> {
+        &self.header
+    }
+    pub fn get_events(&self) -> &Vec<MidiClipEvent> {
+        &self.clip_events
+    }
+    pub fn get_end_delta_time(&self) -> usize {
+        self.end_delta_time
+    }
+}
+
+fn event_to_packets(event: &MidiEvent, protocol: UmpProtocol) -> Vec<UmpPacket> {
+    match event {
+        MidiEvent::Meta { meta_type, data } => match (meta_type, data.as_slice()) {
+            (0x51, [b0, b1, b2]) => {
+                let us_per_quarter = u32::from_be_bytes([0, *b0, *b1, *b2]);
+                vec![UmpPacket::set_tempo(0, us_per_quarter * 100)]
+            }
+            (0x58, [numerator, denominator, _, thirty_seconds, ..]) => {
+                vec![UmpPacket::time_signature(
+                    0,
+                    *numerator,
+                    *denominator,
+                    *thirty_seconds,
+                )]
+            }
+            _ => vec![],
+        },
+        _ => ump::encode(event, 0, protocol),
+    }
+}
+
+/// Delta clockstamps are limited to 20 bits. Longer deltas are split.
+fn write_delta_time<W>(writer: &mut W, mut delta_time: usize) -> Result<(), io::Error>
+where
+    W: io::Write,
+{
+    while delta_time > MAX_DELTA_CLOCKSTAMP {
+        UmpPacket::delta_clockstamp(MAX_DELTA_CLOCKSTAMP as u32).write(writer)?;
+        delta_time -= MAX_DELTA_CLOCKSTAMP;
+    }
+    UmpPacket::delta_clockstamp(delta_time as u32).write(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn absolute_events(midi_file: &MidiFile) -> Vec<(usize, MidiEvent)> {
+        let mut events = vec![];
+        for track in midi_file.get_tracks() {
+            let mut time = 0;
+            for track_event in track.get_events() {
+                time += track_event.get_delta_time();
+                match track_event.get_event() {
+                    MidiEvent::Meta {
+                        meta_type: 0x51 | 0x58,
+                        ..
+                    } => (),
+                    MidiEvent::Meta { .. } => continue,
+                    _ => (),
+                }
+                events.push((time, track_event.get_event().clone()));
+            }
+        }
+        events.sort_by_key(|(time, _)| *time);
+        events
+    }
+
+    #[test]
+    fn test_salsa_roundtrip() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../samples/salsa.mid");
+        let midi_file = MidiFile::try_from(File::open(path).unwrap()).unwrap();
+
+        let clip = MidiClip::from_midi_file(&midi_file, UmpProtocol::Midi1).unwrap();
+        let mut bytes = vec![];
+        clip.write(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], b"SMF2CLIP");
+
+        let clip = MidiClip::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(clip.get_ticks_per_quarter(), midi_file.get_division());
+
+        let converted = clip.to_midi_file();
+        assert_eq!(absolute_events(&converted), absolute_events(&midi_file));
+    }
+
+    #[test]
+    fn test_long_delta() {
+        let clip = MidiClip::new(
+            96,
+            vec![MidiClipEvent::new(
+                MAX_DELTA_CLOCKSTAMP * 2 + 5,
+                UmpPacket::set_tempo(0, 50_000_000),
+            )],
+            3,
+        );
+        let mut bytes = vec![];
+        clip.write(&mut bytes).unwrap();
+        let clip = MidiClip::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(
+            clip.get_events()[0].get_delta_time(),
+            MAX_DELTA_CLOCKSTAMP * 2 + 5
+        );
+        assert_eq!(clip.get_end_delta_time(), 3);
+    }
+
+    #[test]
+    fn test_no_signature() {
+        assert!(matches!(
+            MidiClip::read(&mut b"MThd\0\0\0\x06".as_slice()),
+            Err(MidiClipError::NoSignature)
+        ));
+    }
+}
@@ -1,11 +1,13 @@
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, io};
 
 use crate::{
     midi::{
         channels::MidiChannel,
         keys::{MidiKey, MidiKeyError},
+        sysex::{manufacturer::ManufacturerId, SysExError, SysExMessage},
+        timecode::MtcQuarterFrame,
     },
-    midifile::vlq::read_vlq,
+    midifile::vlq::{read_vlq, write_vlq},
 };
 
 #[derive(Debug)]
@@ -35,8 +37,7 @@ impl From<MidiKeyError> for MidiEventError {
     }
 }
 
-#[derive(Debug)]
-
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum MidiEvent {
     NoteOff {
         channel: MidiChannel,
@@ -77,10 +78,12 @@ pub enum MidiEvent {
         value: u8,
     },
 
+    /// `data` is everything after the manufacturer id, excluding End of Exclusive.
     SysEx {
-        id: u8,
+        id: ManufacturerId,
         data: Vec<u8>,
     },
+    MtcQuarterFrame(MtcQuarterFrame),
     SongPositionPointer {
         position: u16,
     },
@@ -95,6 +98,13 @@ pub enum MidiEvent {
     Continue,
     Stop,
     ActiveSensing,
+    /// Wire streams only. In files, 0xFF introduces a meta event instead.
+    SystemReset,
+
+    /// Undefined system status (0xF4, 0xF5, 0xF9, 0xFD). These carry no data bytes.
+    Undefined {
+        status: u8,
+    },
 
     Meta {
         meta_type: u8,
@@ -126,7 +136,7 @@ impl MidiEvent {
                 let mut buf = [0_u8; 2];
                 file.read_exact(&mut buf)?;
 
-                Ok(Self::NoteOff {
+                Ok(Self::NoteOn {
                     channel: MidiChannel::from(status_byte),
                     key: MidiKey::try_from(buf[0] & 0x7F)?,
                     vel: buf[1] & 0x7F,
@@ -184,30 +194,39 @@ impl MidiEvent {
 
                 Ok(Self::PitchBend {
                     channel: MidiChannel::from(status_byte),
-                    value: ((buf[1] & 0x7F) << 7) as u16 + (buf[0] & 0x7F) as u16,
+                    value: ((buf[1] & 0x7F) as u16) << 7 | (buf[0] & 0x7F) as u16,
                 })
             }
             0xF0 => match status_byte {
                 0xF0 => {
+                    // In files, sysex is stored as F0 <length> <bytes after F0>.
+                    let len = read_vlq(file)?;
+                    let mut message = vec![0_u8; len];
+                    file.read_exact(&mut message)?;
+                    if message.last() == Some(&0xF7) {
+                        message.pop();
+                    }
+                    // Too short for a manufacturer id, but the rest of the track is fine.
+                    let Some((id, data)) = ManufacturerId::parse(&message) else {
+                        return Ok(Self::Undefined { status: 0xF0 });
+                    };
+                    Ok(Self::SysEx {
+                        id,
+                        data: data.to_vec(),
+                    })
+                }
+                0xF1 => {
                     let mut buf = [0_u8];
                     file.read_exact(&mut buf)?;
-                    let id = buf[0] & 0x7F;
-                    let mut data = vec![];
-                    loop {
-                        file.read_exact(&mut buf)?;
-                        data.push(buf[0]);
-                        if buf[0] == 0xF7 {
-                            break;
-                        }
-                    }
-                    Ok(Self::SysEx { id, data })
+
+                    Ok(Self::MtcQuarterFrame(MtcQuarterFrame::from(buf[0])))
                 }
                 0xF2 => {
                     let mut buf = [0_u8; 2];
                     file.read_exact(&mut buf)?;
 
                     Ok(Self::SongPositionPointer {
-                        position: ((buf[1] & 0x7F) << 7) as u16 + (buf[0] & 0x7F) as u16,
+                        position: ((buf[1] & 0x7F) as u16) << 7 | (buf[0] & 0x7F) as u16,
                     })
                 }
                 0xF3 => {
@@ -218,6 +237,9 @@ impl MidiEvent {
                         song: buf[0] & 0x7F,
                     })
                 }
+                0xF4 | 0xF5 | 0xF9 | 0xFD => Ok(Self::Undefined {
+                    status: status_byte,
+                }),
                 0xF6 => Ok(Self::TuneRequest),
                 0xF7 => Ok(Self::EndOfExclusive),
                 0xF8 => Ok(Self::TimingClock),
@@ -242,4 +264,290 @@ impl MidiEvent {
             _ => Err(MidiEventError::UnknownStatusByte(status_byte)),
         }
     }
+
+    /// Decode the contents of a SysEx event. Returns `None` if this isn't a SysEx event.
+    pub fn decode_sysex(&self) -> Option<Result<SysExMessage, SysExError>> {
+        match self {
+            Self::SysEx { id, data } => Some(SysExMessage::decode(*id, data)),
+            _ => None,
+        }
+    }
+
+    /// Write the event as MIDI 1.0 wire bytes, always including the status byte.
+    ///
+    /// Meta events don't exist on the wire, so they are written the way they are stored in
+    /// files: `FF <type> <length> <data>`.
+    pub fn encode<W>(&self, writer: &mut W) -> Result<(), io::Error>
+    where
+        W: io::Write,
+    {
+        match self {
+            Self::SysEx { id, data } => {
+                writer.write_all(&[0xF0])?;
+                writer.write_all(&id.to_bytes())?;
+                writer.write_all(data)?;
+                writer.write_all(&[0xF7])
+            }
+            Self::Meta { meta_type, data } => {
+                writer.write_all(&[0xFF, *meta_type])?;
+                write_vlq(writer, data.len())?;
+                writer.write_all(data)
+            }
+            _ => {
+                writer.write_all(&[self.status_byte()])?;
+                writer.write_all(&self.data_bytes())
+            }
+        }
+    }
+
+    /// Get the event as MIDI 1.0 wire bytes. See [`MidiEvent::encode`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.encode(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Status byte of the event.
+    pub fn status_byte(&self) -> u8 {
+        match self {
+            Self::NoteOff { channel, .. } => 0x80 | u8::from(*channel),
+            Self::NoteOn { channel, .. } => 0x90 | u8::from(*channel),
+            Self::AfterTouch { channel, .. } => 0xA0 | u8::from(*channel),
+            Self::ControlChange { channel, .. } | Self::ChannelMode { channel, .. } => {
+                0xB0 | u8::from(*channel)
+            }
+            Self::ProgramChange { channel, .. } => 0xC0 | u8::from(*channel),
+            Self::ChannelPressure { channel, .. } => 0xD0 | u8::from(*channel),
+            Self::PitchBend { channel, .. } => 0xE0 | u8::from(*channel),
+            Self::SysEx { .. } => 0xF0,
+            Self::MtcQuarterFrame(_) => 0xF1,
+            Self::SongPositionPointer { .. } => 0xF2,
+            Self::SongSelect { .. } => 0xF3,
+            Self::TuneRequest => 0xF6,
+            Self::EndOfExclusive => 0xF7,
+            Self::TimingClock => 0xF8,
+            Self::Start => 0xFA,
+            Self::Continue => 0xFB,
+            Self::Stop => 0xFC,
+            Self::ActiveSensing => 0xFE,
+            Self::SystemReset | Self::Meta { .. } => 0xFF,
+            Self::Undefined { status } => *status,
+        }
+    }
+
+    /// Data bytes of fixed length messages. Empty for SysEx and meta events.
+    fn data_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::NoteOff { key, vel, .. } | Self::NoteOn { key, vel, .. } => {
+                vec![u8::from(*key), vel & 0x7F]
+            }
+            Self::AfterTouch { key, pressure, .. } => vec![u8::from(*key), pressure & 0x7F],
+            Self::ControlChange { control, value, .. }
+            | Self::ChannelMode { control, value, .. } => {
+                vec![control & 0x7F, value & 0x7F]
+            }
+            Self::ProgramChange { program, .. } => vec![program & 0x7F],
+            Self::ChannelPressure { value, .. } => vec![value & 0x7F],
+            Self::PitchBend { value, .. } | Self::SongPositionPointer { position: value } => {
+                vec![(value & 0x7F) as u8, ((value >> 7) & 0x7F) as u8]
+            }
+            Self::MtcQuarterFrame(frame) => vec![u8::from(*frame)],
+            Self::SongSelect { song } => vec![song & 0x7F],
+            _ => vec![],
+        }
+    }
+
+    fn is_realtime(&self) -> bool {
+        matches!(
+            self,
+            Self::TimingClock
+                | Self::Start
+                | Self::Continue
+                | Self::Stop
+                | Self::ActiveSensing
+                | Self::SystemReset
+                | Self::Undefined {
+                    status: 0xF9 | 0xFD
+                }
+        )
+    }
+}
+
+/// Encodes events to wire bytes, leaving out repeated channel status bytes.
+#[derive(Debug, Default)]
+pub struct RunningStatusEncoder {
+    running_status: Option<u8>,
+}
+
+impl RunningStatusEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write an event. The status byte is left out if it's the same as the previous channel
+    /// message's.
+    pub fn encode<W>(&mut self, event: &MidiEvent, writer: &mut W) -> Result<(), io::Error>
+    where
+        W: io::Write,
+    {
+        let status = event.status_byte();
+
+        // Real-time messages don't affect running status.
+        if event.is_realtime() {
+            return event.encode(writer);
+        }
+        if status >= 0xF0 {
+            self.running_status = None;
+            return event.encode(writer);
+        }
+
+        if self.running_status != Some(status) {
+            writer.write_all(&[status])?;
+            self.running_status = Some(status);
+        }
+        writer.write_all(&event.data_bytes())
+    }
+
+    /// Forget the running status. The next channel message will include its status byte.
+    pub fn reset(&mut self) {
+        self.running_status = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::stream::MidiStreamParser;
+
+    fn wire_events() -> Vec<MidiEvent> {
+        vec![
+            MidiEvent::NoteOn {
+                channel: MidiChannel::Ch10,
+                key: MidiKey::B2,
+                vel: 100,
+            },
+            MidiEvent::NoteOff {
+                channel: MidiChannel::Ch10,
+                key: MidiKey::B2,
+                vel: 0,
+            },
+            MidiEvent::AfterTouch {
+                channel: MidiChannel::Ch1,
+                key: MidiKey::C5,
+                pressure: 12,
+            },
+            MidiEvent::ControlChange {
+                channel: MidiChannel::Ch16,
+                control: 7,
+                value: 127,
+            },
+            MidiEvent::ChannelMode {
+                channel: MidiChannel::Ch16,
+                control: 123,
+                value: 0,
+            },
+            MidiEvent::ProgramChange {
+                channel: MidiChannel::Ch3,
+                program: 56,
+            },
+            MidiEvent::ChannelPressure {
+                channel: MidiChannel::Ch3,
+                value: 64,
+            },
+            MidiEvent::PitchBend {
+                channel: MidiChannel::Ch4,
+                value: 0x3FFF,
+            },
+            MidiEvent::SysEx {
+                id: ManufacturerId::Extended(0x20, 0x33),
+                data: vec![0x01, 0x02],
+            },
+            MidiEvent::MtcQuarterFrame(MtcQuarterFrame { piece: 7, value: 3 }),
+            MidiEvent::SongPositionPointer { position: 0x1234 },
+            MidiEvent::SongSelect { song: 5 },
+            MidiEvent::Undefined { status: 0xF4 },
+            MidiEvent::TuneRequest,
+            MidiEvent::TimingClock,
+            MidiEvent::Start,
+            MidiEvent::Undefined { status: 0xF9 },
+            MidiEvent::Continue,
+            MidiEvent::Stop,
+            MidiEvent::Undefined { status: 0xFD },
+            MidiEvent::ActiveSensing,
+            MidiEvent::SystemReset,
+        ]
+    }
+
+    #[test]
+    fn test_encode() {
+        let event = MidiEvent::PitchBend {
+            channel: MidiChannel::Ch2,
+            value: 0x2000,
+        };
+        assert_eq!(event.to_bytes(), vec![0xE1, 0x00, 0x40]);
+
+        let event = MidiEvent::SysEx {
+            id: ManufacturerId::ROLAND,
+            data: vec![0x10, 0x42],
+        };
+        assert_eq!(event.to_bytes(), vec![0xF0, 0x41, 0x10, 0x42, 0xF7]);
+
+        let event = MidiEvent::Meta {
+            meta_type: 0x2F,
+            data: vec![],
+        };
+        assert_eq!(event.to_bytes(), vec![0xFF, 0x2F, 0x00]);
+    }
+
+    #[test]
+    fn test_wire_roundtrip() {
+        let events = wire_events();
+        let mut bytes = vec![];
+        for event in &events {
+            event.encode(&mut bytes).unwrap();
+        }
+        let parsed: Vec<_> = MidiStreamParser::new().feed(&bytes).collect();
+        assert_eq!(parsed, events);
+    }
+
+    #[test]
+    fn test_running_status_roundtrip() {
+        let events = wire_events();
+        let mut encoder = RunningStatusEncoder::new();
+        let mut bytes = vec![];
+        for event in &events {
+            encoder.encode(event, &mut bytes).unwrap();
+        }
+        // ChannelMode shares status with the ControlChange before it.
+        let full_len: usize = events.iter().map(|e| e.to_bytes().len()).sum();
+        assert_eq!(bytes.len(), full_len - 1);
+
+        let parsed: Vec<_> = MidiStreamParser::new().feed(&bytes).collect();
+        assert_eq!(parsed, events);
+    }
+
+    #[test]
+    fn test_file_roundtrip() {
+        let event = MidiEvent::Meta {
+            meta_type: 0x51,
+            data: vec![0x07, 0xA1, 0x20],
+        };
+        let bytes = event.to_bytes();
+        assert_eq!(MidiEvent::read(&mut bytes.as_slice()).unwrap(), event);
+
+        for event in wire_events().into_iter().take(8) {
+            let bytes = event.to_bytes();
+            assert_eq!(MidiEvent::read(&mut bytes.as_slice()).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn test_short_sysex() {
+        let undefined = MidiEvent::Undefined { status: 0xF0 };
+        for bytes in [[0xF0, 0x01, 0xF7], [0xF0, 0x01, 0x00]] {
+            let mut slice = bytes.as_slice();
+            assert_eq!(MidiEvent::read(&mut slice).unwrap(), undefined);
+            assert!(slice.is_empty());
+        }
+    }
 }
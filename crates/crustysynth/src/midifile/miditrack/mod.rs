@@ -62,6 +62,10 @@ impl TryFrom<MidiChunk> for MidiTrack {
     }
 }
 impl MidiTrack {
+    pub fn new(track_events: Vec<MidiTrackEvent>) -> Self {
+        Self { track_events }
+    }
+
     pub fn get_events(&self) -> &Vec<MidiTrackEvent> {
         &self.track_events
     }
@@ -73,6 +77,10 @@ pub struct MidiTrackEvent {
     event: MidiEvent,
 }
 impl MidiTrackEvent {
+    pub fn new(delta_time: usize, event: MidiEvent) -> Self {
+        Self { delta_time, event }
+    }
+
     pub fn read<R>(file: &mut R) -> Result<Self, MidiTrackError>
     where
         R: std::io::Read,
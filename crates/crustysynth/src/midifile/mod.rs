@@ -4,6 +4,7 @@ use chunks::{MidiChunk, MidiChunkError, MidiChunkType};
 use miditrack::{MidiTrack, MidiTrackError};
 
 pub mod chunks;
+pub mod clip;
 pub mod miditrack;
 pub mod vlq;
 
@@ -126,6 +127,15 @@ impl TryFrom<File> for MidiFile {
     }
 }
 impl MidiFile {
+    pub fn new(format: MidiFileFormat, division: u16, tracks: Vec<MidiTrack>) -> Self {
+        Self {
+            format,
+            ntrks: tracks.len() as u16,
+            division,
+            tracks,
+        }
+    }
+
     pub fn get_format(&self) -> MidiFileFormat {
         self.format
     }
@@ -24,6 +24,23 @@ where
     Ok(value)
 }
 
+/// Write a vlq to a buffer.
+pub fn write_vlq<W>(file: &mut W, value: usize) -> Result<(), std::io::Error>
+where
+    W: std::io::Write,
+{
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut value = value >> 7;
+
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.reverse();
+
+    file.write_all(&bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,4 +92,20 @@ mod tests {
             0xFFFFFFF
         );
     }
+
+    #[test]
+    fn test_write_vlq() {
+        for value in [
+            0, 0x40, 0x7F, 0x80, 0x2000, 0x3FFF, 0x4000, 0x100000, 0x1FFFFF, 0x200000, 0x8000000,
+            0xFFFFFFF,
+        ] {
+            let mut bytes = vec![];
+            write_vlq(&mut bytes, value).unwrap();
+            assert_eq!(read_vlq(&mut bytes.as_slice()).unwrap(), value);
+        }
+
+        let mut bytes = vec![];
+        write_vlq(&mut bytes, 0x4000).unwrap();
+        assert_eq!(bytes, vec![0x81, 0x80, 0x00]);
+    }
 }
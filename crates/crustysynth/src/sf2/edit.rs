@@ -0,0 +1,741 @@
+//! Editing
+//!
+//! Records refer to each other by index, so removing or adding presets, instruments or samples
+//! means rebuilding the hydra. Edits work on an owned view where each preset and instrument holds
+//! its zones, and the hydra is rebuilt from that view with new indices and terminal records.
+
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use super::{
+    generator::{Generator, GeneratorAmount, GeneratorType},
+    hydra::{Bag, Hydra, Instrument, PresetHeader, Zone},
+    info::SoundFontInfo,
+    modulator::Modulator,
+    sample::{SampleData, SampleHeader, SampleLink, SAMPLE_PADDING},
+    SoundFont,
+};
+
+/// Points in a coarse address offset
+pub(crate) const COARSE_OFFSET: i32 = 32768;
+
+/// Index that references to missing records are rewritten to. It's past the end of any chunk, so
+/// validation reports it.
+const MISSING_INDEX: u16 = u16::MAX;
+
+#[derive(Debug)]
+pub enum SoundFontEditError {
+    NoSuchPreset(usize),
+    PresetExists { bank: u16, program: u16 },
+}
+impl Error for SoundFontEditError {}
+impl Display for SoundFontEditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoSuchPreset(index) => write!(f, "No preset at index {index}"),
+            Self::PresetExists { bank, program } => {
+                write!(f, "Preset {bank}:{program} already exists.")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ZoneRecords {
+    pub generators: Vec<Generator>,
+    pub modulators: Vec<Modulator>,
+}
+impl From<Zone<'_>> for ZoneRecords {
+    fn from(zone: Zone) -> Self {
+        Self {
+            generators: zone.generators.to_vec(),
+            modulators: zone.modulators.to_vec(),
+        }
+    }
+}
+
+/// A preset with its zones. The bag index of the header is ignored.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PresetRecords {
+    pub header: PresetHeader,
+    pub zones: Vec<ZoneRecords>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct InstrumentRecords {
+    pub name: String,
+    pub zones: Vec<ZoneRecords>,
+}
+
+/// Generators of a zone, in the order the spec requires.
+#[derive(Debug, Default)]
+pub(crate) struct ZoneBuilder {
+    generators: Vec<(GeneratorType, i32)>,
+}
+
+impl ZoneBuilder {
+    pub(crate) fn set(mut self, generator_type: GeneratorType, value: i32) -> Self {
+        match self
+            .generators
+            .iter_mut()
+            .find(|(t, _)| *t == generator_type)
+        {
+            Some((_, old)) => *old = value,
+            None => self.generators.push((generator_type, value)),
+        }
+        self
+    }
+
+    pub(crate) fn set_range(self, generator_type: GeneratorType, (lo, hi): (u8, u8)) -> Self {
+        self.set(generator_type, (hi as i32) << 8 | lo as i32)
+    }
+
+    pub(crate) fn add(self, generator_type: GeneratorType, value: i32) -> Self {
+        let old = self
+            .generators
+            .iter()
+            .find(|(t, _)| *t == generator_type)
+            .map_or(0, |(_, old)| *old);
+        self.set(generator_type, old + value)
+    }
+
+    /// Split an address offset into fine and coarse parts. Zero offsets are left out.
+    pub(crate) fn set_offset(
+        self,
+        fine: GeneratorType,
+        coarse: GeneratorType,
+        offset: i32,
+    ) -> Self {
+        let mut zone = self;
+        if offset % COARSE_OFFSET != 0 {
+            zone = zone.set(fine, offset % COARSE_OFFSET);
+        }
+        if offset / COARSE_OFFSET != 0 {
+            zone = zone.set(coarse, offset / COARSE_OFFSET);
+        }
+        zone
+    }
+
+    pub(crate) fn build(mut self) -> ZoneRecords {
+        // Ranges first, the terminal generator last.
+        self.generators.sort_by_key(|(t, _)| match t {
+            GeneratorType::KeyRange => 0,
+            GeneratorType::VelRange => 1,
+            GeneratorType::Instrument | GeneratorType::SampleId => 3,
+            _ => 2,
+        });
+        let generators = self
+            .generators
+            .into_iter()
+            .map(|(generator_type, value)| {
+                let amount = match generator_type.is_range() || generator_type.is_unsigned() {
+                    true => GeneratorAmount::from_u16(value.clamp(0, u16::MAX as i32) as u16),
+                    false => GeneratorAmount::from_i16(
+                        value.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+                    ),
+                };
+                Generator::new(generator_type, amount)
+            })
+            .collect();
+        ZoneRecords {
+            generators,
+            modulators: vec![],
+        }
+    }
+}
+
+impl Hydra {
+    /// Presets with their zones, without the terminal record.
+    pub fn get_preset_records(&self) -> Vec<PresetRecords> {
+        let count = self.preset_headers.len().saturating_sub(1);
+        (0..count)
+            .map(|i| PresetRecords {
+                header: self.preset_headers[i].clone(),
+                zones: self
+                    .get_preset_zones(i)
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Instruments with their zones, without the terminal record.
+    pub fn get_instrument_records(&self) -> Vec<InstrumentRecords> {
+        let count = self.instruments.len().saturating_sub(1);
+        (0..count)
+            .map(|i| InstrumentRecords {
+                name: self.instruments[i].name.clone(),
+                zones: self
+                    .get_instrument_zones(i)
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Build a hydra from owned records. Terminal records are added, so `sample_headers` should
+    /// not include one.
+    pub fn from_records(
+        presets: &[PresetRecords],
+        instruments: &[InstrumentRecords],
+        mut sample_headers: Vec<SampleHeader>,
+    ) -> Self {
+        let mut hydra = Self::default();
+
+        for preset in presets {
+            hydra.preset_headers.push(PresetHeader {
+                bag_index: hydra.preset_bags.len() as u16,
+                ..preset.header.clone()
+            });
+            push_zones(
+                &preset.zones,
+                &mut hydra.preset_bags,
+                &mut hydra.preset_generators,
+                &mut hydra.preset_modulators,
+            );
+        }
+        hydra.preset_headers.push(PresetHeader {
+            name: "EOP".into(),
+            bag_index: hydra.preset_bags.len() as u16,
+            ..Default::default()
+        });
+        push_terminal_zone(
+            &mut hydra.preset_bags,
+            &mut hydra.preset_generators,
+            &mut hydra.preset_modulators,
+        );
+
+        for instrument in instruments {
+            hydra.instruments.push(Instrument {
+                name: instrument.name.clone(),
+                bag_index: hydra.instrument_bags.len() as u16,
+            });
+            push_zones(
+                &instrument.zones,
+                &mut hydra.instrument_bags,
+                &mut hydra.instrument_generators,
+                &mut hydra.instrument_modulators,
+            );
+        }
+        hydra.instruments.push(Instrument {
+            name: "EOI".into(),
+            bag_index: hydra.instrument_bags.len() as u16,
+        });
+        push_terminal_zone(
+            &mut hydra.instrument_bags,
+            &mut hydra.instrument_generators,
+            &mut hydra.instrument_modulators,
+        );
+
+        sample_headers.push(SampleHeader {
+            name: "EOS".into(),
+            ..Default::default()
+        });
+        hydra.sample_headers = sample_headers;
+
+        hydra
+    }
+}
+
+fn push_zones(
+    zones: &[ZoneRecords],
+    bags: &mut Vec<Bag>,
+    generators: &mut Vec<Generator>,
+    modulators: &mut Vec<Modulator>,
+) {
+    for zone in zones {
+        bags.push(Bag {
+            generator_index: generators.len() as u16,
+            modulator_index: modulators.len() as u16,
+        });
+        generators.extend(&zone.generators);
+        modulators.extend(&zone.modulators);
+    }
+}
+
+fn push_terminal_zone(
+    bags: &mut Vec<Bag>,
+    generators: &mut Vec<Generator>,
+    modulators: &mut Vec<Modulator>,
+) {
+    bags.push(Bag {
+        generator_index: generators.len() as u16,
+        modulator_index: modulators.len() as u16,
+    });
+    generators.push(Generator::default());
+    modulators.push(Modulator::default());
+}
+
+impl SoundFont {
+    pub fn get_info_mut(&mut self) -> &mut SoundFontInfo {
+        &mut self.info
+    }
+
+    pub fn rename_preset(
+        &mut self,
+        preset_index: usize,
+        name: &str,
+    ) -> Result<(), SoundFontEditError> {
+        self.check_preset(preset_index)?;
+        self.hydra.preset_headers[preset_index].name = name.into();
+        Ok(())
+    }
+
+    /// Move a preset to another bank and program number.
+    pub fn remap_preset(
+        &mut self,
+        preset_index: usize,
+        bank: u16,
+        program: u16,
+    ) -> Result<(), SoundFontEditError> {
+        self.check_preset(preset_index)?;
+        if let Some(existing) = self.find_preset_number(bank, program) {
+            if existing != preset_index {
+                return Err(SoundFontEditError::PresetExists { bank, program });
+            }
+        }
+        let header = &mut self.hydra.preset_headers[preset_index];
+        header.bank = bank;
+        header.preset = program;
+        Ok(())
+    }
+
+    /// Remove a preset. Its instruments and samples stay, see `remove_unused`.
+    pub fn remove_preset(&mut self, preset_index: usize) -> Result<(), SoundFontEditError> {
+        self.check_preset(preset_index)?;
+        let mut presets = self.hydra.get_preset_records();
+        presets.remove(preset_index);
+        let instruments = self.hydra.get_instrument_records();
+        let sample_headers = without_terminal(&self.hydra.sample_headers);
+        self.hydra = Hydra::from_records(&presets, &instruments, sample_headers);
+        Ok(())
+    }
+
+    /// Remove instruments no preset uses, and samples no remaining instrument uses. Returns the
+    /// number of removed instruments and samples.
+    pub fn remove_unused(&mut self) -> (usize, usize) {
+        let instrument_count = self.hydra.instruments.len();
+        let sample_count = self.hydra.sample_headers.len();
+
+        let mut rebuild = Rebuild::default();
+        rebuild.add(self, self.hydra.get_preset_records(), true);
+        (self.hydra, self.sample_data) = rebuild.finish();
+
+        (
+            instrument_count - self.hydra.instruments.len(),
+            sample_count - self.hydra.sample_headers.len(),
+        )
+    }
+
+    /// Copy presets from another SoundFont, with the instruments and samples they use. Bank and
+    /// program numbers are kept, and must not be taken.
+    pub fn merge_presets(
+        &mut self,
+        other: &SoundFont,
+        preset_indices: &[usize],
+    ) -> Result<(), SoundFontEditError> {
+        let other_presets = other.hydra.get_preset_records();
+        let mut merged = vec![];
+        for index in preset_indices {
+            let preset = other_presets
+                .get(*index)
+                .ok_or(SoundFontEditError::NoSuchPreset(*index))?;
+            let (bank, program) = (preset.header.bank, preset.header.preset);
+            let taken_in_merge = merged
+                .iter()
+                .any(|p: &PresetRecords| p.header.bank == bank && p.header.preset == program);
+            if self.find_preset_number(bank, program).is_some() || taken_in_merge {
+                return Err(SoundFontEditError::PresetExists { bank, program });
+            }
+            merged.push(preset.clone());
+        }
+
+        let mut rebuild = Rebuild::default();
+        rebuild.add(self, self.hydra.get_preset_records(), false);
+        rebuild.add(other, merged, true);
+        (self.hydra, self.sample_data) = rebuild.finish();
+        Ok(())
+    }
+
+    fn check_preset(&self, preset_index: usize) -> Result<(), SoundFontEditError> {
+        match preset_index + 1 < self.hydra.preset_headers.len() {
+            true => Ok(()),
+            false => Err(SoundFontEditError::NoSuchPreset(preset_index)),
+        }
+    }
+
+    fn find_preset_number(&self, bank: u16, program: u16) -> Option<usize> {
+        let headers = &self.hydra.preset_headers;
+        headers
+            .iter()
+            .take(headers.len().saturating_sub(1))
+            .position(|header| header.bank == bank && header.preset == program)
+    }
+}
+
+/// Collects presets, instruments and samples from one or more SoundFonts into a new hydra and
+/// sample data.
+#[derive(Default)]
+struct Rebuild {
+    presets: Vec<PresetRecords>,
+    instruments: Vec<InstrumentRecords>,
+    sample_headers: Vec<SampleHeader>,
+    smpl: Vec<i16>,
+    sm24: Vec<u8>,
+    has_sm24: bool,
+}
+
+impl Rebuild {
+    /// Add presets from a SoundFont. If `only_used`, instruments and samples the presets don't
+    /// use are left out.
+    fn add(&mut self, font: &SoundFont, mut presets: Vec<PresetRecords>, only_used: bool) {
+        let instruments = font.hydra.get_instrument_records();
+        let sample_headers = without_terminal(&font.hydra.sample_headers);
+
+        let instrument_indices = match only_used {
+            true => used_instruments(&presets, instruments.len()),
+            false => (0..instruments.len()).collect(),
+        };
+        let mut new_instruments: Vec<InstrumentRecords> = instrument_indices
+            .iter()
+            .map(|i| instruments[*i].clone())
+            .collect();
+        let sample_indices = match only_used {
+            true => used_samples(&new_instruments, &sample_headers),
+            false => (0..sample_headers.len()).collect(),
+        };
+
+        let instrument_map = index_map(&instrument_indices, self.instruments.len());
+        let sample_map = index_map(&sample_indices, self.sample_headers.len());
+        for preset in &mut presets {
+            remap(
+                &mut preset.zones,
+                GeneratorType::Instrument,
+                &instrument_map,
+            );
+        }
+        for instrument in &mut new_instruments {
+            remap(&mut instrument.zones, GeneratorType::SampleId, &sample_map);
+        }
+
+        for index in sample_indices {
+            let mut header = self.copy_sample(&font.sample_data, &sample_headers[index]);
+            // Mono samples have no link.
+            if header.get_link() != Some(SampleLink::Mono) {
+                let link = sample_map.get(&(header.sample_link as usize));
+                header.sample_link = link.map_or(MISSING_INDEX, |link| *link as u16);
+            }
+            self.sample_headers.push(header);
+        }
+
+        self.presets.extend(presets);
+        self.instruments.extend(new_instruments);
+    }
+
+    /// Append the points of a sample. Returns the header with updated positions.
+    fn copy_sample(&mut self, sample_data: &SampleData, header: &SampleHeader) -> SampleHeader {
+        if header.is_rom() {
+            return header.clone();
+        }
+
+        let points = sample_data.get_points(header);
+        // Where the points start, clamped like `get_points` does.
+        let old_end = (header.end as usize).min(sample_data.get_sample_count());
+        let old_start = (header.start as usize).min(old_end);
+        let new_start = self.smpl.len();
+        self.smpl.extend(points);
+        if let Some(sm24) = sample_data.get_sm24() {
+            self.has_sm24 = true;
+            if let Some(low) = sm24.get(old_start..old_start + points.len()) {
+                self.sm24.extend(low);
+            }
+        }
+        // Missing low bytes are zero.
+        self.sm24.resize(self.smpl.len(), 0);
+        self.smpl.resize(self.smpl.len() + SAMPLE_PADDING, 0);
+        self.sm24.resize(self.smpl.len(), 0);
+
+        let shift =
+            |position: u32| (position as i64 - old_start as i64 + new_start as i64).max(0) as u32;
+        SampleHeader {
+            start: new_start as u32,
+            end: (new_start + points.len()) as u32,
+            start_loop: shift(header.start_loop),
+            end_loop: shift(header.end_loop),
+            ..header.clone()
+        }
+    }
+
+    fn finish(self) -> (Hydra, SampleData) {
+        let hydra = Hydra::from_records(&self.presets, &self.instruments, self.sample_headers);
+        let sm24 = self.has_sm24.then_some(self.sm24);
+        (hydra, SampleData::new(self.smpl, sm24))
+    }
+}
+
+fn without_terminal<T: Clone>(records: &[T]) -> Vec<T> {
+    records[..records.len().saturating_sub(1)].to_vec()
+}
+
+/// Indices referred to by generators of a type, that exist.
+fn referenced(zones: &[ZoneRecords], generator_type: GeneratorType, count: usize) -> Vec<usize> {
+    zones
+        .iter()
+        .flat_map(|zone| &zone.generators)
+        .filter(|generator| generator.oper == u16::from(generator_type))
+        .map(|generator| generator.amount.as_u16() as usize)
+        .filter(|index| *index < count)
+        .collect()
+}
+
+fn used_instruments(presets: &[PresetRecords], instrument_count: usize) -> Vec<usize> {
+    let mut used: Vec<usize> = presets
+        .iter()
+        .flat_map(|preset| referenced(&preset.zones, GeneratorType::Instrument, instrument_count))
+        .collect();
+    used.sort();
+    used.dedup();
+    used
+}
+
+/// Samples used by instruments, and the other halves of stereo pairs.
+fn used_samples(instruments: &[InstrumentRecords], sample_headers: &[SampleHeader]) -> Vec<usize> {
+    let count = sample_headers.len();
+    let mut used: Vec<usize> = instruments
+        .iter()
+        .flat_map(|instrument| referenced(&instrument.zones, GeneratorType::SampleId, count))
+        .collect();
+    let links: Vec<usize> = used
+        .iter()
+        .map(|index| &sample_headers[*index])
+        .filter(|header| header.get_link() != Some(SampleLink::Mono))
+        .map(|header| header.sample_link as usize)
+        .filter(|link| *link < count)
+        .collect();
+    used.extend(links);
+    used.sort();
+    used.dedup();
+    used
+}
+
+/// Old index to new index, for records placed after `offset` existing records.
+fn index_map(old_indices: &[usize], offset: usize) -> HashMap<usize, usize> {
+    old_indices
+        .iter()
+        .enumerate()
+        .map(|(new, old)| (*old, offset + new))
+        .collect()
+}
+
+/// Update references. References to records that were not carried over point to
+/// [`MISSING_INDEX`], so they can't end up on an unrelated record.
+fn remap(zones: &mut [ZoneRecords], generator_type: GeneratorType, map: &HashMap<usize, usize>) {
+    for generator in zones.iter_mut().flat_map(|zone| &mut zone.generators) {
+        if generator.oper != u16::from(generator_type) {
+            continue;
+        }
+        let new = map.get(&(generator.amount.as_u16() as usize));
+        let new = new.map_or(MISSING_INDEX, |new| *new as u16);
+        generator.amount = GeneratorAmount::from_u16(new);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::keys::MidiKey;
+
+    fn zone(generator_type: GeneratorType, index: u16) -> ZoneRecords {
+        ZoneRecords {
+            generators: vec![Generator::new(
+                generator_type,
+                GeneratorAmount::from_u16(index),
+            )],
+            modulators: vec![],
+        }
+    }
+
+    fn preset(name: &str, program: u16, instrument: u16) -> PresetRecords {
+        PresetRecords {
+            header: PresetHeader {
+                name: name.into(),
+                preset: program,
+                ..Default::default()
+            },
+            zones: vec![zone(GeneratorType::Instrument, instrument)],
+        }
+    }
+
+    fn instrument(name: &str, sample: u16) -> InstrumentRecords {
+        InstrumentRecords {
+            name: name.into(),
+            zones: vec![zone(GeneratorType::SampleId, sample)],
+        }
+    }
+
+    fn sample(name: &str, start: u32, len: u32) -> SampleHeader {
+        SampleHeader::test(name, start, start + len)
+    }
+
+    /// Presets 0 and 1 use instruments 2 and 0. Instrument 1 and sample 0 are unused.
+    fn test_font() -> SoundFont {
+        let presets = [preset("A", 0, 2), preset("B", 1, 0)];
+        let instruments = [
+            instrument("I0", 2),
+            instrument("I1", 0),
+            instrument("I2", 1),
+        ];
+        let samples = vec![sample("S0", 0, 4), sample("S1", 4, 4), sample("S2", 8, 4)];
+        let smpl = (0..12).collect();
+        SoundFont::new(
+            Default::default(),
+            SampleData::new(smpl, None),
+            Hydra::from_records(&presets, &instruments, samples),
+        )
+    }
+
+    fn sample_points(font: &SoundFont, program: u8) -> Vec<i16> {
+        let region = &font.get_regions(0, program, MidiKey::C5, 100)[0];
+        let header = &font.get_hydra().sample_headers[region.get_sample_index()];
+        font.get_sample_data().get_points(header).to_vec()
+    }
+
+    #[test]
+    fn test_records_roundtrip() {
+        let font = test_font();
+        let hydra = font.get_hydra();
+        let rebuilt = Hydra::from_records(
+            &hydra.get_preset_records(),
+            &hydra.get_instrument_records(),
+            without_terminal(&hydra.sample_headers),
+        );
+        assert_eq!(&rebuilt, hydra);
+    }
+
+    #[test]
+    fn test_rename_and_remap() {
+        let mut font = test_font();
+        font.rename_preset(0, "Renamed").unwrap();
+        assert_eq!(font.get_hydra().preset_headers[0].name, "Renamed");
+
+        assert!(matches!(
+            font.remap_preset(0, 0, 1),
+            Err(SoundFontEditError::PresetExists {
+                bank: 0,
+                program: 1
+            })
+        ));
+        font.remap_preset(0, 8, 5).unwrap();
+        assert_eq!(font.find_preset(8, 5), Some(0));
+        assert_eq!(sample_points(&font, 1), vec![8, 9, 10, 11]);
+
+        assert!(matches!(
+            font.rename_preset(2, "EOP"),
+            Err(SoundFontEditError::NoSuchPreset(2))
+        ));
+    }
+
+    #[test]
+    fn test_remove_unused() {
+        let mut font = test_font();
+        assert_eq!(font.remove_unused(), (1, 1));
+        assert_eq!(sample_points(&font, 0), vec![4, 5, 6, 7]);
+        assert_eq!(sample_points(&font, 1), vec![8, 9, 10, 11]);
+
+        let header = &font.get_hydra().sample_headers[0];
+        assert_eq!(header.start_loop - header.start, 1);
+        assert_eq!(font.get_sample_data().get_sample_count(), 2 * (4 + 46));
+
+        font.remove_preset(0).unwrap();
+        assert_eq!(font.remove_unused(), (1, 1));
+        assert_eq!(sample_points(&font, 1), vec![8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut font = test_font();
+        let mut other = test_font();
+        other.remap_preset(0, 1, 0).unwrap();
+
+        assert!(matches!(
+            font.merge_presets(&other, &[1]),
+            Err(SoundFontEditError::PresetExists {
+                bank: 0,
+                program: 1
+            })
+        ));
+
+        font.merge_presets(&other, &[0]).unwrap();
+        let hydra = font.get_hydra();
+        assert_eq!(hydra.preset_headers.len(), 4);
+        assert_eq!(hydra.instruments.len(), 5);
+        assert_eq!(hydra.sample_headers.len(), 5);
+
+        let region = &font.get_regions(1, 0, MidiKey::C5, 100)[0];
+        let header = &hydra.sample_headers[region.get_sample_index()];
+        assert_eq!(font.get_sample_data().get_points(header), &[4, 5, 6, 7]);
+
+        let mut bytes = vec![];
+        font.write(&mut bytes).unwrap();
+        let reread = SoundFont::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(reread.get_hydra(), font.get_hydra());
+        assert_eq!(reread.get_sample_data(), font.get_sample_data());
+    }
+
+    #[test]
+    fn test_sample_past_data() {
+        // 24-bit sample data, and a sample header past its end
+        let samples = vec![sample("S0", 0, 4), sample("Broken", 100, 4)];
+        let mut font = SoundFont::new(
+            Default::default(),
+            SampleData::new((0..12).collect(), Some(vec![1; 12])),
+            Hydra::from_records(
+                &[preset("A", 0, 0), preset("B", 1, 1)],
+                &[instrument("I0", 0), instrument("I1", 1)],
+                samples,
+            ),
+        );
+        assert_eq!(font.remove_unused(), (0, 0));
+        assert_eq!(sample_points(&font, 0), vec![0, 1, 2, 3]);
+        assert!(sample_points(&font, 1).is_empty());
+        let sample_data = font.get_sample_data();
+        assert_eq!(sample_data.get_point_24(1), 1 << 8 | 1);
+        assert_eq!(
+            sample_data.get_sm24().unwrap().len(),
+            sample_data.get_sample_count()
+        );
+    }
+
+    #[test]
+    fn test_missing_references() {
+        // Preset A uses a missing instrument, and instrument I1 a missing sample.
+        let mut font = SoundFont::new(
+            Default::default(),
+            SampleData::new((0..4).collect(), None),
+            Hydra::from_records(
+                &[preset("A", 0, 5), preset("B", 1, 1)],
+                &[instrument("I0", 0), instrument("I1", 9)],
+                vec![sample("S0", 0, 4)],
+            ),
+        );
+        assert_eq!(font.remove_unused(), (1, 1));
+        let hydra = font.get_hydra();
+        let records = hydra.get_preset_records();
+        assert_eq!(
+            records[0].zones[0].generators[0].amount.as_u16(),
+            MISSING_INDEX
+        );
+        let records = hydra.get_instrument_records();
+        assert_eq!(
+            records[0].zones[0].generators[0].amount.as_u16(),
+            MISSING_INDEX
+        );
+        let missing = font
+            .validate()
+            .into_iter()
+            .filter(|issue| issue.message.contains("does not exist"))
+            .count();
+        assert_eq!(missing, 2);
+    }
+}
@@ -0,0 +1,302 @@
+//! Generators (`pgen`, `igen`)
+//!
+//! A generator sets one synthesis parameter of a zone. At instrument level the value is absolute,
+//! at preset level it's added to the instrument value.
+
+use std::{error::Error, fmt::Display};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GeneratorType {
+    StartAddrsOffset = 0,
+    EndAddrsOffset = 1,
+    StartloopAddrsOffset = 2,
+    EndloopAddrsOffset = 3,
+    StartAddrsCoarseOffset = 4,
+    ModLfoToPitch = 5,
+    VibLfoToPitch = 6,
+    ModEnvToPitch = 7,
+    InitialFilterFc = 8,
+    InitialFilterQ = 9,
+    ModLfoToFilterFc = 10,
+    ModEnvToFilterFc = 11,
+    EndAddrsCoarseOffset = 12,
+    ModLfoToVolume = 13,
+    Unused1 = 14,
+    ChorusEffectsSend = 15,
+    ReverbEffectsSend = 16,
+    Pan = 17,
+    Unused2 = 18,
+    Unused3 = 19,
+    Unused4 = 20,
+    DelayModLfo = 21,
+    FreqModLfo = 22,
+    DelayVibLfo = 23,
+    FreqVibLfo = 24,
+    DelayModEnv = 25,
+    AttackModEnv = 26,
+    HoldModEnv = 27,
+    DecayModEnv = 28,
+    SustainModEnv = 29,
+    ReleaseModEnv = 30,
+    KeynumToModEnvHold = 31,
+    KeynumToModEnvDecay = 32,
+    DelayVolEnv = 33,
+    AttackVolEnv = 34,
+    HoldVolEnv = 35,
+    DecayVolEnv = 36,
+    SustainVolEnv = 37,
+    ReleaseVolEnv = 38,
+    KeynumToVolEnvHold = 39,
+    KeynumToVolEnvDecay = 40,
+    /// Preset zones only. Index of the instrument the zone plays.
+    Instrument = 41,
+    Reserved1 = 42,
+    KeyRange = 43,
+    VelRange = 44,
+    StartloopAddrsCoarseOffset = 45,
+    Keynum = 46,
+    Velocity = 47,
+    InitialAttenuation = 48,
+    Reserved2 = 49,
+    EndloopAddrsCoarseOffset = 50,
+    CoarseTune = 51,
+    FineTune = 52,
+    /// Instrument zones only. Index of the sample the zone plays.
+    SampleId = 53,
+    SampleModes = 54,
+    Reserved3 = 55,
+    ScaleTuning = 56,
+    ExclusiveClass = 57,
+    OverridingRootKey = 58,
+    Unused5 = 59,
+    EndOper = 60,
+}
+
+/// Number of defined generator types
+pub const GENERATOR_COUNT: usize = 61;
+
+#[derive(Debug)]
+pub enum GeneratorTypeError {
+    NotAGenerator(u16),
+}
+impl Error for GeneratorTypeError {}
+impl Display for GeneratorTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotAGenerator(val) => write!(f, "Generator type out of range: {val}"),
+        }
+    }
+}
+
+impl TryFrom<u16> for GeneratorType {
+    type Error = GeneratorTypeError;
+
+    fn try_from(v: u16) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(Self::StartAddrsOffset),
+            1 => Ok(Self::EndAddrsOffset),
+            2 => Ok(Self::StartloopAddrsOffset),
+            3 => Ok(Self::EndloopAddrsOffset),
+            4 => Ok(Self::StartAddrsCoarseOffset),
+            5 => Ok(Self::ModLfoToPitch),
+            6 => Ok(Self::VibLfoToPitch),
+            7 => Ok(Self::ModEnvToPitch),
+            8 => Ok(Self::InitialFilterFc),
+            9 => Ok(Self::InitialFilterQ),
+            10 => Ok(Self::ModLfoToFilterFc),
+            11 => Ok(Self::ModEnvToFilterFc),
+            12 => Ok(Self::EndAddrsCoarseOffset),
+            13 => Ok(Self::ModLfoToVolume),
+            14 => Ok(Self::Unused1),
+            15 => Ok(Self::ChorusEffectsSend),
+            16 => Ok(Self::ReverbEffectsSend),
+            17 => Ok(Self::Pan),
+            18 => Ok(Self::Unused2),
+            19 => Ok(Self::Unused3),
+            20 => Ok(Self::Unused4),
+            21 => Ok(Self::DelayModLfo),
+            22 => Ok(Self::FreqModLfo),
+            23 => Ok(Self::DelayVibLfo),
+            24 => Ok(Self::FreqVibLfo),
+            25 => Ok(Self::DelayModEnv),
+            26 => Ok(Self::AttackModEnv),
+            27 => Ok(Self::HoldModEnv),
+            28 => Ok(Self::DecayModEnv),
+            29 => Ok(Self::SustainModEnv),
+            30 => Ok(Self::ReleaseModEnv),
+            31 => Ok(Self::KeynumToModEnvHold),
+            32 => Ok(Self::KeynumToModEnvDecay),
+            33 => Ok(Self::DelayVolEnv),
+            34 => Ok(Self::AttackVolEnv),
+            35 => Ok(Self::HoldVolEnv),
+            36 => Ok(Self::DecayVolEnv),
+            37 => Ok(Self::SustainVolEnv),
+            38 => Ok(Self::ReleaseVolEnv),
+            39 => Ok(Self::KeynumToVolEnvHold),
+            40 => Ok(Self::KeynumToVolEnvDecay),
+            41 => Ok(Self::Instrument),
+            42 => Ok(Self::Reserved1),
+            43 => Ok(Self::KeyRange),
+            44 => Ok(Self::VelRange),
+            45 => Ok(Self::StartloopAddrsCoarseOffset),
+            46 => Ok(Self::Keynum),
+            47 => Ok(Self::Velocity),
+            48 => Ok(Self::InitialAttenuation),
+            49 => Ok(Self::Reserved2),
+            50 => Ok(Self::EndloopAddrsCoarseOffset),
+            51 => Ok(Self::CoarseTune),
+            52 => Ok(Self::FineTune),
+            53 => Ok(Self::SampleId),
+            54 => Ok(Self::SampleModes),
+            55 => Ok(Self::Reserved3),
+            56 => Ok(Self::ScaleTuning),
+            57 => Ok(Self::ExclusiveClass),
+            58 => Ok(Self::OverridingRootKey),
+            59 => Ok(Self::Unused5),
+            60 => Ok(Self::EndOper),
+            _ => Err(GeneratorTypeError::NotAGenerator(v)),
+        }
+    }
+}
+
+impl From<GeneratorType> for u16 {
+    fn from(generator_type: GeneratorType) -> Self {
+        generator_type as u16
+    }
+}
+
+impl GeneratorType {
+    /// Value used when no zone sets the generator (SF2.04 section 8.1.3).
+    pub fn default_value(&self) -> i32 {
+        match self {
+            Self::InitialFilterFc => 13500,
+            Self::DelayModLfo
+            | Self::DelayVibLfo
+            | Self::DelayModEnv
+            | Self::AttackModEnv
+            | Self::HoldModEnv
+            | Self::DecayModEnv
+            | Self::ReleaseModEnv
+            | Self::DelayVolEnv
+            | Self::AttackVolEnv
+            | Self::HoldVolEnv
+            | Self::DecayVolEnv
+            | Self::ReleaseVolEnv => -12000,
+            Self::KeyRange | Self::VelRange => 0x7F00,
+            Self::Keynum | Self::Velocity | Self::OverridingRootKey => -1,
+            Self::ScaleTuning => 100,
+            _ => 0,
+        }
+    }
+
+    /// Amount is a lo-hi byte pair instead of a number.
+    pub fn is_range(&self) -> bool {
+        matches!(self, Self::KeyRange | Self::VelRange)
+    }
+
+    /// Amount is unsigned.
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, Self::Instrument | Self::SampleId | Self::SampleModes)
+    }
+
+    /// Generators that describe the sample itself may only appear in instrument zones.
+    pub fn is_instrument_only(&self) -> bool {
+        matches!(
+            self,
+            Self::StartAddrsOffset
+                | Self::EndAddrsOffset
+                | Self::StartloopAddrsOffset
+                | Self::EndloopAddrsOffset
+                | Self::StartAddrsCoarseOffset
+                | Self::EndAddrsCoarseOffset
+                | Self::StartloopAddrsCoarseOffset
+                | Self::Keynum
+                | Self::Velocity
+                | Self::EndloopAddrsCoarseOffset
+                | Self::SampleId
+                | Self::SampleModes
+                | Self::ExclusiveClass
+                | Self::OverridingRootKey
+        )
+    }
+
+    /// Unused and reserved types, which must be ignored.
+    pub fn is_unused(&self) -> bool {
+        matches!(
+            self,
+            Self::Unused1
+                | Self::Unused2
+                | Self::Unused3
+                | Self::Unused4
+                | Self::Unused5
+                | Self::Reserved1
+                | Self::Reserved2
+                | Self::Reserved3
+                | Self::EndOper
+        )
+    }
+}
+
+/// 16-bit generator amount. Depending on the generator it's signed, unsigned or a byte range.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct GeneratorAmount(u16);
+
+impl GeneratorAmount {
+    pub fn from_i16(v: i16) -> Self {
+        Self(v as u16)
+    }
+    pub fn from_u16(v: u16) -> Self {
+        Self(v)
+    }
+    pub fn from_range(lo: u8, hi: u8) -> Self {
+        Self(u16::from_le_bytes([lo, hi]))
+    }
+
+    pub fn as_i16(&self) -> i16 {
+        self.0 as i16
+    }
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+    /// (lo, hi)
+    pub fn as_range(&self) -> (u8, u8) {
+        let [lo, hi] = self.0.to_le_bytes();
+        (lo, hi)
+    }
+}
+
+/// Generator record. The type is kept raw, so that unknown types survive a round trip.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct Generator {
+    pub oper: u16,
+    pub amount: GeneratorAmount,
+}
+
+impl Generator {
+    pub const RECORD_SIZE: usize = 4;
+
+    pub fn new(generator_type: GeneratorType, amount: GeneratorAmount) -> Self {
+        Self {
+            oper: generator_type.into(),
+            amount,
+        }
+    }
+
+    pub fn parse(b: &[u8]) -> Self {
+        Self {
+            oper: u16::from_le_bytes([b[0], b[1]]),
+            amount: GeneratorAmount(u16::from_le_bytes([b[2], b[3]])),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.oper.to_le_bytes().to_vec();
+        bytes.extend(self.amount.0.to_le_bytes());
+        bytes
+    }
+
+    /// `None` if the type is not defined by the spec.
+    pub fn get_type(&self) -> Option<GeneratorType> {
+        GeneratorType::try_from(self.oper).ok()
+    }
+}
@@ -0,0 +1,307 @@
+//! The "hydra": nine `pdta` sub-chunks describing presets, instruments and samples.
+//!
+//! Records are stored as they appear in the file, including the terminal records. Presets and
+//! instruments own a range of bags (zones), which own ranges of generators and modulators. Each
+//! range ends where the next record's range begins.
+
+use std::ops::Range;
+
+use super::{
+    generator::{Generator, GeneratorAmount, GeneratorType},
+    modulator::Modulator,
+    riff::{parse_zstr, write_zstr, RiffChunk},
+    sample::SampleHeader,
+    SoundFontError,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct PresetHeader {
+    pub name: String,
+    /// MIDI program number
+    pub preset: u16,
+    pub bank: u16,
+    pub bag_index: u16,
+    pub library: u32,
+    pub genre: u32,
+    pub morphology: u32,
+}
+
+impl PresetHeader {
+    pub const RECORD_SIZE: usize = 38;
+
+    pub fn parse(b: &[u8]) -> Self {
+        let u32_at = |i: usize| u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+        Self {
+            name: parse_zstr(&b[0..20]),
+            preset: u16::from_le_bytes([b[20], b[21]]),
+            bank: u16::from_le_bytes([b[22], b[23]]),
+            bag_index: u16::from_le_bytes([b[24], b[25]]),
+            library: u32_at(26),
+            genre: u32_at(30),
+            morphology: u32_at(34),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = write_zstr(&self.name, 20);
+        bytes.extend(self.preset.to_le_bytes());
+        bytes.extend(self.bank.to_le_bytes());
+        bytes.extend(self.bag_index.to_le_bytes());
+        bytes.extend(self.library.to_le_bytes());
+        bytes.extend(self.genre.to_le_bytes());
+        bytes.extend(self.morphology.to_le_bytes());
+        bytes
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct Instrument {
+    pub name: String,
+    pub bag_index: u16,
+}
+
+impl Instrument {
+    pub const RECORD_SIZE: usize = 22;
+
+    pub fn parse(b: &[u8]) -> Self {
+        Self {
+            name: parse_zstr(&b[0..20]),
+            bag_index: u16::from_le_bytes([b[20], b[21]]),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = write_zstr(&self.name, 20);
+        bytes.extend(self.bag_index.to_le_bytes());
+        bytes
+    }
+}
+
+/// A zone: the first generator and modulator that belong to it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct Bag {
+    pub generator_index: u16,
+    pub modulator_index: u16,
+}
+
+impl Bag {
+    pub const RECORD_SIZE: usize = 4;
+
+    pub fn parse(b: &[u8]) -> Self {
+        Self {
+            generator_index: u16::from_le_bytes([b[0], b[1]]),
+            modulator_index: u16::from_le_bytes([b[2], b[3]]),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.generator_index.to_le_bytes().to_vec();
+        bytes.extend(self.modulator_index.to_le_bytes());
+        bytes
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Hydra {
+    /// `phdr`
+    pub preset_headers: Vec<PresetHeader>,
+    /// `pbag`
+    pub preset_bags: Vec<Bag>,
+    /// `pmod`
+    pub preset_modulators: Vec<Modulator>,
+    /// `pgen`
+    pub preset_generators: Vec<Generator>,
+    /// `inst`
+    pub instruments: Vec<Instrument>,
+    /// `ibag`
+    pub instrument_bags: Vec<Bag>,
+    /// `imod`
+    pub instrument_modulators: Vec<Modulator>,
+    /// `igen`
+    pub instrument_generators: Vec<Generator>,
+    /// `shdr`
+    pub sample_headers: Vec<SampleHeader>,
+}
+
+impl Hydra {
+    pub fn parse(list: &RiffChunk) -> Result<Self, SoundFontError> {
+        let subchunks = list.subchunks()?;
+        let find = |id: &[u8; 4]| {
+            subchunks
+                .iter()
+                .find(|chunk| &chunk.get_id() == id)
+                .ok_or(SoundFontError::MissingChunk(*id))
+        };
+
+        Ok(Self {
+            preset_headers: parse_records(
+                find(b"phdr")?,
+                PresetHeader::RECORD_SIZE,
+                PresetHeader::parse,
+            )?,
+            preset_bags: parse_records(find(b"pbag")?, Bag::RECORD_SIZE, Bag::parse)?,
+            preset_modulators: parse_records(
+                find(b"pmod")?,
+                Modulator::RECORD_SIZE,
+                Modulator::parse,
+            )?,
+            preset_generators: parse_records(
+                find(b"pgen")?,
+                Generator::RECORD_SIZE,
+                Generator::parse,
+            )?,
+            instruments: parse_records(find(b"inst")?, Instrument::RECORD_SIZE, Instrument::parse)?,
+            instrument_bags: parse_records(find(b"ibag")?, Bag::RECORD_SIZE, Bag::parse)?,
+            instrument_modulators: parse_records(
+                find(b"imod")?,
+                Modulator::RECORD_SIZE,
+                Modulator::parse,
+            )?,
+            instrument_generators: parse_records(
+                find(b"igen")?,
+                Generator::RECORD_SIZE,
+                Generator::parse,
+            )?,
+            sample_headers: parse_records(
+                find(b"shdr")?,
+                SampleHeader::RECORD_SIZE,
+                SampleHeader::parse,
+            )?,
+        })
+    }
+}
+
+impl Hydra {
+    /// `pdta` list with the records as they are. Terminal records are not added.
+    pub fn to_chunk(&self) -> RiffChunk {
+        RiffChunk::new_list(
+            *b"LIST",
+            *b"pdta",
+            &[
+                records_chunk(*b"phdr", &self.preset_headers, PresetHeader::to_bytes),
+                records_chunk(*b"pbag", &self.preset_bags, Bag::to_bytes),
+                records_chunk(*b"pmod", &self.preset_modulators, Modulator::to_bytes),
+                records_chunk(*b"pgen", &self.preset_generators, Generator::to_bytes),
+                records_chunk(*b"inst", &self.instruments, Instrument::to_bytes),
+                records_chunk(*b"ibag", &self.instrument_bags, Bag::to_bytes),
+                records_chunk(*b"imod", &self.instrument_modulators, Modulator::to_bytes),
+                records_chunk(*b"igen", &self.instrument_generators, Generator::to_bytes),
+                records_chunk(*b"shdr", &self.sample_headers, SampleHeader::to_bytes),
+            ],
+        )
+    }
+}
+
+fn records_chunk<T>(id: [u8; 4], records: &[T], to_bytes: fn(&T) -> Vec<u8>) -> RiffChunk {
+    RiffChunk::new(id, records.iter().flat_map(to_bytes).collect())
+}
+
+/// Generators and modulators of one bag.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Zone<'a> {
+    pub generators: &'a [Generator],
+    pub modulators: &'a [Modulator],
+}
+
+impl Zone<'_> {
+    /// Amount of a generator. If it appears more than once, the last one is used.
+    pub fn find(&self, generator_type: GeneratorType) -> Option<GeneratorAmount> {
+        self.generators
+            .iter()
+            .rev()
+            .find(|generator| generator.oper == u16::from(generator_type))
+            .map(|generator| generator.amount)
+    }
+
+    /// Amount of the last generator, if it's of type `terminal`. Local preset zones end with
+    /// `Instrument`, local instrument zones with `SampleId`.
+    pub fn find_terminal(&self, terminal: GeneratorType) -> Option<GeneratorAmount> {
+        self.generators
+            .last()
+            .filter(|generator| generator.oper == u16::from(terminal))
+            .map(|generator| generator.amount)
+    }
+}
+
+impl Hydra {
+    /// Zones of a preset. Empty if the preset has no following record to end its bag range.
+    pub fn get_preset_zones(&self, preset_index: usize) -> Vec<Zone<'_>> {
+        let (Some(preset), Some(next)) = (
+            self.preset_headers.get(preset_index),
+            self.preset_headers.get(preset_index + 1),
+        ) else {
+            return vec![];
+        };
+        zones(
+            &self.preset_bags,
+            preset.bag_index..next.bag_index,
+            &self.preset_generators,
+            &self.preset_modulators,
+        )
+    }
+
+    /// Zones of an instrument. Empty if the instrument has no following record to end its bag
+    /// range.
+    pub fn get_instrument_zones(&self, instrument_index: usize) -> Vec<Zone<'_>> {
+        let (Some(instrument), Some(next)) = (
+            self.instruments.get(instrument_index),
+            self.instruments.get(instrument_index + 1),
+        ) else {
+            return vec![];
+        };
+        zones(
+            &self.instrument_bags,
+            instrument.bag_index..next.bag_index,
+            &self.instrument_generators,
+            &self.instrument_modulators,
+        )
+    }
+}
+
+/// Out of range indices are clamped, so malformed files produce empty zones instead of panics.
+fn zones<'a>(
+    bags: &[Bag],
+    bag_range: Range<u16>,
+    generators: &'a [Generator],
+    modulators: &'a [Modulator],
+) -> Vec<Zone<'a>> {
+    bag_range
+        .filter_map(|i| {
+            let bag = bags.get(i as usize)?;
+            let next = bags.get(i as usize + 1);
+            Some(Zone {
+                generators: clamped_slice(
+                    generators,
+                    bag.generator_index,
+                    next.map(|next| next.generator_index),
+                ),
+                modulators: clamped_slice(
+                    modulators,
+                    bag.modulator_index,
+                    next.map(|next| next.modulator_index),
+                ),
+            })
+        })
+        .collect()
+}
+
+fn clamped_slice<T>(items: &[T], start: u16, end: Option<u16>) -> &[T] {
+    let end = end.map_or(items.len(), |end| (end as usize).min(items.len()));
+    let start = (start as usize).min(end);
+    &items[start..end]
+}
+
+fn parse_records<T>(
+    chunk: &RiffChunk,
+    record_size: usize,
+    parse: fn(&[u8]) -> T,
+) -> Result<Vec<T>, SoundFontError> {
+    let data = chunk.get_data();
+    if !data.len().is_multiple_of(record_size) {
+        return Err(SoundFontError::InvalidChunkSize {
+            id: chunk.get_id(),
+            size: data.len(),
+        });
+    }
+    Ok(data.chunks_exact(record_size).map(parse).collect())
+}
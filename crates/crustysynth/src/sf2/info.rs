@@ -0,0 +1,136 @@
+//! INFO list: version and descriptive text of a SoundFont.
+
+use std::fmt::Display;
+
+use super::{
+    riff::{parse_zstr, RiffChunk},
+    SoundFontError,
+};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct SoundFontVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+impl Display for SoundFontVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:02}", self.major, self.minor)
+    }
+}
+impl SoundFontVersion {
+    fn to_chunk(self, id: [u8; 4]) -> RiffChunk {
+        let mut data = self.major.to_le_bytes().to_vec();
+        data.extend(self.minor.to_le_bytes());
+        RiffChunk::new(id, data)
+    }
+
+    fn parse(chunk: &RiffChunk) -> Result<Self, SoundFontError> {
+        let data = chunk.get_data();
+        if data.len() != 4 {
+            return Err(SoundFontError::InvalidChunkSize {
+                id: chunk.get_id(),
+                size: data.len(),
+            });
+        }
+        Ok(Self {
+            major: u16::from_le_bytes([data[0], data[1]]),
+            minor: u16::from_le_bytes([data[2], data[3]]),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SoundFontInfo {
+    /// `ifil`
+    pub version: SoundFontVersion,
+    /// `isng`, usually "EMU8000"
+    pub sound_engine: String,
+    /// `INAM`
+    pub bank_name: String,
+    /// `irom`
+    pub rom_name: Option<String>,
+    /// `iver`
+    pub rom_version: Option<SoundFontVersion>,
+    /// `ICRD`
+    pub creation_date: Option<String>,
+    /// `IENG`
+    pub engineers: Option<String>,
+    /// `IPRD`
+    pub product: Option<String>,
+    /// `ICOP`
+    pub copyright: Option<String>,
+    /// `ICMT`
+    pub comments: Option<String>,
+    /// `ISFT`
+    pub software: Option<String>,
+}
+
+impl SoundFontInfo {
+    pub fn parse(list: &RiffChunk) -> Result<Self, SoundFontError> {
+        let mut version = None;
+        let mut info = Self {
+            sound_engine: "EMU8000".into(),
+            ..Default::default()
+        };
+
+        for chunk in list.subchunks()? {
+            let text = parse_zstr(chunk.get_data());
+            match &chunk.get_id() {
+                b"ifil" => version = Some(SoundFontVersion::parse(&chunk)?),
+                b"isng" => info.sound_engine = text,
+                b"INAM" => info.bank_name = text,
+                b"irom" => info.rom_name = Some(text),
+                b"iver" => info.rom_version = Some(SoundFontVersion::parse(&chunk)?),
+                b"ICRD" => info.creation_date = Some(text),
+                b"IENG" => info.engineers = Some(text),
+                b"IPRD" => info.product = Some(text),
+                b"ICOP" => info.copyright = Some(text),
+                b"ICMT" => info.comments = Some(text),
+                b"ISFT" => info.software = Some(text),
+                // Unknown chunks are ignored.
+                _ => (),
+            }
+        }
+
+        info.version = version.ok_or(SoundFontError::MissingChunk(*b"ifil"))?;
+        Ok(info)
+    }
+
+    pub fn to_chunk(&self) -> RiffChunk {
+        let mut chunks = vec![
+            self.version.to_chunk(*b"ifil"),
+            text_chunk(*b"isng", &self.sound_engine),
+            text_chunk(*b"INAM", &self.bank_name),
+        ];
+        if let Some(rom_name) = &self.rom_name {
+            chunks.push(text_chunk(*b"irom", rom_name));
+        }
+        if let Some(rom_version) = self.rom_version {
+            chunks.push(rom_version.to_chunk(*b"iver"));
+        }
+        let optional_texts = [
+            (b"ICRD", &self.creation_date),
+            (b"IENG", &self.engineers),
+            (b"IPRD", &self.product),
+            (b"ICOP", &self.copyright),
+            (b"ICMT", &self.comments),
+            (b"ISFT", &self.software),
+        ];
+        for (id, text) in optional_texts {
+            if let Some(text) = text {
+                chunks.push(text_chunk(*id, text));
+            }
+        }
+        RiffChunk::new_list(*b"LIST", *b"INFO", &chunks)
+    }
+}
+
+/// Zero-terminated text, padded to an even length.
+fn text_chunk(id: [u8; 4], text: &str) -> RiffChunk {
+    let mut data = text.as_bytes().to_vec();
+    data.push(0);
+    if data.len() % 2 == 1 {
+        data.push(0);
+    }
+    RiffChunk::new(id, data)
+}
@@ -0,0 +1,358 @@
+//! SoundFont 2 banks
+//!
+//! A SoundFont is a RIFF file of form `sfbk`, made of three lists:
+//! - `INFO`: version and descriptive text
+//! - `sdta`: sample data
+//! - `pdta`: presets, instruments and sample headers
+//!
+//! SF3 banks with Ogg Vorbis compressed samples are read with the `sf3` feature.
+
+use std::{error::Error, fmt::Display, fs::File, io, io::BufReader};
+
+use hydra::Hydra;
+use info::SoundFontInfo;
+use riff::{RiffChunk, RiffChunkError};
+use sample::SampleData;
+
+pub mod edit;
+pub mod generator;
+pub mod hydra;
+pub mod info;
+pub mod modulator;
+pub mod region;
+pub mod riff;
+pub mod sample;
+#[cfg(feature = "sf3")]
+mod sf3;
+pub mod validate;
+pub mod wav;
+
+#[derive(Debug)]
+pub enum SoundFontError {
+    IOError {
+        source: std::io::Error,
+    },
+    RiffError {
+        source: RiffChunkError,
+    },
+    NotASoundFont,
+    MissingChunk([u8; 4]),
+    InvalidChunkSize {
+        id: [u8; 4],
+        size: usize,
+    },
+    /// The SoundFont has compressed samples, and the `sf3` feature is disabled.
+    CompressedSamples,
+    SampleDecodeError {
+        sample: usize,
+        message: String,
+    },
+}
+impl Error for SoundFontError {}
+impl Display for SoundFontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IOError { source } => write!(f, "{source}"),
+            Self::RiffError { source } => write!(f, "{source}"),
+            Self::NotASoundFont => write!(f, "File is not a RIFF sfbk file."),
+            Self::MissingChunk(id) => write!(
+                f,
+                "SoundFont is missing required chunk: {}",
+                String::from_utf8_lossy(id)
+            ),
+            Self::InvalidChunkSize { id, size } => write!(
+                f,
+                "Invalid size for chunk {}: {size}",
+                String::from_utf8_lossy(id)
+            ),
+            Self::CompressedSamples => {
+                write!(f, "Compressed SF3 samples are not supported in this build.")
+            }
+            Self::SampleDecodeError { sample, message } => {
+                write!(f, "Failed to decode sample {sample}: {message}")
+            }
+        }
+    }
+}
+impl From<std::io::Error> for SoundFontError {
+    fn from(e: std::io::Error) -> Self {
+        Self::IOError { source: e }
+    }
+}
+impl From<RiffChunkError> for SoundFontError {
+    fn from(e: RiffChunkError) -> Self {
+        Self::RiffError { source: e }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SoundFont {
+    info: SoundFontInfo,
+    sample_data: SampleData,
+    hydra: Hydra,
+}
+
+impl TryFrom<File> for SoundFont {
+    type Error = SoundFontError;
+
+    fn try_from(file: File) -> Result<Self, Self::Error> {
+        Self::read(&mut BufReader::new(file))
+    }
+}
+
+impl SoundFont {
+    pub fn new(info: SoundFontInfo, sample_data: SampleData, hydra: Hydra) -> Self {
+        Self {
+            info,
+            sample_data,
+            hydra,
+        }
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Self, SoundFontError>
+    where
+        R: io::Read,
+    {
+        let riff = RiffChunk::read(reader)?;
+        if &riff.get_id() != b"RIFF" || riff.get_form_type() != Some(*b"sfbk") {
+            return Err(SoundFontError::NotASoundFont);
+        }
+
+        let lists = riff.subchunks()?;
+        let find_list = |form_type: &[u8; 4]| {
+            lists
+                .iter()
+                .find(|chunk| {
+                    &chunk.get_id() == b"LIST" && chunk.get_form_type() == Some(*form_type)
+                })
+                .ok_or(SoundFontError::MissingChunk(*form_type))
+        };
+
+        let info = SoundFontInfo::parse(find_list(b"INFO")?)?;
+        let sdta = find_list(b"sdta")?;
+        let sample_data = SampleData::parse(sdta, info.version)?;
+        let hydra = Hydra::parse(find_list(b"pdta")?)?;
+
+        let mut soundfont = Self {
+            info,
+            sample_data,
+            hydra,
+        };
+        if soundfont
+            .hydra
+            .sample_headers
+            .iter()
+            .any(|h| h.is_compressed())
+        {
+            soundfont.decode_compressed(sdta)?;
+        }
+        Ok(soundfont)
+    }
+
+    #[cfg(not(feature = "sf3"))]
+    fn decode_compressed(&mut self, _sdta: &RiffChunk) -> Result<(), SoundFontError> {
+        Err(SoundFontError::CompressedSamples)
+    }
+
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), io::Error>
+    where
+        W: io::Write,
+    {
+        let riff = RiffChunk::new_list(
+            *b"RIFF",
+            *b"sfbk",
+            &[
+                self.info.to_chunk(),
+                self.sample_data.to_chunk(),
+                self.hydra.to_chunk(),
+            ],
+        );
+        riff.write(writer)
+    }
+
+    pub fn get_info(&self) -> &SoundFontInfo {
+        &self.info
+    }
+    pub fn get_sample_data(&self) -> &SampleData {
+        &self.sample_data
+    }
+    pub fn get_hydra(&self) -> &Hydra {
+        &self.hydra
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator::{GeneratorAmount, GeneratorType};
+    use sample::SampleLink;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        if data.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn list(id: &[u8; 4], form_type: &[u8; 4], chunks: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = form_type.to_vec();
+        for c in chunks {
+            data.extend(c);
+        }
+        chunk(id, &data)
+    }
+
+    fn name(s: &str) -> Vec<u8> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.resize(20, 0);
+        bytes
+    }
+
+    fn le16(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn le32(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// One preset, one instrument, one sample, with terminal records.
+    fn minimal_sfbk() -> Vec<u8> {
+        let info = list(
+            b"LIST",
+            b"INFO",
+            &[
+                chunk(b"ifil", &le16(&[2, 4])),
+                chunk(b"isng", b"EMU8000\0"),
+                chunk(b"INAM", b"Test Bank\0"),
+                chunk(b"ICOP", b"Public domain\0"),
+            ],
+        );
+
+        let smpl: Vec<i16> = (0..60).map(|i| i * 100).collect();
+        let smpl_bytes: Vec<u8> = smpl.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let sdta = list(
+            b"LIST",
+            b"sdta",
+            &[chunk(b"smpl", &smpl_bytes), chunk(b"sm24", &[0x7F; 60])],
+        );
+
+        let phdr = [
+            [name("Piano"), le16(&[0, 0, 0]), le32(&[0, 0, 0])].concat(),
+            [name("EOP"), le16(&[0, 0, 1]), le32(&[0, 0, 0])].concat(),
+        ]
+        .concat();
+        let inst = [
+            [name("Piano Inst"), le16(&[0])].concat(),
+            [name("EOI"), le16(&[1])].concat(),
+        ]
+        .concat();
+        let shdr = [
+            [
+                name("Piano C5"),
+                le32(&[0, 10, 2, 8, 44100]),
+                vec![60, (-5_i8) as u8],
+                le16(&[0, 1]),
+            ]
+            .concat(),
+            [name("EOS"), le32(&[0; 5]), vec![0, 0], le16(&[0, 0])].concat(),
+        ]
+        .concat();
+        let pdta = list(
+            b"LIST",
+            b"pdta",
+            &[
+                chunk(b"phdr", &phdr),
+                chunk(b"pbag", &le16(&[0, 0, 1, 0])),
+                chunk(b"pmod", &[0; 10]),
+                chunk(b"pgen", &le16(&[41, 0, 0, 0])),
+                chunk(b"inst", &inst),
+                chunk(b"ibag", &le16(&[0, 0, 2, 0])),
+                chunk(b"imod", &[0; 10]),
+                chunk(b"igen", &le16(&[43, 0x7F00, 53, 0, 0, 0])),
+                chunk(b"shdr", &shdr),
+            ],
+        );
+
+        list(b"RIFF", b"sfbk", &[info, sdta, pdta])
+    }
+
+    #[test]
+    fn test_parse_minimal() {
+        let font = SoundFont::read(&mut minimal_sfbk().as_slice()).unwrap();
+
+        let info = font.get_info();
+        assert_eq!(info.version.to_string(), "2.04");
+        assert_eq!(info.bank_name, "Test Bank");
+        assert_eq!(info.copyright.as_deref(), Some("Public domain"));
+        assert_eq!(info.engineers, None);
+
+        let sample_data = font.get_sample_data();
+        assert_eq!(sample_data.get_sample_count(), 60);
+        assert_eq!(sample_data.get_point_24(3), 300 << 8 | 0x7F);
+
+        let hydra = font.get_hydra();
+        assert_eq!(hydra.preset_headers.len(), 2);
+        assert_eq!(hydra.preset_headers[0].name, "Piano");
+        assert_eq!(hydra.preset_headers[1].bag_index, 1);
+        assert_eq!(
+            hydra.preset_generators[0].get_type(),
+            Some(GeneratorType::Instrument)
+        );
+        assert_eq!(
+            hydra.instrument_generators[0].amount,
+            GeneratorAmount::from_range(0, 127)
+        );
+        assert_eq!(hydra.instruments[1].name, "EOI");
+
+        let sample = &hydra.sample_headers[0];
+        assert_eq!(sample.name, "Piano C5");
+        assert_eq!(sample.pitch_correction, -5);
+        assert_eq!(sample.get_link(), Some(SampleLink::Mono));
+        assert_eq!(sample_data.get_points(sample).len(), 10);
+    }
+
+    #[test]
+    fn test_write_roundtrip() {
+        let bytes = minimal_sfbk();
+        let font = SoundFont::read(&mut bytes.as_slice()).unwrap();
+        let mut written = vec![];
+        font.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn test_not_a_soundfont() {
+        let bytes = list(b"RIFF", b"WAVE", &[]);
+        assert!(matches!(
+            SoundFont::read(&mut bytes.as_slice()),
+            Err(SoundFontError::NotASoundFont)
+        ));
+    }
+
+    #[test]
+    fn test_missing_chunk() {
+        let bytes = list(
+            b"RIFF",
+            b"sfbk",
+            &[list(b"LIST", b"INFO", &[chunk(b"INAM", b"x\0")])],
+        );
+        assert!(matches!(
+            SoundFont::read(&mut bytes.as_slice()),
+            Err(SoundFontError::MissingChunk(id)) if &id == b"ifil"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_record_size() {
+        let bytes = list(b"LIST", b"pdta", &[chunk(b"phdr", &[0; 39])]);
+        let pdta = RiffChunk::read(&mut bytes.as_slice()).unwrap();
+        assert!(matches!(
+            Hydra::parse(&pdta),
+            Err(SoundFontError::InvalidChunkSize { id, size: 39 }) if &id == b"phdr"
+        ));
+    }
+}
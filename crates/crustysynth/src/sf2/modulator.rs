@@ -0,0 +1,276 @@
+//! Modulators (`pmod`, `imod`)
+//!
+//! A modulator routes a controller through a curve to a generator. A second source may scale the
+//! amount. Every instrument zone starts with the default modulators of SF2.04 section 8.4, which
+//! identical modulators in the zone replace.
+
+use super::generator::GeneratorType;
+
+/// Controller used as a modulator source, when the CC flag is not set.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GeneralController {
+    NoController = 0,
+    NoteOnVelocity = 2,
+    NoteOnKeyNumber = 3,
+    PolyPressure = 10,
+    ChannelPressure = 13,
+    PitchWheel = 14,
+    PitchWheelSensitivity = 16,
+    /// Output of another modulator
+    Link = 127,
+}
+
+impl GeneralController {
+    /// `None` for undefined controller indices.
+    pub fn from_index(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Self::NoController),
+            2 => Some(Self::NoteOnVelocity),
+            3 => Some(Self::NoteOnKeyNumber),
+            10 => Some(Self::PolyPressure),
+            13 => Some(Self::ChannelPressure),
+            14 => Some(Self::PitchWheel),
+            16 => Some(Self::PitchWheelSensitivity),
+            127 => Some(Self::Link),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ModulatorCurve {
+    Linear = 0,
+    Concave = 1,
+    Convex = 2,
+    Switch = 3,
+}
+
+/// Modulator source: `TTTTTTPD CIIIIIII`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct ModulatorSource(pub u16);
+
+impl ModulatorSource {
+    /// Controller number. A general controller, or a MIDI CC if `is_cc`.
+    pub fn index(&self) -> u8 {
+        (self.0 & 0x7F) as u8
+    }
+
+    pub fn is_cc(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+
+    /// Source maps from max to min.
+    pub fn is_descending(&self) -> bool {
+        self.0 & 0x100 != 0
+    }
+
+    /// Source maps to -1..1 instead of 0..1.
+    pub fn is_bipolar(&self) -> bool {
+        self.0 & 0x200 != 0
+    }
+
+    /// `None` for undefined curve types.
+    pub fn curve(&self) -> Option<ModulatorCurve> {
+        match self.0 >> 10 {
+            0 => Some(ModulatorCurve::Linear),
+            1 => Some(ModulatorCurve::Concave),
+            2 => Some(ModulatorCurve::Convex),
+            3 => Some(ModulatorCurve::Switch),
+            _ => None,
+        }
+    }
+
+    /// `None` for CC sources and undefined controllers.
+    pub fn general_controller(&self) -> Option<GeneralController> {
+        match self.is_cc() {
+            true => None,
+            false => GeneralController::from_index(self.index()),
+        }
+    }
+
+    /// Map a controller value between 0 and `max` through direction, polarity and curve. The
+    /// result is 0 to 1 for unipolar sources and -1 to 1 for bipolar ones.
+    pub fn map(&self, value: u16, max: u16) -> f64 {
+        let value = value.min(max) as f64;
+        let max = max as f64;
+        // Bipolar sources are centered on half the range, so 64 of 127 is 0.
+        let x = match self.is_bipolar() {
+            false => value / max,
+            true => (value / (max + 1.0) * 2.0 - 1.0).max(-1.0),
+        };
+        let x = match self.is_descending() {
+            false => x,
+            true if self.is_bipolar() => -x,
+            true => 1.0 - x,
+        };
+        let curve = self.curve().unwrap_or(ModulatorCurve::Linear);
+        match self.is_bipolar() {
+            false => curve.apply(x),
+            true => x.signum() * curve.apply(x.abs()),
+        }
+    }
+}
+
+impl ModulatorCurve {
+    /// Curve between 0 and 1. Switch steps at the midpoint.
+    pub fn apply(&self, x: f64) -> f64 {
+        match self {
+            Self::Linear => x,
+            Self::Concave => concave(x),
+            Self::Convex => 1.0 - concave(1.0 - x),
+            Self::Switch => match x >= 0.5 {
+                true => 1.0,
+                false => 0.0,
+            },
+        }
+    }
+}
+
+/// Concave curve: 96 dB of attenuation mapped to 0..1, so it rises like perceived loudness.
+fn concave(x: f64) -> f64 {
+    match x {
+        x if x <= 0.0 => 0.0,
+        x if x >= 1.0 => 1.0,
+        x => (-40.0 / 96.0 * (1.0 - x).log10()).min(1.0),
+    }
+}
+
+/// Transform applied to the modulator output.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ModulatorTransform {
+    Linear = 0,
+    AbsoluteValue = 2,
+}
+
+/// Modulator record. Destination and transform are kept raw, so that unknown values survive a
+/// round trip.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct Modulator {
+    pub source: ModulatorSource,
+    /// Generator type, or the index of another modulator if the high bit is set.
+    pub destination: u16,
+    pub amount: i16,
+    pub amount_source: ModulatorSource,
+    pub transform: u16,
+}
+
+/// Destination of the default pitch wheel modulator: pitch in cents. It isn't a generator that
+/// files can set.
+pub const INITIAL_PITCH: u16 = 59;
+
+/// Default modulators of SF2.04 section 8.4.
+pub const DEFAULT_MODULATORS: [Modulator; 10] = [
+    // Velocity to attenuation, negative concave
+    Modulator::new(0x0502, GeneratorType::InitialAttenuation as u16, 960, 0),
+    // Velocity to filter cutoff, negative linear
+    Modulator::new(0x0102, GeneratorType::InitialFilterFc as u16, -2400, 0),
+    // Channel pressure to vibrato depth
+    Modulator::new(0x000D, GeneratorType::VibLfoToPitch as u16, 50, 0),
+    // Modulation wheel (CC1) to vibrato depth
+    Modulator::new(0x0081, GeneratorType::VibLfoToPitch as u16, 50, 0),
+    // Volume (CC7) to attenuation, negative concave
+    Modulator::new(0x0587, GeneratorType::InitialAttenuation as u16, 960, 0),
+    // Pan (CC10) to pan, bipolar linear
+    Modulator::new(0x028A, GeneratorType::Pan as u16, 1000, 0),
+    // Expression (CC11) to attenuation, negative concave
+    Modulator::new(0x058B, GeneratorType::InitialAttenuation as u16, 960, 0),
+    // Reverb send (CC91)
+    Modulator::new(0x00DB, GeneratorType::ReverbEffectsSend as u16, 200, 0),
+    // Chorus send (CC93)
+    Modulator::new(0x00DD, GeneratorType::ChorusEffectsSend as u16, 200, 0),
+    // Pitch wheel to pitch, scaled by the pitch wheel sensitivity
+    Modulator::new(0x020E, INITIAL_PITCH, 12700, 0x0010),
+];
+
+impl Modulator {
+    pub const RECORD_SIZE: usize = 10;
+
+    /// Modulator with a linear transform.
+    pub const fn new(source: u16, destination: u16, amount: i16, amount_source: u16) -> Self {
+        Self {
+            source: ModulatorSource(source),
+            destination,
+            amount,
+            amount_source: ModulatorSource(amount_source),
+            transform: 0,
+        }
+    }
+
+    pub fn parse(b: &[u8]) -> Self {
+        Self {
+            source: ModulatorSource(u16::from_le_bytes([b[0], b[1]])),
+            destination: u16::from_le_bytes([b[2], b[3]]),
+            amount: i16::from_le_bytes([b[4], b[5]]),
+            amount_source: ModulatorSource(u16::from_le_bytes([b[6], b[7]])),
+            transform: u16::from_le_bytes([b[8], b[9]]),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.source.0.to_le_bytes().to_vec();
+        bytes.extend(self.destination.to_le_bytes());
+        bytes.extend(self.amount.to_le_bytes());
+        bytes.extend(self.amount_source.0.to_le_bytes());
+        bytes.extend(self.transform.to_le_bytes());
+        bytes
+    }
+
+    /// `None` for undefined transforms.
+    pub fn get_transform(&self) -> Option<ModulatorTransform> {
+        match self.transform {
+            0 => Some(ModulatorTransform::Linear),
+            2 => Some(ModulatorTransform::AbsoluteValue),
+            _ => None,
+        }
+    }
+
+    /// Modulators are identical if everything but the amount matches. An identical modulator in a
+    /// more specific zone replaces the other one.
+    pub fn is_identical(&self, other: &Self) -> bool {
+        self.source == other.source
+            && self.destination == other.destination
+            && self.amount_source == other.amount_source
+            && self.transform == other.transform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        // Negative unipolar concave, as for velocity to attenuation
+        let velocity = ModulatorSource(0x0502);
+        assert_eq!(velocity.map(127, 127), 0.0);
+        assert_eq!(velocity.map(0, 127), 1.0);
+        // Half velocity is about 12 dB down.
+        assert!((velocity.map(64, 127) * 960.0 - 119.0).abs() < 1.0);
+
+        // Bipolar linear, as for pan
+        let pan = ModulatorSource(0x028A);
+        assert_eq!(pan.map(64, 127), 0.0);
+        assert_eq!(pan.map(0, 127), -1.0);
+        assert_eq!(pan.map(8192, 16383), 0.0);
+
+        // Bipolar concave keeps the curve's shape on both sides of the center.
+        let bipolar = ModulatorSource(0x0600);
+        assert_eq!(bipolar.map(32, 127), -bipolar.map(96, 127));
+
+        let switch = ModulatorSource(0x0C00);
+        assert_eq!(switch.map(63, 127), 0.0);
+        assert_eq!(switch.map(64, 127), 1.0);
+    }
+
+    #[test]
+    fn test_curves() {
+        for x in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            assert_eq!(ModulatorCurve::Linear.apply(x), x);
+            let concave = ModulatorCurve::Concave.apply(x);
+            let convex = ModulatorCurve::Convex.apply(x);
+            assert!(concave <= x && convex >= x);
+        }
+        assert_eq!(ModulatorCurve::Concave.apply(1.0), 1.0);
+        assert_eq!(ModulatorCurve::Convex.apply(0.0), 0.0);
+    }
+}
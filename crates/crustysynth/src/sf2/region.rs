@@ -0,0 +1,378 @@
+//! Zone resolution (SF2.04 section 9)
+//!
+//! A note plays one region per matching instrument zone of each matching preset zone. The region
+//! holds the final generator values: instrument values are absolute and replace the defaults,
+//! preset values are relative and added on top. In both levels a local zone overrides the global
+//! zone, and key and velocity ranges of both levels are intersected.
+//!
+//! Modulators follow the same rules, starting from the default modulators: instrument modulators
+//! replace identical ones, and preset modulators add their amount to them.
+
+use super::{
+    generator::{GeneratorType, GENERATOR_COUNT},
+    hydra::Zone,
+    modulator::{Modulator, DEFAULT_MODULATORS},
+    SoundFont,
+};
+use crate::midi::keys::MidiKey;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Region {
+    sample_index: usize,
+    key_range: (u8, u8),
+    vel_range: (u8, u8),
+    generators: [i32; GENERATOR_COUNT],
+    modulators: Vec<Modulator>,
+}
+
+impl Region {
+    /// Region with default generator values and the default modulators.
+    pub fn new(sample_index: usize) -> Self {
+        let mut generators = [0; GENERATOR_COUNT];
+        for (oper, value) in generators.iter_mut().enumerate() {
+            if let Ok(generator_type) = GeneratorType::try_from(oper as u16) {
+                *value = generator_type.default_value();
+            }
+        }
+        Self {
+            sample_index,
+            key_range: (0, 127),
+            vel_range: (0, 127),
+            generators,
+            modulators: DEFAULT_MODULATORS.to_vec(),
+        }
+    }
+
+    pub fn get_sample_index(&self) -> usize {
+        self.sample_index
+    }
+    pub fn get_key_range(&self) -> (u8, u8) {
+        self.key_range
+    }
+    pub fn get_vel_range(&self) -> (u8, u8) {
+        self.vel_range
+    }
+    pub fn get_modulators(&self) -> &Vec<Modulator> {
+        &self.modulators
+    }
+
+    /// Generator value. Key and velocity ranges are available from `get_key_range` and
+    /// `get_vel_range` instead.
+    pub fn get(&self, generator_type: GeneratorType) -> i32 {
+        self.generators[generator_type as usize]
+    }
+
+    pub fn set(&mut self, generator_type: GeneratorType, value: i32) {
+        self.generators[generator_type as usize] = value;
+    }
+
+    pub fn set_key_range(&mut self, lo: u8, hi: u8) {
+        self.key_range = (lo, hi);
+    }
+    pub fn set_vel_range(&mut self, lo: u8, hi: u8) {
+        self.vel_range = (lo, hi);
+    }
+
+    /// Add a modulator, replacing an identical one.
+    pub fn set_modulator(&mut self, modulator: Modulator) {
+        match self
+            .modulators
+            .iter_mut()
+            .find(|m| m.is_identical(&modulator))
+        {
+            Some(existing) => *existing = modulator,
+            None => self.modulators.push(modulator),
+        }
+    }
+
+    /// Add a preset level modulator. An identical modulator has its amount increased instead.
+    pub fn add_modulator(&mut self, modulator: Modulator) {
+        match self
+            .modulators
+            .iter_mut()
+            .find(|m| m.is_identical(&modulator))
+        {
+            Some(existing) => existing.amount = existing.amount.saturating_add(modulator.amount),
+            None => self.modulators.push(modulator),
+        }
+    }
+
+    pub fn contains(&self, key: u8, velocity: u8) -> bool {
+        in_range(self.key_range, key) && in_range(self.vel_range, velocity)
+    }
+}
+
+impl SoundFont {
+    /// Index of the preset header for a bank and program. The terminal record is never matched.
+    pub fn find_preset(&self, bank: u16, program: u8) -> Option<usize> {
+        let headers = &self.hydra.preset_headers;
+        headers
+            .iter()
+            .take(headers.len().saturating_sub(1))
+            .position(|header| header.bank == bank && header.preset == program as u16)
+    }
+
+    /// Regions that sound for a note. Empty if the preset doesn't exist or doesn't cover the
+    /// note.
+    pub fn get_regions(&self, bank: u16, program: u8, key: MidiKey, velocity: u8) -> Vec<Region> {
+        match self.find_preset(bank, program) {
+            Some(preset_index) => self.get_preset_regions(preset_index, key, velocity),
+            None => vec![],
+        }
+    }
+
+    pub fn get_preset_regions(
+        &self,
+        preset_index: usize,
+        key: MidiKey,
+        velocity: u8,
+    ) -> Vec<Region> {
+        let key = u8::from(key);
+        let preset_zones = self.hydra.get_preset_zones(preset_index);
+        let (preset_global, preset_locals) = split_global(&preset_zones, GeneratorType::Instrument);
+
+        let mut regions = vec![];
+        for preset_zone in preset_locals {
+            let Some(instrument) = preset_zone.find_terminal(GeneratorType::Instrument) else {
+                continue;
+            };
+            let preset_keys = find_range(preset_zone, preset_global, GeneratorType::KeyRange);
+            let preset_vels = find_range(preset_zone, preset_global, GeneratorType::VelRange);
+            if !in_range(preset_keys, key) || !in_range(preset_vels, velocity) {
+                continue;
+            }
+
+            let instrument_zones = self
+                .hydra
+                .get_instrument_zones(instrument.as_u16() as usize);
+            let (instrument_global, instrument_locals) =
+                split_global(&instrument_zones, GeneratorType::SampleId);
+
+            for instrument_zone in instrument_locals {
+                let Some(sample_id) = instrument_zone.find_terminal(GeneratorType::SampleId) else {
+                    continue;
+                };
+                let keys = find_range(instrument_zone, instrument_global, GeneratorType::KeyRange);
+                let vels = find_range(instrument_zone, instrument_global, GeneratorType::VelRange);
+                if !in_range(keys, key) || !in_range(vels, velocity) {
+                    continue;
+                }
+
+                let mut region = Region::new(sample_id.as_u16() as usize);
+                region.key_range = intersect(keys, preset_keys);
+                region.vel_range = intersect(vels, preset_vels);
+
+                for zone in instrument_global.into_iter().chain([instrument_zone]) {
+                    for generator in zone.generators {
+                        match generator.get_type() {
+                            Some(t) if is_value_generator(t) => {
+                                let value = match t.is_unsigned() {
+                                    true => generator.amount.as_u16() as i32,
+                                    false => generator.amount.as_i16() as i32,
+                                };
+                                region.set(t, value);
+                            }
+                            _ => (),
+                        }
+                    }
+                    for modulator in zone.modulators {
+                        region.set_modulator(*modulator);
+                    }
+                }
+
+                // Local preset generators replace global ones before being added.
+                let mut preset_values = [None; GENERATOR_COUNT];
+                let mut preset_modulators: Vec<Modulator> = vec![];
+                for zone in preset_global.into_iter().chain([preset_zone]) {
+                    for generator in zone.generators {
+                        match generator.get_type() {
+                            Some(t) if is_value_generator(t) && !t.is_instrument_only() => {
+                                preset_values[t as usize] = Some(generator.amount.as_i16() as i32);
+                            }
+                            _ => (),
+                        }
+                    }
+                    for modulator in zone.modulators {
+                        match preset_modulators
+                            .iter_mut()
+                            .find(|m| m.is_identical(modulator))
+                        {
+                            Some(existing) => *existing = *modulator,
+                            None => preset_modulators.push(*modulator),
+                        }
+                    }
+                }
+                for (oper, value) in preset_values.iter().enumerate() {
+                    if let Some(value) = value {
+                        region.generators[oper] += value;
+                    }
+                }
+                for modulator in preset_modulators {
+                    region.add_modulator(modulator);
+                }
+
+                regions.push(region);
+            }
+        }
+        regions
+    }
+}
+
+/// Generators that hold a plain value, as opposed to ranges, links and ignored types.
+fn is_value_generator(generator_type: GeneratorType) -> bool {
+    !generator_type.is_unused()
+        && !generator_type.is_range()
+        && !matches!(
+            generator_type,
+            GeneratorType::Instrument | GeneratorType::SampleId
+        )
+}
+
+/// The first zone is global if it doesn't end with the terminal generator. Other zones without
+/// one are ignored by the caller.
+fn split_global<'a, 'b>(
+    zones: &'b [Zone<'a>],
+    terminal: GeneratorType,
+) -> (Option<&'b Zone<'a>>, &'b [Zone<'a>]) {
+    match zones.split_first() {
+        Some((first, rest)) if first.find_terminal(terminal).is_none() => (Some(first), rest),
+        _ => (None, zones),
+    }
+}
+
+fn find_range(zone: &Zone, global: Option<&Zone>, generator_type: GeneratorType) -> (u8, u8) {
+    zone.find(generator_type)
+        .or_else(|| global.and_then(|global| global.find(generator_type)))
+        .map_or((0, 127), |amount| amount.as_range())
+}
+
+fn in_range(range: (u8, u8), value: u8) -> bool {
+    range.0 <= value && value <= range.1
+}
+
+fn intersect(a: (u8, u8), b: (u8, u8)) -> (u8, u8) {
+    (a.0.max(b.0), a.1.min(b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sf2::{
+        generator::{Generator, GeneratorAmount},
+        hydra::{Bag, Hydra, Instrument, PresetHeader},
+        modulator::ModulatorSource,
+        sample::SampleHeader,
+    };
+
+    fn generator(generator_type: GeneratorType, value: i16) -> Generator {
+        Generator::new(generator_type, GeneratorAmount::from_i16(value))
+    }
+
+    fn range(generator_type: GeneratorType, lo: u8, hi: u8) -> Generator {
+        Generator::new(generator_type, GeneratorAmount::from_range(lo, hi))
+    }
+
+    fn bag(generator_index: u16, modulator_index: u16) -> Bag {
+        Bag {
+            generator_index,
+            modulator_index,
+        }
+    }
+
+    fn preset(name: &str, preset: u16, bank: u16, bag_index: u16) -> PresetHeader {
+        PresetHeader {
+            name: name.into(),
+            preset,
+            bank,
+            bag_index,
+            ..Default::default()
+        }
+    }
+
+    fn instrument(name: &str, bag_index: u16) -> Instrument {
+        Instrument {
+            name: name.into(),
+            bag_index,
+        }
+    }
+
+    /// Preset 0:0 with a global zone and two instrument zones split at C5. The instrument has a
+    /// global zone and two samples split by velocity.
+    fn test_font() -> SoundFont {
+        let modulator = Modulator {
+            source: ModulatorSource(0x0502),
+            destination: GeneratorType::InitialAttenuation as u16,
+            amount: 960,
+            ..Default::default()
+        };
+        let hydra = Hydra {
+            preset_headers: vec![
+                preset("Piano", 0, 0, 0),
+                preset("Other", 1, 0, 3),
+                preset("EOP", 0, 0, 3),
+            ],
+            preset_bags: vec![bag(0, 0), bag(1, 1), bag(3, 1), bag(5, 1)],
+            preset_modulators: vec![modulator, Modulator::default()],
+            preset_generators: vec![
+                generator(GeneratorType::InitialAttenuation, 10),
+                range(GeneratorType::KeyRange, 0, 59),
+                generator(GeneratorType::Instrument, 0),
+                range(GeneratorType::KeyRange, 60, 127),
+                generator(GeneratorType::Instrument, 0),
+                Generator::default(),
+            ],
+            instruments: vec![instrument("Piano", 0), instrument("EOI", 3)],
+            instrument_bags: vec![bag(0, 0), bag(2, 1), bag(4, 1), bag(6, 1)],
+            instrument_modulators: vec![modulator, Modulator::default()],
+            instrument_generators: vec![
+                generator(GeneratorType::InitialAttenuation, 50),
+                range(GeneratorType::KeyRange, 30, 90),
+                range(GeneratorType::VelRange, 0, 63),
+                generator(GeneratorType::SampleId, 0),
+                range(GeneratorType::VelRange, 64, 127),
+                generator(GeneratorType::SampleId, 1),
+                Generator::default(),
+            ],
+            sample_headers: vec![
+                SampleHeader::default(),
+                SampleHeader::default(),
+                SampleHeader::default(),
+            ],
+        };
+        SoundFont::new(Default::default(), Default::default(), hydra)
+    }
+
+    #[test]
+    fn test_resolve_regions() {
+        let font = test_font();
+        assert_eq!(font.find_preset(0, 0), Some(0));
+        assert_eq!(font.find_preset(0, 5), None);
+
+        let regions = font.get_regions(0, 0, MidiKey::C5, 100);
+        assert_eq!(regions.len(), 1);
+        let region = &regions[0];
+        assert_eq!(region.get_sample_index(), 1);
+        assert_eq!(region.get_key_range(), (60, 90));
+        assert_eq!(region.get_vel_range(), (64, 127));
+        // Instrument global 50 + preset global 10
+        assert_eq!(region.get(GeneratorType::InitialAttenuation), 60);
+        assert_eq!(region.get(GeneratorType::InitialFilterFc), 13500);
+        // The instrument modulator replaces the identical default, and the preset one adds to it.
+        assert_eq!(region.get_modulators().len(), DEFAULT_MODULATORS.len());
+        assert_eq!(region.get_modulators()[0].amount, 1920);
+
+        let regions = font.get_regions(0, 0, MidiKey::try_from(20).unwrap(), 10);
+        assert!(regions.is_empty());
+
+        let regions = font.get_regions(0, 0, MidiKey::try_from(40).unwrap(), 10);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].get_sample_index(), 0);
+        assert_eq!(regions[0].get_key_range(), (30, 59));
+    }
+
+    #[test]
+    fn test_empty_preset() {
+        let font = test_font();
+        assert!(font.get_regions(0, 1, MidiKey::C5, 100).is_empty());
+    }
+}
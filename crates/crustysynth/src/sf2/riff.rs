@@ -0,0 +1,141 @@
+//! RIFF chunks
+//!
+//! Like SMF chunks, but little-endian and padded to an even length. `RIFF` and `LIST` chunks
+//! start with a form type and contain more chunks.
+
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+    io,
+};
+
+#[derive(Debug)]
+pub enum RiffChunkError {
+    IOError { source: std::io::Error },
+    NotAList([u8; 4]),
+}
+impl Error for RiffChunkError {}
+impl Display for RiffChunkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IOError { source } => write!(f, "{source}"),
+            Self::NotAList(id) => write!(
+                f,
+                "Chunk {} does not contain subchunks.",
+                String::from_utf8_lossy(id)
+            ),
+        }
+    }
+}
+impl From<std::io::Error> for RiffChunkError {
+    fn from(e: std::io::Error) -> Self {
+        Self::IOError { source: e }
+    }
+}
+
+pub struct RiffChunk {
+    id: [u8; 4],
+    data: Vec<u8>,
+}
+impl Debug for RiffChunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RiffChunk")
+            .field("id", &String::from_utf8_lossy(&self.id))
+            .field("length", &self.data.len())
+            /* Data hidden to avoid excessive printing */
+            .finish()
+    }
+}
+
+impl RiffChunk {
+    pub fn new(id: [u8; 4], data: Vec<u8>) -> Self {
+        Self { id, data }
+    }
+
+    /// `RIFF` or `LIST` chunk containing `subchunks`.
+    pub fn new_list(id: [u8; 4], form_type: [u8; 4], subchunks: &[RiffChunk]) -> Self {
+        let mut data = form_type.to_vec();
+        for subchunk in subchunks {
+            // Writing to a Vec can't fail.
+            let _ = subchunk.write(&mut data);
+        }
+        Self { id, data }
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Self, RiffChunkError>
+    where
+        R: io::Read,
+    {
+        let mut id = [0_u8; 4];
+        reader.read_exact(&mut id)?;
+
+        let mut length_buf = [0_u8; 4];
+        reader.read_exact(&mut length_buf)?;
+        let length = u32::from_le_bytes(length_buf);
+
+        let mut data = vec![0_u8; length as usize];
+        reader.read_exact(&mut data)?;
+
+        // Some writers leave out the pad byte of the last chunk, so it's not required.
+        if length % 2 == 1 {
+            let _ = reader.read(&mut [0_u8])?;
+        }
+
+        Ok(Self { id, data })
+    }
+
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), io::Error>
+    where
+        W: io::Write,
+    {
+        writer.write_all(&self.id)?;
+        writer.write_all(&(self.data.len() as u32).to_le_bytes())?;
+        writer.write_all(&self.data)?;
+        if self.data.len() % 2 == 1 {
+            writer.write_all(&[0])?;
+        }
+        Ok(())
+    }
+
+    pub fn get_id(&self) -> [u8; 4] {
+        self.id
+    }
+    pub fn get_data(&self) -> &Vec<u8> {
+        &self.data
+    }
+
+    /// Form type of a `RIFF` or `LIST` chunk.
+    pub fn get_form_type(&self) -> Option<[u8; 4]> {
+        match &self.id {
+            b"RIFF" | b"LIST" => self.data.get(..4)?.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Parse the chunks contained by a `RIFF` or `LIST` chunk.
+    pub fn subchunks(&self) -> Result<Vec<RiffChunk>, RiffChunkError> {
+        if self.get_form_type().is_none() {
+            return Err(RiffChunkError::NotAList(self.id));
+        }
+        let mut reader = &self.data[4..];
+        let mut subchunks = vec![];
+        while !reader.is_empty() {
+            subchunks.push(Self::read(&mut reader)?);
+        }
+        Ok(subchunks)
+    }
+}
+
+/// Decode a fixed-size, zero-terminated string field.
+pub(crate) fn parse_zstr(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// Encode a fixed-size, zero-terminated string field. Long strings are truncated.
+pub(crate) fn write_zstr(s: &str, len: usize) -> Vec<u8> {
+    let mut bytes = s.as_bytes().to_vec();
+    bytes.truncate(len - 1);
+    bytes.resize(len, 0);
+    bytes
+}
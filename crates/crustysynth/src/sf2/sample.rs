@@ -0,0 +1,207 @@
+//! Sample headers (`shdr`) and sample data (`sdta`)
+
+use super::{
+    info::SoundFontVersion,
+    riff::{parse_zstr, write_zstr, RiffChunk},
+    SoundFontError,
+};
+
+/// Zero points the spec requires after each sample
+pub(crate) const SAMPLE_PADDING: usize = 46;
+
+/// Loop points of a sample from `start` to `end`, from loop offsets relative to `start`. Bogus
+/// offsets saturate, and the loop is kept within the sample.
+pub(crate) fn clamp_loop(start: u32, end: u32, (start_loop, end_loop): (u32, u32)) -> (u32, u32) {
+    let point = |offset: u32| start.saturating_add(offset).min(end);
+    (point(start_loop), point(end_loop))
+}
+
+/// How a sample relates to its linked sample.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SampleLink {
+    Mono = 1,
+    Right = 2,
+    Left = 4,
+    Linked = 8,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct SampleHeader {
+    pub name: String,
+    /// Index of the first sample point in `smpl`
+    pub start: u32,
+    /// Index of the first sample point after the sample
+    pub end: u32,
+    pub start_loop: u32,
+    /// Index of the first sample point after the loop
+    pub end_loop: u32,
+    pub sample_rate: u32,
+    /// MIDI key of the recorded pitch
+    pub original_key: u8,
+    /// Pitch correction in cents
+    pub pitch_correction: i8,
+    /// Index of the other sample of a stereo pair
+    pub sample_link: u16,
+    pub sample_type: u16,
+}
+
+impl SampleHeader {
+    pub const RECORD_SIZE: usize = 46;
+
+    /// Sample type flag for samples stored in ROM, not in `smpl`.
+    pub const ROM_FLAG: u16 = 0x8000;
+
+    /// SF3 sample type flag for Ogg Vorbis compressed samples.
+    pub const COMPRESSED_FLAG: u16 = 0x10;
+
+    pub fn parse(b: &[u8]) -> Self {
+        let u32_at = |i: usize| u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+        Self {
+            name: parse_zstr(&b[0..20]),
+            start: u32_at(20),
+            end: u32_at(24),
+            start_loop: u32_at(28),
+            end_loop: u32_at(32),
+            sample_rate: u32_at(36),
+            original_key: b[40],
+            pitch_correction: b[41] as i8,
+            sample_link: u16::from_le_bytes([b[42], b[43]]),
+            sample_type: u16::from_le_bytes([b[44], b[45]]),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = write_zstr(&self.name, 20);
+        bytes.extend(self.start.to_le_bytes());
+        bytes.extend(self.end.to_le_bytes());
+        bytes.extend(self.start_loop.to_le_bytes());
+        bytes.extend(self.end_loop.to_le_bytes());
+        bytes.extend(self.sample_rate.to_le_bytes());
+        bytes.push(self.original_key);
+        bytes.push(self.pitch_correction as u8);
+        bytes.extend(self.sample_link.to_le_bytes());
+        bytes.extend(self.sample_type.to_le_bytes());
+        bytes
+    }
+
+    pub fn is_rom(&self) -> bool {
+        self.sample_type & Self::ROM_FLAG != 0
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.sample_type & Self::COMPRESSED_FLAG != 0
+    }
+
+    /// `None` if the type is not defined by the spec.
+    pub fn get_link(&self) -> Option<SampleLink> {
+        match self.sample_type & !(Self::ROM_FLAG | Self::COMPRESSED_FLAG) {
+            1 => Some(SampleLink::Mono),
+            2 => Some(SampleLink::Right),
+            4 => Some(SampleLink::Left),
+            8 => Some(SampleLink::Linked),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl SampleHeader {
+    /// Mono sample at 44.1 kHz recorded at key 60, looping from its second point to its last.
+    pub(crate) fn test(name: &str, start: u32, end: u32) -> Self {
+        Self {
+            name: name.into(),
+            start,
+            end,
+            start_loop: start + 1,
+            end_loop: end - 1,
+            sample_rate: 44100,
+            original_key: 60,
+            sample_type: SampleLink::Mono as u16,
+            ..Default::default()
+        }
+    }
+}
+
+/// Sample points, 16-bit with optional 8 extra low bits.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SampleData {
+    smpl: Vec<i16>,
+    sm24: Option<Vec<u8>>,
+}
+
+impl SampleData {
+    /// `sm24` is dropped if its length doesn't match `smpl`.
+    pub fn new(smpl: Vec<i16>, sm24: Option<Vec<u8>>) -> Self {
+        let sm24 = sm24.filter(|sm24| sm24.len() == smpl.len());
+        Self { smpl, sm24 }
+    }
+
+    pub fn parse(list: &RiffChunk, version: SoundFontVersion) -> Result<Self, SoundFontError> {
+        let mut smpl = vec![];
+        let mut sm24 = None;
+        for chunk in list.subchunks()? {
+            match &chunk.get_id() {
+                b"smpl" => {
+                    smpl = chunk
+                        .get_data()
+                        .chunks_exact(2)
+                        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                        .collect();
+                }
+                b"sm24" => sm24 = Some(chunk.get_data().clone()),
+                _ => (),
+            }
+        }
+
+        // sm24 was added in 2.04, and may have a pad byte. Older files can't contain it, so it's
+        // ignored there.
+        let sm24 = sm24
+            .filter(|_| version >= SoundFontVersion { major: 2, minor: 4 })
+            .map(|mut sm24| {
+                if sm24.len() == smpl.len() + 1 {
+                    sm24.pop();
+                }
+                sm24
+            });
+
+        Ok(Self::new(smpl, sm24))
+    }
+
+    pub fn to_chunk(&self) -> RiffChunk {
+        let smpl = self.smpl.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let mut chunks = vec![RiffChunk::new(*b"smpl", smpl)];
+        if let Some(sm24) = &self.sm24 {
+            chunks.push(RiffChunk::new(*b"sm24", sm24.clone()));
+        }
+        RiffChunk::new_list(*b"LIST", *b"sdta", &chunks)
+    }
+
+    pub fn get_smpl(&self) -> &Vec<i16> {
+        &self.smpl
+    }
+    pub fn get_sm24(&self) -> Option<&Vec<u8>> {
+        self.sm24.as_ref()
+    }
+    pub fn get_sample_count(&self) -> usize {
+        self.smpl.len()
+    }
+
+    /// Sample point as a 24-bit value. Out of range reads are silent.
+    pub fn get_point_24(&self, index: usize) -> i32 {
+        let high = self.smpl.get(index).copied().unwrap_or(0) as i32;
+        let low = self
+            .sm24
+            .as_ref()
+            .and_then(|sm24| sm24.get(index))
+            .copied()
+            .unwrap_or(0) as i32;
+        high << 8 | low
+    }
+
+    /// Sample points of a sample, clamped to the available data.
+    pub fn get_points(&self, header: &SampleHeader) -> &[i16] {
+        let end = (header.end as usize).min(self.smpl.len());
+        let start = (header.start as usize).min(end);
+        &self.smpl[start..end]
+    }
+}
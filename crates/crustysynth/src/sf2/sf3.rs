@@ -0,0 +1,195 @@
+//! SF3: Ogg Vorbis compressed samples
+//!
+//! SF3 is an extension by MuseScore. Each sample with the compressed type flag is a separate Ogg
+//! Vorbis stream in `smpl`. Its `start` and `end` are byte offsets of the stream, and its loop
+//! points are relative to the start of the decoded sample.
+//!
+//! Samples are decoded to 16-bit PCM at load time. After that, the SoundFont is a plain SF2.
+
+use std::io::Cursor;
+
+use lewton::inside_ogg::OggStreamReader;
+
+use super::{
+    hydra::Hydra,
+    info::SoundFontVersion,
+    riff::RiffChunk,
+    sample::{clamp_loop, SampleData, SampleHeader, SAMPLE_PADDING},
+    SoundFont, SoundFontError,
+};
+
+impl SoundFont {
+    /// Decode compressed samples and rebuild the sample data. Sample headers are updated to point
+    /// to the new data. Uncompressed samples are copied as they are.
+    pub(super) fn decode_compressed(&mut self, sdta: &RiffChunk) -> Result<(), SoundFontError> {
+        let smpl = sdta
+            .subchunks()?
+            .into_iter()
+            .find(|chunk| &chunk.get_id() == b"smpl")
+            .map(|chunk| chunk.get_data().clone())
+            .unwrap_or_default();
+        self.sample_data = rebuild(&smpl, &self.sample_data, &mut self.hydra, decode_vorbis)?;
+
+        // Decoded, the bank is a plain SF2, and is written as one.
+        if self.info.version.major == 3 {
+            self.info.version = SoundFontVersion { major: 2, minor: 4 };
+        }
+        Ok(())
+    }
+}
+
+fn rebuild<D>(
+    smpl: &[u8],
+    sample_data: &SampleData,
+    hydra: &mut Hydra,
+    decode: D,
+) -> Result<SampleData, SoundFontError>
+where
+    D: Fn(&[u8]) -> Result<Vec<i16>, String>,
+{
+    let mut points = vec![];
+    let sample_count = hydra.sample_headers.len().saturating_sub(1);
+
+    for (index, header) in hydra
+        .sample_headers
+        .iter_mut()
+        .take(sample_count)
+        .enumerate()
+    {
+        if header.is_rom() {
+            continue;
+        }
+
+        let new_start = points.len() as u32;
+        // Loop points relative to the start of the sample
+        let (start_loop, end_loop) = if header.is_compressed() {
+            let end = (header.end as usize).min(smpl.len());
+            let start = (header.start as usize).min(end);
+            let decoded =
+                decode(&smpl[start..end]).map_err(|message| SoundFontError::SampleDecodeError {
+                    sample: index,
+                    message,
+                })?;
+            points.extend(decoded);
+            (header.start_loop, header.end_loop)
+        } else {
+            points.extend(sample_data.get_points(header));
+            (
+                header.start_loop.saturating_sub(header.start),
+                header.end_loop.saturating_sub(header.start),
+            )
+        };
+        let new_end = points.len() as u32;
+        points.resize(points.len() + SAMPLE_PADDING, 0);
+        // Corrupt loop points stay within the sample.
+        let (start_loop, end_loop) = clamp_loop(new_start, new_end, (start_loop, end_loop));
+
+        *header = SampleHeader {
+            start: new_start,
+            end: new_end,
+            start_loop,
+            end_loop,
+            sample_type: header.sample_type & !SampleHeader::COMPRESSED_FLAG,
+            ..header.clone()
+        };
+    }
+
+    // Extra 8 bits don't apply to decoded samples.
+    Ok(SampleData::new(points, None))
+}
+
+/// Decode an Ogg Vorbis stream. Only the first channel is kept, because SF3 samples are mono.
+fn decode_vorbis(bytes: &[u8]) -> Result<Vec<i16>, String> {
+    let mut reader = OggStreamReader::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let channels = (reader.ident_hdr.audio_channels as usize).max(1);
+
+    let mut points = vec![];
+    while let Some(packet) = reader.read_dec_packet_itl().map_err(|e| e.to_string())? {
+        points.extend(packet.into_iter().step_by(channels));
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(
+        start: u32,
+        end: u32,
+        start_loop: u32,
+        end_loop: u32,
+        sample_type: u16,
+    ) -> SampleHeader {
+        SampleHeader {
+            start_loop,
+            end_loop,
+            sample_type,
+            ..SampleHeader::test("Sample", start, end)
+        }
+    }
+
+    fn hydra(sample_headers: Vec<SampleHeader>) -> Hydra {
+        Hydra::from_records(&[], &[], sample_headers)
+    }
+
+    /// Stand-in decoder: each byte is one point.
+    fn decode_bytes(bytes: &[u8]) -> Result<Vec<i16>, String> {
+        Ok(bytes.iter().map(|b| *b as i16).collect())
+    }
+
+    #[test]
+    fn test_rebuild() {
+        let compressed = 1 | SampleHeader::COMPRESSED_FLAG;
+        let mut hydra = hydra(vec![sample(2, 6, 1, 3, compressed), sample(1, 3, 1, 3, 1)]);
+        let smpl = [0, 0, 10, 11, 12, 13, 0, 0];
+        let sample_data = SampleData::new(vec![0, 20, 21, 0], None);
+
+        let data = rebuild(&smpl, &sample_data, &mut hydra, decode_bytes).unwrap();
+
+        let headers = &hydra.sample_headers;
+        assert_eq!(&data.get_smpl()[..4], &[10, 11, 12, 13]);
+        assert_eq!((headers[0].start, headers[0].end), (0, 4));
+        assert_eq!((headers[0].start_loop, headers[0].end_loop), (1, 3));
+        assert!(!headers[0].is_compressed());
+
+        let start = 4 + SAMPLE_PADDING as u32;
+        assert_eq!(data.get_points(&headers[1]), &[20, 21]);
+        assert_eq!((headers[1].start, headers[1].end), (start, start + 2));
+        assert_eq!(
+            (headers[1].start_loop, headers[1].end_loop),
+            (start, start + 2)
+        );
+        assert_eq!(data.get_sample_count(), 6 + 2 * SAMPLE_PADDING);
+    }
+
+    #[test]
+    fn test_corrupt_loop() {
+        let compressed = 1 | SampleHeader::COMPRESSED_FLAG;
+        let mut hydra = hydra(vec![
+            sample(0, 2, 0, 0, 1),
+            sample(0, 4, 1, u32::MAX, compressed),
+        ]);
+        let data = SampleData::new(vec![1, 2], None);
+        rebuild(&[10, 11, 12, 13], &data, &mut hydra, decode_bytes).unwrap();
+
+        let header = &hydra.sample_headers[1];
+        let start = 2 + SAMPLE_PADDING as u32;
+        assert_eq!((header.start_loop, header.end_loop), (start + 1, start + 4));
+    }
+
+    #[test]
+    fn test_invalid_stream() {
+        let mut hydra = hydra(vec![sample(0, 4, 0, 0, 1 | SampleHeader::COMPRESSED_FLAG)]);
+        let result = rebuild(
+            &[1, 2, 3, 4],
+            &SampleData::default(),
+            &mut hydra,
+            decode_vorbis,
+        );
+        assert!(matches!(
+            result,
+            Err(SoundFontError::SampleDecodeError { sample: 0, .. })
+        ));
+    }
+}
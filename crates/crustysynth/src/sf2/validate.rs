@@ -0,0 +1,707 @@
+//! Validation
+//!
+//! The parser accepts anything structurally readable, and resolution skips what it can't use.
+//! This reports what was skipped or looks suspicious, with the index of the record at fault.
+
+use std::fmt::Display;
+
+use super::{
+    generator::GeneratorType,
+    hydra::{Bag, Zone},
+    sample::SampleLink,
+    SoundFont,
+};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum LintSeverity {
+    /// Unusual, but playable as the spec intends.
+    Warning,
+    /// Breaks the spec. Players disagree on how to handle it, or part of the bank is unusable.
+    Error,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum LintCategory {
+    /// Terminal records and the index ranges that link records together
+    Structure,
+    /// Links to presets, instruments or samples that don't exist
+    Reference,
+    /// Generator types, values and order
+    Generator,
+    /// Key and velocity ranges
+    Range,
+    /// Sample headers and loops
+    Sample,
+    Modulator,
+}
+
+/// Where an issue was found.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum LintRecord {
+    Chunk([u8; 4]),
+    Preset(usize),
+    PresetZone { preset: usize, zone: usize },
+    Instrument(usize),
+    InstrumentZone { instrument: usize, zone: usize },
+    Sample(usize),
+}
+impl LintRecord {
+    /// Sort key: chunks, then presets, instruments and samples, each followed by its zones.
+    fn order(&self) -> (u8, usize, Option<usize>) {
+        match *self {
+            Self::Chunk(_) => (0, 0, None),
+            Self::Preset(preset) => (1, preset, None),
+            Self::PresetZone { preset, zone } => (1, preset, Some(zone)),
+            Self::Instrument(instrument) => (2, instrument, None),
+            Self::InstrumentZone { instrument, zone } => (2, instrument, Some(zone)),
+            Self::Sample(sample) => (3, sample, None),
+        }
+    }
+}
+impl Display for LintRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Chunk(id) => write!(f, "chunk {}", String::from_utf8_lossy(id)),
+            Self::Preset(preset) => write!(f, "preset {preset}"),
+            Self::PresetZone { preset, zone } => write!(f, "preset {preset} zone {zone}"),
+            Self::Instrument(instrument) => write!(f, "instrument {instrument}"),
+            Self::InstrumentZone { instrument, zone } => {
+                write!(f, "instrument {instrument} zone {zone}")
+            }
+            Self::Sample(sample) => write!(f, "sample {sample}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct LintIssue {
+    pub severity: LintSeverity,
+    pub category: LintCategory,
+    pub record: LintRecord,
+    pub message: String,
+}
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            LintSeverity::Warning => "warning",
+            LintSeverity::Error => "error",
+        };
+        write!(
+            f,
+            "{severity} ({:?}) {}: {}",
+            self.category, self.record, self.message
+        )
+    }
+}
+
+/// Which side of the hydra a zone belongs to.
+#[derive(Clone, Copy)]
+enum Level {
+    Preset,
+    Instrument,
+}
+
+impl Level {
+    fn terminal(&self) -> GeneratorType {
+        match self {
+            Self::Preset => GeneratorType::Instrument,
+            Self::Instrument => GeneratorType::SampleId,
+        }
+    }
+
+    fn record(&self, owner: usize) -> LintRecord {
+        match self {
+            Self::Preset => LintRecord::Preset(owner),
+            Self::Instrument => LintRecord::Instrument(owner),
+        }
+    }
+
+    fn zone_record(&self, owner: usize, zone: usize) -> LintRecord {
+        match self {
+            Self::Preset => LintRecord::PresetZone {
+                preset: owner,
+                zone,
+            },
+            Self::Instrument => LintRecord::InstrumentZone {
+                instrument: owner,
+                zone,
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct Linter {
+    issues: Vec<LintIssue>,
+}
+
+impl Linter {
+    fn push(
+        &mut self,
+        severity: LintSeverity,
+        category: LintCategory,
+        record: LintRecord,
+        message: String,
+    ) {
+        self.issues.push(LintIssue {
+            severity,
+            category,
+            record,
+            message,
+        });
+    }
+}
+
+impl SoundFont {
+    /// Check the SoundFont for problems. Issues are ordered by record.
+    pub fn validate(&self) -> Vec<LintIssue> {
+        let mut linter = Linter::default();
+        self.lint_terminals(&mut linter);
+        self.lint_bag_ranges(&mut linter);
+        self.lint_presets(&mut linter);
+        self.lint_instruments(&mut linter);
+        self.lint_samples(&mut linter);
+        // Stable, so issues of a record stay in the order they were found.
+        linter.issues.sort_by_key(|issue| issue.record.order());
+        linter.issues
+    }
+
+    fn lint_terminals(&self, linter: &mut Linter) {
+        let hydra = &self.hydra;
+        let terminals = [
+            (
+                *b"phdr",
+                hydra.preset_headers.last().map(|h| h.name.as_str()),
+                "EOP",
+            ),
+            (
+                *b"inst",
+                hydra.instruments.last().map(|i| i.name.as_str()),
+                "EOI",
+            ),
+            (
+                *b"shdr",
+                hydra.sample_headers.last().map(|s| s.name.as_str()),
+                "EOS",
+            ),
+        ];
+        for (id, last_name, expected) in terminals {
+            match last_name {
+                None => linter.push(
+                    LintSeverity::Error,
+                    LintCategory::Structure,
+                    LintRecord::Chunk(id),
+                    "Chunk is empty, the terminal record is missing.".into(),
+                ),
+                Some(name) if name != expected => linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Structure,
+                    LintRecord::Chunk(id),
+                    format!("Last record is named \"{name}\", expected \"{expected}\"."),
+                ),
+                _ => (),
+            }
+        }
+
+        let counts = [
+            (*b"pbag", hydra.preset_bags.len()),
+            (*b"pgen", hydra.preset_generators.len()),
+            (*b"pmod", hydra.preset_modulators.len()),
+            (*b"ibag", hydra.instrument_bags.len()),
+            (*b"igen", hydra.instrument_generators.len()),
+            (*b"imod", hydra.instrument_modulators.len()),
+        ];
+        for (id, count) in counts {
+            if count == 0 {
+                linter.push(
+                    LintSeverity::Error,
+                    LintCategory::Structure,
+                    LintRecord::Chunk(id),
+                    "Chunk is empty, the terminal record is missing.".into(),
+                );
+            }
+        }
+    }
+
+    /// Index fields must not decrease, and must stay within the records they point to.
+    fn lint_bag_ranges(&self, linter: &mut Linter) {
+        let hydra = &self.hydra;
+
+        let preset_bag_indices: Vec<u16> =
+            hydra.preset_headers.iter().map(|h| h.bag_index).collect();
+        lint_indices(
+            linter,
+            &preset_bag_indices,
+            hydra.preset_bags.len(),
+            LintRecord::Preset,
+            "bag",
+        );
+        let instrument_bag_indices: Vec<u16> =
+            hydra.instruments.iter().map(|i| i.bag_index).collect();
+        lint_indices(
+            linter,
+            &instrument_bag_indices,
+            hydra.instrument_bags.len(),
+            LintRecord::Instrument,
+            "bag",
+        );
+
+        let bag_lists = [
+            (
+                *b"pbag",
+                &hydra.preset_bags,
+                hydra.preset_generators.len(),
+                hydra.preset_modulators.len(),
+            ),
+            (
+                *b"ibag",
+                &hydra.instrument_bags,
+                hydra.instrument_generators.len(),
+                hydra.instrument_modulators.len(),
+            ),
+        ];
+        for (id, bags, generator_count, modulator_count) in bag_lists {
+            let generator_indices: Vec<u16> =
+                bags.iter().map(|b: &Bag| b.generator_index).collect();
+            let modulator_indices: Vec<u16> = bags.iter().map(|b| b.modulator_index).collect();
+            let record = |_| LintRecord::Chunk(id);
+            lint_indices(
+                linter,
+                &generator_indices,
+                generator_count,
+                record,
+                "generator",
+            );
+            lint_indices(
+                linter,
+                &modulator_indices,
+                modulator_count,
+                record,
+                "modulator",
+            );
+        }
+    }
+
+    fn lint_presets(&self, linter: &mut Linter) {
+        let headers = &self.hydra.preset_headers;
+        let preset_count = headers.len().saturating_sub(1);
+        let instrument_count = self.hydra.instruments.len().saturating_sub(1);
+
+        for (i, header) in headers.iter().take(preset_count).enumerate() {
+            if let Some(first) = headers[..i]
+                .iter()
+                .position(|other| other.bank == header.bank && other.preset == header.preset)
+            {
+                linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Structure,
+                    LintRecord::Preset(i),
+                    format!(
+                        "Preset {}:{} is already defined by preset {first}, and is unreachable.",
+                        header.bank, header.preset
+                    ),
+                );
+            }
+
+            let zones = self.hydra.get_preset_zones(i);
+            self.lint_zones(linter, Level::Preset, i, &zones, instrument_count);
+        }
+    }
+
+    fn lint_instruments(&self, linter: &mut Linter) {
+        let instrument_count = self.hydra.instruments.len().saturating_sub(1);
+        let sample_count = self.hydra.sample_headers.len().saturating_sub(1);
+
+        for i in 0..instrument_count {
+            let zones = self.hydra.get_instrument_zones(i);
+            self.lint_zones(linter, Level::Instrument, i, &zones, sample_count);
+            self.lint_overlaps(linter, i, &zones);
+        }
+    }
+
+    fn lint_zones(
+        &self,
+        linter: &mut Linter,
+        level: Level,
+        owner: usize,
+        zones: &[Zone],
+        target_count: usize,
+    ) {
+        let terminal = level.terminal();
+        if zones.is_empty() {
+            linter.push(
+                LintSeverity::Warning,
+                LintCategory::Structure,
+                level.record(owner),
+                "No zones.".into(),
+            );
+        }
+
+        for (z, zone) in zones.iter().enumerate() {
+            let record = level.zone_record(owner, z);
+
+            match zone.find_terminal(terminal) {
+                Some(amount) if amount.as_u16() as usize >= target_count => linter.push(
+                    LintSeverity::Error,
+                    LintCategory::Reference,
+                    record,
+                    format!(
+                        "{terminal:?} {} does not exist. There are {target_count}.",
+                        amount.as_u16()
+                    ),
+                ),
+                Some(_) => (),
+                // The first zone without a terminal generator is the global zone.
+                None if z == 0 && zones.len() > 1 => (),
+                None => linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Generator,
+                    record,
+                    format!("Zone doesn't end with {terminal:?}, and is ignored."),
+                ),
+            }
+
+            self.lint_generators(linter, level, record, zone);
+            lint_modulators(linter, record, zone);
+        }
+    }
+
+    fn lint_generators(&self, linter: &mut Linter, level: Level, record: LintRecord, zone: &Zone) {
+        let terminal = level.terminal();
+        let mut seen = vec![];
+        for (g, generator) in zone.generators.iter().enumerate() {
+            let Some(generator_type) = generator.get_type() else {
+                linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Generator,
+                    record,
+                    format!("Generator {g} has unknown type {}.", generator.oper),
+                );
+                continue;
+            };
+
+            if seen.contains(&generator_type) {
+                linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Generator,
+                    record,
+                    format!("{generator_type:?} appears more than once."),
+                );
+            }
+            seen.push(generator_type);
+
+            let misplaced = match generator_type {
+                GeneratorType::KeyRange => g != 0,
+                GeneratorType::VelRange => g > 1 || (g == 1 && seen[0] != GeneratorType::KeyRange),
+                t if t == terminal => g + 1 != zone.generators.len(),
+                _ => false,
+            };
+            if misplaced {
+                linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Generator,
+                    record,
+                    format!("{generator_type:?} is out of order, and should be ignored."),
+                );
+            }
+
+            let wrong_level = match level {
+                Level::Preset => generator_type.is_instrument_only(),
+                Level::Instrument => generator_type == GeneratorType::Instrument,
+            };
+            if generator_type.is_unused() || wrong_level {
+                linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Generator,
+                    record,
+                    format!("{generator_type:?} is not allowed here, and is ignored."),
+                );
+            }
+
+            if generator_type.is_range() {
+                let (lo, hi) = generator.amount.as_range();
+                if lo > hi || hi > 127 {
+                    linter.push(
+                        LintSeverity::Error,
+                        LintCategory::Range,
+                        record,
+                        format!("{generator_type:?} {lo}-{hi} is invalid."),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Zones that overlap in both key and velocity play together. That's intended for stereo
+    /// pairs and layers, but often a mistake.
+    fn lint_overlaps(&self, linter: &mut Linter, instrument: usize, zones: &[Zone]) {
+        // Zones without a sample are global or ignored, and don't play.
+        let ranges: Vec<_> = zones
+            .iter()
+            .enumerate()
+            .filter_map(|(z, zone)| {
+                let sample = zone.find_terminal(GeneratorType::SampleId)?.as_u16() as usize;
+                let keys = zone
+                    .find(GeneratorType::KeyRange)
+                    .map_or((0, 127), |a| a.as_range());
+                let vels = zone
+                    .find(GeneratorType::VelRange)
+                    .map_or((0, 127), |a| a.as_range());
+                Some((z, keys, vels, sample))
+            })
+            .collect();
+        let overlaps = |x: (u8, u8), y: (u8, u8)| x.0 <= y.1 && y.0 <= x.1;
+
+        for (i, &(a, keys_a, vels_a, sample_a)) in ranges.iter().enumerate() {
+            for &(b, keys_b, vels_b, sample_b) in &ranges[i + 1..] {
+                if !overlaps(keys_a, keys_b) || !overlaps(vels_a, vels_b) {
+                    continue;
+                }
+                let stereo_pair = sample_a != sample_b
+                    && self
+                        .get_stereo_pair(sample_a)
+                        .is_some_and(|(left, right)| left == sample_b || right == sample_b);
+                if !stereo_pair {
+                    linter.push(
+                        LintSeverity::Warning,
+                        LintCategory::Range,
+                        LintRecord::InstrumentZone {
+                            instrument,
+                            zone: b,
+                        },
+                        format!("Key and velocity ranges overlap zone {a}."),
+                    );
+                }
+            }
+        }
+    }
+
+    fn lint_samples(&self, linter: &mut Linter) {
+        let headers = &self.hydra.sample_headers;
+        let sample_count = headers.len().saturating_sub(1);
+        let point_count = self.sample_data.get_sample_count() as u32;
+
+        for (i, header) in headers.iter().take(sample_count).enumerate() {
+            let record = LintRecord::Sample(i);
+            let mut error = |message: String| {
+                linter.push(LintSeverity::Error, LintCategory::Sample, record, message)
+            };
+
+            if header.start >= header.end {
+                error(format!(
+                    "Sample start {} is not before end {}.",
+                    header.start, header.end
+                ));
+            }
+            if !header.is_rom() && header.end > point_count {
+                error(format!(
+                    "Sample end {} is past sample data of {point_count} points.",
+                    header.end
+                ));
+            }
+            if header.sample_rate == 0 {
+                error("Sample rate is 0.".into());
+            }
+
+            if header.start_loop < header.start
+                || header.end_loop > header.end
+                || header.start_loop >= header.end_loop
+            {
+                linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Sample,
+                    record,
+                    format!(
+                        "Loop {}-{} is not within sample {}-{}.",
+                        header.start_loop, header.end_loop, header.start, header.end
+                    ),
+                );
+            }
+
+            if header.original_key > 127 && header.original_key != 255 {
+                linter.push(
+                    LintSeverity::Warning,
+                    LintCategory::Sample,
+                    record,
+                    format!("Original key {} is invalid.", header.original_key),
+                );
+            }
+
+            match header.get_link() {
+                None => linter.push(
+                    LintSeverity::Error,
+                    LintCategory::Sample,
+                    record,
+                    format!("Unknown sample type {:#06x}.", header.sample_type),
+                ),
+                Some(SampleLink::Mono) => (),
+                Some(_) if header.sample_link as usize >= sample_count => linter.push(
+                    LintSeverity::Error,
+                    LintCategory::Reference,
+                    record,
+                    format!("Linked sample {} does not exist.", header.sample_link),
+                ),
+                Some(SampleLink::Left | SampleLink::Right) if self.get_stereo_pair(i).is_none() => {
+                    linter.push(
+                        LintSeverity::Warning,
+                        LintCategory::Reference,
+                        record,
+                        format!(
+                            "Linked sample {} is not the other half of a stereo pair.",
+                            header.sample_link
+                        ),
+                    )
+                }
+                Some(_) => (),
+            }
+        }
+    }
+}
+
+/// Index fields of consecutive records must not decrease, and must not point past `len`.
+fn lint_indices(
+    linter: &mut Linter,
+    indices: &[u16],
+    len: usize,
+    record: impl Fn(usize) -> LintRecord,
+    target: &str,
+) {
+    for (i, index) in indices.iter().enumerate() {
+        if *index as usize > len {
+            linter.push(
+                LintSeverity::Error,
+                LintCategory::Structure,
+                record(i),
+                format!("Record {i}: {target} index {index} is out of range."),
+            );
+        }
+        if i > 0 && *index < indices[i - 1] {
+            linter.push(
+                LintSeverity::Error,
+                LintCategory::Structure,
+                record(i),
+                format!("Record {i}: {target} index {index} is less than the previous one."),
+            );
+        }
+    }
+}
+
+fn lint_modulators(linter: &mut Linter, record: LintRecord, zone: &Zone) {
+    for (m, modulator) in zone.modulators.iter().enumerate() {
+        let curves_valid =
+            modulator.source.curve().is_some() && modulator.amount_source.curve().is_some();
+        let destination_valid = modulator.destination & 0x8000 != 0
+            || GeneratorType::try_from(modulator.destination).is_ok();
+        if !curves_valid || !destination_valid || modulator.get_transform().is_none() {
+            linter.push(
+                LintSeverity::Warning,
+                LintCategory::Modulator,
+                record,
+                format!("Modulator {m} has an invalid source, destination or transform."),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sf2::{
+        edit::{InstrumentRecords, PresetRecords, ZoneRecords},
+        generator::{Generator, GeneratorAmount},
+        hydra::{Hydra, PresetHeader},
+        sample::{SampleData, SampleHeader},
+    };
+
+    fn generator(generator_type: GeneratorType, value: u16) -> Generator {
+        Generator::new(generator_type, GeneratorAmount::from_u16(value))
+    }
+
+    fn zone(generators: Vec<Generator>) -> ZoneRecords {
+        ZoneRecords {
+            generators,
+            modulators: vec![],
+        }
+    }
+
+    fn sample(start: u32, end: u32, link: u16, sample_type: u16) -> SampleHeader {
+        SampleHeader {
+            sample_link: link,
+            sample_type,
+            ..SampleHeader::test("Sample", start, end)
+        }
+    }
+
+    fn font(
+        preset_zones: Vec<ZoneRecords>,
+        instrument_zones: Vec<ZoneRecords>,
+        samples: Vec<SampleHeader>,
+    ) -> SoundFont {
+        let presets = [PresetRecords {
+            header: PresetHeader {
+                name: "Preset".into(),
+                ..Default::default()
+            },
+            zones: preset_zones,
+        }];
+        let instruments = [InstrumentRecords {
+            name: "Instrument".into(),
+            zones: instrument_zones,
+        }];
+        SoundFont::new(
+            Default::default(),
+            SampleData::new(vec![0; 100], None),
+            Hydra::from_records(&presets, &instruments, samples),
+        )
+    }
+
+    #[test]
+    fn test_valid() {
+        let font = font(
+            vec![zone(vec![generator(GeneratorType::Instrument, 0)])],
+            vec![
+                zone(vec![generator(GeneratorType::SampleId, 0)]),
+                zone(vec![generator(GeneratorType::SampleId, 1)]),
+            ],
+            vec![sample(0, 10, 1, 4), sample(20, 30, 0, 2)],
+        );
+        assert_eq!(font.validate(), vec![]);
+    }
+
+    #[test]
+    fn test_broken() {
+        let font = font(
+            vec![zone(vec![generator(GeneratorType::Instrument, 3)])],
+            vec![zone(vec![
+                generator(GeneratorType::SampleId, 0),
+                Generator::new(GeneratorType::KeyRange, GeneratorAmount::from_range(0, 60)),
+            ])],
+            vec![sample(0, 200, 5, 4)],
+        );
+        let issues = font.validate();
+        let has = |category, record| {
+            issues
+                .iter()
+                .any(|issue| issue.category == category && issue.record == record)
+        };
+
+        assert!(has(
+            LintCategory::Reference,
+            LintRecord::PresetZone { preset: 0, zone: 0 }
+        ));
+        assert!(has(
+            LintCategory::Generator,
+            LintRecord::InstrumentZone {
+                instrument: 0,
+                zone: 0
+            }
+        ));
+        assert!(has(LintCategory::Sample, LintRecord::Sample(0)));
+        assert!(has(LintCategory::Reference, LintRecord::Sample(0)));
+
+        // Ordered by record, whichever check found them
+        let order: Vec<_> = issues.iter().map(|issue| issue.record.order()).collect();
+        assert!(order.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(issues.last().unwrap().record, LintRecord::Sample(0));
+    }
+}
@@ -0,0 +1,240 @@
+//! Sample export to WAV
+//!
+//! Samples are written as PCM, 24-bit if the SoundFont has `sm24` data. Root key, pitch
+//! correction and loop points go into a `smpl` chunk, which samplers read on import. A stereo
+//! pair is written as one file.
+
+use std::io;
+
+use super::{
+    riff::RiffChunk,
+    sample::{SampleHeader, SampleLink},
+    SoundFont,
+};
+
+impl SoundFont {
+    /// Samples to export, one per file. The right half of a stereo pair is left out, because
+    /// it's written together with the left half. ROM samples and the terminal record are left
+    /// out as well.
+    pub fn get_export_samples(&self) -> Vec<usize> {
+        let headers = &self.hydra.sample_headers;
+        let count = headers.len().saturating_sub(1);
+        (0..count)
+            .filter(|i| {
+                let header = &headers[*i];
+                if header.is_rom() {
+                    return false;
+                }
+                let is_right = header.get_link() == Some(SampleLink::Right);
+                !(is_right && self.get_stereo_pair(*i).is_some())
+            })
+            .collect()
+    }
+
+    /// Left and right sample indices, if the sample is half of a valid stereo pair.
+    pub fn get_stereo_pair(&self, sample_index: usize) -> Option<(usize, usize)> {
+        let headers = &self.hydra.sample_headers;
+        let header = headers.get(sample_index)?;
+        let link = header.sample_link as usize;
+        let other = headers.get(link)?;
+        if other.sample_link as usize != sample_index {
+            return None;
+        }
+        match (header.get_link()?, other.get_link()?) {
+            (SampleLink::Left, SampleLink::Right) => Some((sample_index, link)),
+            (SampleLink::Right, SampleLink::Left) => Some((link, sample_index)),
+            _ => None,
+        }
+    }
+
+    /// Write a sample as a WAV file. Half of a stereo pair produces a stereo file.
+    pub fn write_wav<W>(&self, sample_index: usize, writer: &mut W) -> Result<(), io::Error>
+    where
+        W: io::Write,
+    {
+        let headers = &self.hydra.sample_headers;
+        let Some(header) = headers.get(sample_index) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("No sample at index {sample_index}"),
+            ));
+        };
+
+        let channels: Vec<&SampleHeader> = match self.get_stereo_pair(sample_index) {
+            Some((left, right)) => vec![&headers[left], &headers[right]],
+            None => vec![header],
+        };
+        // The left sample carries rate, pitch and loops.
+        let header = channels[0];
+
+        let frames = channels
+            .iter()
+            .map(|channel| self.sample_data.get_points(channel).len())
+            .min()
+            .unwrap_or(0);
+        let bytes_per_point = match self.sample_data.get_sm24() {
+            Some(_) => 3,
+            None => 2,
+        };
+
+        let mut data = Vec::with_capacity(frames * channels.len() * bytes_per_point);
+        for frame in 0..frames {
+            for channel in &channels {
+                let index = channel.start as usize + frame;
+                let point = self.sample_data.get_point_24(index).to_le_bytes();
+                data.extend_from_slice(&point[3 - bytes_per_point..3]);
+            }
+        }
+
+        let wave = RiffChunk::new_list(
+            *b"RIFF",
+            *b"WAVE",
+            &[
+                fmt_chunk(
+                    channels.len() as u16,
+                    header.sample_rate,
+                    bytes_per_point as u16 * 8,
+                ),
+                smpl_chunk(header),
+                RiffChunk::new(*b"data", data),
+            ],
+        );
+        wave.write(writer)
+    }
+}
+
+fn fmt_chunk(channels: u16, sample_rate: u32, bits_per_sample: u16) -> RiffChunk {
+    let block_align = channels * bits_per_sample / 8;
+    let mut data = vec![];
+    data.extend(1_u16.to_le_bytes()); // PCM
+    data.extend(channels.to_le_bytes());
+    data.extend(sample_rate.to_le_bytes());
+    data.extend((sample_rate * block_align as u32).to_le_bytes());
+    data.extend(block_align.to_le_bytes());
+    data.extend(bits_per_sample.to_le_bytes());
+    RiffChunk::new(*b"fmt ", data)
+}
+
+/// Sampler chunk with the root key, tuning and the loop, if the sample has a valid one.
+fn smpl_chunk(header: &SampleHeader) -> RiffChunk {
+    // Pitch correction is applied on playback, so the recorded pitch is the opposite.
+    let cents = header.original_key as i32 * 100 - header.pitch_correction as i32;
+    let unity_note = cents.div_euclid(100) as u32;
+    let pitch_fraction = ((cents.rem_euclid(100) as u64) << 32) / 100;
+
+    let sample_period = match header.sample_rate {
+        0 => 0,
+        rate => 1_000_000_000 / rate,
+    };
+
+    let has_loop = header.start <= header.start_loop
+        && header.start_loop < header.end_loop
+        && header.end_loop <= header.end;
+
+    let mut data = vec![];
+    data.extend(0_u32.to_le_bytes()); // manufacturer
+    data.extend(0_u32.to_le_bytes()); // product
+    data.extend(sample_period.to_le_bytes());
+    data.extend(unity_note.to_le_bytes());
+    data.extend((pitch_fraction as u32).to_le_bytes());
+    data.extend(0_u32.to_le_bytes()); // SMPTE format
+    data.extend(0_u32.to_le_bytes()); // SMPTE offset
+    data.extend((has_loop as u32).to_le_bytes());
+    data.extend(0_u32.to_le_bytes()); // sampler data
+    if has_loop {
+        data.extend(0_u32.to_le_bytes()); // cue point id
+        data.extend(0_u32.to_le_bytes()); // forward loop
+        data.extend((header.start_loop - header.start).to_le_bytes());
+        // Loop end is inclusive in WAV, exclusive in SF2.
+        data.extend((header.end_loop - header.start - 1).to_le_bytes());
+        data.extend(0_u32.to_le_bytes()); // fraction
+        data.extend(0_u32.to_le_bytes()); // play forever
+    }
+    RiffChunk::new(*b"smpl", data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sf2::{hydra::Hydra, sample::SampleData};
+
+    fn sample(name: &str, start: u32, end: u32, link: u16, sample_type: u16) -> SampleHeader {
+        SampleHeader {
+            sample_rate: 22050,
+            pitch_correction: 10,
+            sample_link: link,
+            sample_type,
+            ..SampleHeader::test(name, start, end)
+        }
+    }
+
+    fn test_font(sm24: Option<Vec<u8>>) -> SoundFont {
+        let hydra = Hydra {
+            sample_headers: vec![
+                sample("Mono", 0, 4, 0, 1),
+                sample("Left", 4, 8, 2, 4),
+                sample("Right", 8, 12, 1, 2),
+                SampleHeader {
+                    name: "EOS".into(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let smpl = (0..12).collect();
+        SoundFont::new(Default::default(), SampleData::new(smpl, sm24), hydra)
+    }
+
+    fn find_chunk<'a>(wav: &'a [u8], id: &[u8; 4]) -> &'a [u8] {
+        let pos = wav.windows(4).position(|w| w == id).unwrap();
+        let len = u32::from_le_bytes(wav[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        &wav[pos + 8..pos + 8 + len]
+    }
+
+    #[test]
+    fn test_export_samples() {
+        let font = test_font(None);
+        assert_eq!(font.get_export_samples(), vec![0, 1]);
+        assert_eq!(font.get_stereo_pair(2), Some((1, 2)));
+        assert_eq!(font.get_stereo_pair(0), None);
+    }
+
+    #[test]
+    fn test_write_mono() {
+        let font = test_font(None);
+        let mut wav = vec![];
+        font.write_wav(0, &mut wav).unwrap();
+        assert_eq!(&wav[8..12], b"WAVE");
+
+        let fmt = find_chunk(&wav, b"fmt ");
+        assert_eq!(u16::from_le_bytes([fmt[2], fmt[3]]), 1);
+        assert_eq!(u16::from_le_bytes([fmt[14], fmt[15]]), 16);
+
+        let data = find_chunk(&wav, b"data");
+        assert_eq!(data, &[0, 0, 1, 0, 2, 0, 3, 0]);
+
+        let smpl = find_chunk(&wav, b"smpl");
+        let u32_at = |i: usize| u32::from_le_bytes(smpl[i..i + 4].try_into().unwrap());
+        // 60 with +10 cents correction was recorded at 59.90
+        assert_eq!(u32_at(12), 59);
+        assert_eq!(u32_at(16), ((90_u64 << 32) / 100) as u32);
+        assert_eq!(u32_at(28), 1);
+        assert_eq!((u32_at(44), u32_at(48)), (1, 2));
+    }
+
+    #[test]
+    fn test_write_stereo_24() {
+        let font = test_font(Some(vec![0xAB; 12]));
+        let mut wav = vec![];
+        font.write_wav(2, &mut wav).unwrap();
+
+        let fmt = find_chunk(&wav, b"fmt ");
+        assert_eq!(u16::from_le_bytes([fmt[2], fmt[3]]), 2);
+        assert_eq!(u16::from_le_bytes([fmt[14], fmt[15]]), 24);
+
+        let data = find_chunk(&wav, b"data");
+        assert_eq!(data.len(), 4 * 2 * 3);
+        // First frame: left point 4, right point 8
+        assert_eq!(&data[..6], &[0xAB, 4, 0, 0xAB, 8, 0]);
+    }
+}
@@ -0,0 +1,520 @@
+//! Conversion to SoundFont
+//!
+//! The SFZ file becomes one instrument with a zone per region, played by preset 0 of bank 0.
+//! Each sample file becomes one sample. Opcodes with an SF2 generator of the same meaning are
+//! converted:
+//! - `key`, `lokey`, `hikey`, `lovel`, `hivel`, `pitch_keycenter`
+//! - `tune`, `transpose`, `pitch_keytrack`, `volume`, `pan`
+//! - `offset`, `end`, `loop_mode`, `loop_start`, `loop_end`
+//! - `ampeg_*`, `fileg_*` and `pitcheg_*` envelopes. The filter and pitch envelopes share the
+//!   SF2 modulation envelope.
+//! - `cutoff`, `resonance`, and the `amplfo_*`, `fillfo_*` and `pitchlfo_*` LFOs
+//! - `group` and `off_by`, as exclusive classes
+//!
+//! Other opcodes are ignored. Regions triggered by anything but note-on, and regions that play a
+//! generated waveform instead of a file, are left out.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dls::wave::DlsWave,
+    sf2::{
+        edit::{InstrumentRecords, PresetRecords, ZoneBuilder, ZoneRecords},
+        generator::GeneratorType,
+        hydra::{Hydra, PresetHeader},
+        info::{SoundFontInfo, SoundFontVersion},
+        riff::RiffChunk,
+        sample::{clamp_loop, SampleData, SampleHeader, SampleLink, SAMPLE_PADDING},
+        SoundFont,
+    },
+    synth::CENTS_ZERO_HZ,
+};
+
+use super::{SfzError, SfzFile, SfzRegion};
+
+/// Points, root key and loop of a WAV file.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct WavSample {
+    /// First channel as 16-bit PCM
+    pub points: Vec<i16>,
+    pub sample_rate: u32,
+    /// From the `smpl` chunk
+    pub root_key: Option<u8>,
+    /// First loop of the `smpl` chunk. The end is exclusive.
+    pub loop_points: Option<(u32, u32)>,
+}
+
+impl WavSample {
+    pub fn read(path: &Path) -> Result<Self, SfzError> {
+        let file_error = |e| SfzError::FileError {
+            path: path.to_path_buf(),
+            source: e,
+        };
+        let invalid = |e| SfzError::InvalidSample {
+            path: path.to_path_buf(),
+            source: e,
+        };
+
+        let file = File::open(path).map_err(file_error)?;
+        let riff = RiffChunk::read(&mut BufReader::new(file)).map_err(|e| invalid(e.into()))?;
+        if &riff.get_id() != b"RIFF" || riff.get_form_type() != Some(*b"WAVE") {
+            return Err(SfzError::NotAWav(path.to_path_buf()));
+        }
+        // A WAV file has the same chunks as a DLS wave.
+        let wave = DlsWave::parse(&riff).map_err(invalid)?;
+
+        let mut sample = Self {
+            points: wave.get_points(),
+            sample_rate: wave.format.sample_rate,
+            ..Default::default()
+        };
+        let smpl = riff
+            .subchunks()
+            .map_err(|e| invalid(e.into()))?
+            .into_iter()
+            .find(|chunk| &chunk.get_id() == b"smpl");
+        if let Some(smpl) = smpl {
+            let data = smpl.get_data();
+            let u32_at = |i: usize| {
+                data.get(i..i + 4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            };
+            sample.root_key = u32_at(12).filter(|key| *key <= 127).map(|key| key as u8);
+            if u32_at(28).unwrap_or(0) > 0 {
+                // Loop end is inclusive in WAV.
+                if let (Some(start), Some(end)) = (u32_at(44), u32_at(48)) {
+                    sample.loop_points = Some((start, end.saturating_add(1)));
+                }
+            }
+        }
+        Ok(sample)
+    }
+}
+
+impl SfzFile {
+    /// Convert to a SoundFont, loading samples from disk.
+    pub fn to_soundfont(&self) -> Result<SoundFont, SfzError> {
+        self.to_soundfont_with(WavSample::read)
+    }
+
+    /// Convert to a SoundFont, loading samples with `load`.
+    pub fn to_soundfont_with<L>(&self, mut load: L) -> Result<SoundFont, SfzError>
+    where
+        L: FnMut(&Path) -> Result<WavSample, SfzError>,
+    {
+        let mut smpl = vec![];
+        let mut sample_headers = vec![];
+        let mut sample_indices: HashMap<PathBuf, usize> = HashMap::new();
+        let mut zones = vec![];
+
+        // Groups that cut off others need the exclusive class of those they cut off.
+        let off_by: HashSet<&str> = self
+            .regions
+            .iter()
+            .filter_map(|region| region.get("off_by"))
+            .collect();
+
+        for region in &self.regions {
+            let trigger = region.get("trigger").unwrap_or("attack");
+            if trigger != "attack" || region.get("sample").is_some_and(|s| s.starts_with('*')) {
+                continue;
+            }
+            let Some(path) = self.get_sample_path(region) else {
+                continue;
+            };
+
+            let sample_index = match sample_indices.get(&path) {
+                Some(index) => *index,
+                None => {
+                    let wav = load(&path)?;
+                    let name = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+                    sample_headers.push(add_sample(&wav, name.unwrap_or_default(), &mut smpl));
+                    sample_indices.insert(path, sample_headers.len() - 1);
+                    sample_headers.len() - 1
+                }
+            };
+
+            let exclusive_class = region
+                .get("off_by")
+                .or(region.get("group").filter(|group| off_by.contains(group)))
+                .and_then(|class| class.parse::<i32>().ok());
+            zones.push(region_zone(
+                region,
+                &sample_headers[sample_index],
+                sample_index,
+                exclusive_class,
+            ));
+        }
+
+        let instrument = InstrumentRecords {
+            name: self.name.chars().take(20).collect(),
+            zones,
+        };
+        let preset = PresetRecords {
+            header: PresetHeader {
+                name: instrument.name.clone(),
+                ..Default::default()
+            },
+            zones: vec![ZoneBuilder::default()
+                .set(GeneratorType::Instrument, 0)
+                .build()],
+        };
+        let info = SoundFontInfo {
+            version: SoundFontVersion { major: 2, minor: 4 },
+            sound_engine: "EMU8000".into(),
+            bank_name: self.name.clone(),
+            ..Default::default()
+        };
+        Ok(SoundFont::new(
+            info,
+            SampleData::new(smpl, None),
+            Hydra::from_records(&[preset], &[instrument], sample_headers),
+        ))
+    }
+}
+
+/// Append the points of a WAV sample to `smpl`, and make its header.
+fn add_sample(wav: &WavSample, name: String, smpl: &mut Vec<i16>) -> SampleHeader {
+    let start = smpl.len() as u32;
+    let end = start + wav.points.len() as u32;
+    smpl.extend(&wav.points);
+    smpl.resize(smpl.len() + SAMPLE_PADDING, 0);
+
+    // Bogus loop points stay within the sample.
+    let (start_loop, end_loop) =
+        clamp_loop(start, end, wav.loop_points.unwrap_or((0, end - start)));
+    SampleHeader {
+        name: name.chars().take(20).collect(),
+        start,
+        end,
+        start_loop,
+        end_loop,
+        sample_rate: wav.sample_rate,
+        original_key: wav.root_key.unwrap_or(60),
+        pitch_correction: 0,
+        sample_link: 0,
+        sample_type: SampleLink::Mono as u16,
+    }
+}
+
+fn region_zone(
+    region: &SfzRegion,
+    header: &SampleHeader,
+    sample_index: usize,
+    exclusive_class: Option<i32>,
+) -> ZoneRecords {
+    let key = region.get_key("key");
+    let key_range = (
+        region.get_key("lokey").or(key).unwrap_or(0),
+        region.get_key("hikey").or(key).unwrap_or(127),
+    );
+    let vel_range = (
+        region
+            .get_f64("lovel")
+            .map_or(0, |v| v.clamp(0.0, 127.0) as u8),
+        region
+            .get_f64("hivel")
+            .map_or(127, |v| v.clamp(0.0, 127.0) as u8),
+    );
+    let mut zone = ZoneBuilder::default()
+        .set_range(GeneratorType::KeyRange, key_range)
+        .set_range(GeneratorType::VelRange, vel_range);
+    let mut set = |generator_type, value: Option<i32>| {
+        if let Some(value) = value {
+            zone = std::mem::take(&mut zone).set(generator_type, value);
+        }
+    };
+    let number = |opcode: &str| region.get_f64(opcode);
+    let any_number = |opcodes: &[&str]| opcodes.iter().find_map(|opcode| number(opcode));
+
+    // Pitch
+    let keycenter = match region.get("pitch_keycenter") {
+        // Use the root key of the file.
+        Some("sample") => None,
+        Some(_) => region.get_key("pitch_keycenter"),
+        None => Some(key.unwrap_or(60)),
+    };
+    set(
+        GeneratorType::OverridingRootKey,
+        keycenter
+            .filter(|k| *k != header.original_key)
+            .map(i32::from),
+    );
+    set(GeneratorType::FineTune, number("tune").map(round));
+    set(GeneratorType::CoarseTune, number("transpose").map(round));
+    set(
+        GeneratorType::ScaleTuning,
+        number("pitch_keytrack").map(round),
+    );
+
+    // Amplitude, in dB and percent
+    set(
+        GeneratorType::InitialAttenuation,
+        number("volume").map(|db| round(-db * 10.0).clamp(0, 1440)),
+    );
+    set(
+        GeneratorType::Pan,
+        number("pan").map(|pan| round(pan * 5.0).clamp(-500, 500)),
+    );
+
+    // Sample and loop positions
+    let length = header.end - header.start;
+    set(GeneratorType::SampleModes, Some(loop_mode(region, header)));
+    set(
+        GeneratorType::ExclusiveClass,
+        exclusive_class.filter(|c| *c > 0),
+    );
+    let offsets = [
+        (number("offset"), 0),
+        (number("end").map(|end| end + 1.0), length),
+        (
+            any_number(&["loop_start", "loopstart"]),
+            header.start_loop - header.start,
+        ),
+        (
+            any_number(&["loop_end", "loopend"]).map(|end| end + 1.0),
+            header.end_loop - header.start,
+        ),
+    ];
+
+    // Envelopes, in seconds and percent
+    for (stage, vol_env, mod_env) in [
+        (
+            "delay",
+            GeneratorType::DelayVolEnv,
+            GeneratorType::DelayModEnv,
+        ),
+        (
+            "attack",
+            GeneratorType::AttackVolEnv,
+            GeneratorType::AttackModEnv,
+        ),
+        ("hold", GeneratorType::HoldVolEnv, GeneratorType::HoldModEnv),
+        (
+            "decay",
+            GeneratorType::DecayVolEnv,
+            GeneratorType::DecayModEnv,
+        ),
+        (
+            "release",
+            GeneratorType::ReleaseVolEnv,
+            GeneratorType::ReleaseModEnv,
+        ),
+    ] {
+        set(vol_env, number(&format!("ampeg_{stage}")).map(timecents));
+        set(
+            mod_env,
+            any_number(&[&format!("fileg_{stage}"), &format!("pitcheg_{stage}")]).map(timecents),
+        );
+    }
+    set(
+        GeneratorType::SustainVolEnv,
+        number("ampeg_sustain").map(|percent| match percent {
+            ..=0.0 => 1440,
+            percent => round(-200.0 * (percent.min(100.0) / 100.0).log10()),
+        }),
+    );
+    set(
+        GeneratorType::SustainModEnv,
+        any_number(&["fileg_sustain", "pitcheg_sustain"])
+            .map(|percent| 1000 - round(percent.clamp(0.0, 100.0) * 10.0)),
+    );
+    set(
+        GeneratorType::ModEnvToFilterFc,
+        number("fileg_depth").map(round),
+    );
+    set(
+        GeneratorType::ModEnvToPitch,
+        number("pitcheg_depth").map(round),
+    );
+
+    // Filter, in Hz and dB
+    set(
+        GeneratorType::InitialFilterFc,
+        number("cutoff").map(|hz| absolute_cents(hz).clamp(1500, 13500)),
+    );
+    set(
+        GeneratorType::InitialFilterQ,
+        number("resonance").map(|db| round(db * 10.0).clamp(0, 960)),
+    );
+
+    // LFOs, in Hz and seconds. Amplitude and filter LFOs share the SF2 modulation LFO.
+    set(
+        GeneratorType::FreqModLfo,
+        any_number(&["fillfo_freq", "amplfo_freq"]).map(absolute_cents),
+    );
+    set(
+        GeneratorType::DelayModLfo,
+        any_number(&["fillfo_delay", "amplfo_delay"]).map(timecents),
+    );
+    set(
+        GeneratorType::ModLfoToVolume,
+        number("amplfo_depth").map(|db| round(db * 10.0)),
+    );
+    set(
+        GeneratorType::ModLfoToFilterFc,
+        number("fillfo_depth").map(round),
+    );
+    set(
+        GeneratorType::FreqVibLfo,
+        number("pitchlfo_freq").map(absolute_cents),
+    );
+    set(
+        GeneratorType::DelayVibLfo,
+        number("pitchlfo_delay").map(timecents),
+    );
+    set(
+        GeneratorType::VibLfoToPitch,
+        number("pitchlfo_depth").map(round),
+    );
+
+    let [start, end, start_loop, end_loop] = offsets;
+    let offset =
+        |(value, base): (Option<f64>, u32)| value.map(|v| round(v).saturating_sub(base as i32));
+    for (value, fine, coarse) in [
+        (
+            offset(start),
+            GeneratorType::StartAddrsOffset,
+            GeneratorType::StartAddrsCoarseOffset,
+        ),
+        (
+            offset(end),
+            GeneratorType::EndAddrsOffset,
+            GeneratorType::EndAddrsCoarseOffset,
+        ),
+        (
+            offset(start_loop),
+            GeneratorType::StartloopAddrsOffset,
+            GeneratorType::StartloopAddrsCoarseOffset,
+        ),
+        (
+            offset(end_loop),
+            GeneratorType::EndloopAddrsOffset,
+            GeneratorType::EndloopAddrsCoarseOffset,
+        ),
+    ] {
+        if let Some(value) = value {
+            zone = zone.set_offset(fine, coarse, value);
+        }
+    }
+
+    zone.set(GeneratorType::SampleId, sample_index as i32)
+        .build()
+}
+
+/// SF2 sample mode. Samples with a loop in the file loop by default.
+fn loop_mode(region: &SfzRegion, header: &SampleHeader) -> i32 {
+    match region.get_any(&["loop_mode", "loopmode"]) {
+        Some("loop_continuous") => 1,
+        Some("loop_sustain") => 3,
+        Some(_) => 0,
+        None => {
+            let has_loop = header.start_loop > header.start || header.end_loop < header.end;
+            has_loop as i32
+        }
+    }
+}
+
+fn round(value: f64) -> i32 {
+    value.round() as i32
+}
+
+fn timecents(seconds: f64) -> i32 {
+    match seconds {
+        ..=0.0 => -12000,
+        seconds => round(1200.0 * seconds.log2()).clamp(-12000, 8000),
+    }
+}
+
+/// Frequency in cents above key 0
+fn absolute_cents(hz: f64) -> i32 {
+    match hz {
+        ..=0.0 => -16000,
+        hz => round(1200.0 * (hz / CENTS_ZERO_HZ).log2()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::keys::MidiKey;
+
+    fn fake_sample(path: &Path) -> Result<WavSample, SfzError> {
+        let loop_points = match path.file_name().and_then(|name| name.to_str()) {
+            Some("loop.wav") => Some((10, 90)),
+            Some("bad_loop.wav") => Some((50, u32::MAX)),
+            _ => None,
+        };
+        Ok(WavSample {
+            points: vec![0; 100],
+            sample_rate: 44100,
+            root_key: None,
+            loop_points,
+        })
+    }
+
+    #[test]
+    fn test_to_soundfont() {
+        let text = "
+            <group> ampeg_release=0.5 tune=-10
+            <region> sample=loop.wav lokey=c3 hikey=b3 pitch_keycenter=c3
+            <region> sample=loop.wav lokey=c4 hikey=b4 loop_end=79 volume=-6
+            <group> group=1
+            <region> sample=hat.wav key=42
+            <region> sample=open.wav key=46 off_by=1 trigger=attack
+            <region> sample=release.wav trigger=release";
+        let file = SfzFile::parse(text, Path::new("/sfz")).unwrap();
+        let font = file.to_soundfont_with(fake_sample).unwrap();
+        let regions = |key: u8| font.get_regions(0, 0, MidiKey::try_from(key).unwrap(), 100);
+
+        let low = &regions(50)[0];
+        assert_eq!(low.get(GeneratorType::OverridingRootKey), 48);
+        assert_eq!(low.get(GeneratorType::ReleaseVolEnv), -1200);
+        assert_eq!(low.get(GeneratorType::FineTune), -10);
+        assert_eq!(low.get(GeneratorType::SampleModes), 1);
+
+        let high = &regions(60)[0];
+        assert_eq!(high.get_sample_index(), low.get_sample_index());
+        assert_eq!(high.get(GeneratorType::OverridingRootKey), -1);
+        assert_eq!(high.get(GeneratorType::InitialAttenuation), 60);
+        assert_eq!(high.get(GeneratorType::EndloopAddrsOffset), -10);
+
+        let hat = &regions(42)[0];
+        let open = &regions(46)[0];
+        assert_eq!(hat.get(GeneratorType::SampleModes), 0);
+        assert_eq!(hat.get(GeneratorType::ExclusiveClass), 1);
+        assert_eq!(open.get(GeneratorType::ExclusiveClass), 1);
+
+        // The release region is left out.
+        assert_eq!(font.get_hydra().sample_headers.len(), 4);
+        assert!(regions(80).is_empty());
+    }
+
+    #[test]
+    fn test_bad_loop() {
+        let text = "<region> sample=loop.wav key=60\n\
+            <region> sample=bad_loop.wav key=61 loop_start=-3000000000";
+        let file = SfzFile::parse(text, Path::new("/sfz")).unwrap();
+        let font = file.to_soundfont_with(fake_sample).unwrap();
+        let header = &font.get_hydra().sample_headers[1];
+        assert_eq!(header.start_loop - header.start, 50);
+        assert_eq!(header.end_loop, header.end);
+
+        let region = &font.get_regions(0, 0, MidiKey::try_from(61).unwrap(), 100)[0];
+        assert!(region.get(GeneratorType::StartloopAddrsCoarseOffset) < 0);
+    }
+
+    #[test]
+    fn test_missing_sample() {
+        let file = SfzFile::parse("<region> sample=missing.wav", Path::new("/sfz")).unwrap();
+        assert!(matches!(
+            file.to_soundfont(),
+            Err(SfzError::FileError { .. })
+        ));
+    }
+}
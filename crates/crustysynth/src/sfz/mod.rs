@@ -0,0 +1,251 @@
+//! SFZ instruments
+//!
+//! An SFZ file is text that maps WAV samples to key and velocity ranges. Opcodes under a header
+//! apply to everything below it:
+//! - `<control>`: `default_path` for samples
+//! - `<global>`, `<master>`, `<group>`: defaults for the regions that follow
+//! - `<region>`: one sample and its parameters
+//!
+//! `#define $NAME value` and `#include "file"` are expanded before parsing. An instrument is
+//! played by converting it to a [`SoundFont`](crate::sf2::SoundFont), so it shares the region
+//! model of SoundFont presets.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::dls::DlsError;
+
+pub mod convert;
+mod parser;
+
+use parser::Token;
+
+#[derive(Debug)]
+pub enum SfzError {
+    /// Reading an SFZ, include or sample file failed.
+    FileError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    NotAWav(PathBuf),
+    InvalidSample {
+        path: PathBuf,
+        source: DlsError,
+    },
+    /// `#include`s nested too deep, likely including each other.
+    IncludeDepth(String),
+}
+impl Error for SfzError {}
+impl Display for SfzError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FileError { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::NotAWav(path) => write!(f, "{}: Not a WAV file.", path.display()),
+            Self::InvalidSample { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::IncludeDepth(path) => write!(f, "Includes nested too deep at {path}"),
+        }
+    }
+}
+
+/// All opcodes that apply to a region, including those inherited from its headers.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SfzRegion {
+    opcodes: HashMap<String, String>,
+}
+
+impl SfzRegion {
+    pub fn get(&self, opcode: &str) -> Option<&str> {
+        self.opcodes.get(opcode).map(String::as_str)
+    }
+
+    /// Numeric opcode. Unparseable values count as missing.
+    pub fn get_f64(&self, opcode: &str) -> Option<f64> {
+        self.get(opcode)?.parse().ok()
+    }
+
+    /// Key opcode, as a number or a note name like `c#4`. Middle C is `c4`, key 60.
+    pub fn get_key(&self, opcode: &str) -> Option<u8> {
+        parse_key(self.get(opcode)?)
+    }
+
+    /// First opcode present of a name and its aliases
+    pub fn get_any(&self, opcodes: &[&str]) -> Option<&str> {
+        opcodes.iter().find_map(|opcode| self.get(opcode))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SfzFile {
+    /// Directory sample paths are relative to
+    dir: PathBuf,
+    name: String,
+    control: HashMap<String, String>,
+    regions: Vec<SfzRegion>,
+}
+
+impl SfzFile {
+    pub fn read(path: &Path) -> Result<Self, SfzError> {
+        let text = fs::read_to_string(path).map_err(|e| SfzError::FileError {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut file = Self::parse(&text, dir)?;
+        if let Some(stem) = path.file_stem() {
+            file.name = stem.to_string_lossy().into_owned();
+        }
+        Ok(file)
+    }
+
+    /// Parse SFZ text. Includes and samples are relative to `dir`.
+    pub fn parse(text: &str, dir: &Path) -> Result<Self, SfzError> {
+        let text = parser::preprocess(text, dir)?;
+
+        let mut file = Self {
+            dir: dir.to_path_buf(),
+            ..Default::default()
+        };
+        // Opcodes of global, master and group
+        let mut levels: [HashMap<String, String>; 3] = Default::default();
+        let mut current = None;
+
+        for token in parser::tokenize(&text) {
+            match token {
+                Token::Header(header) => {
+                    current = match header.as_str() {
+                        "control" => Some(0),
+                        "global" => Some(1),
+                        "master" => Some(2),
+                        "group" => Some(3),
+                        "region" => Some(4),
+                        // Curves, effects and so on aren't supported.
+                        _ => None,
+                    };
+                    let Some(level) = current else {
+                        continue;
+                    };
+                    match level {
+                        // A header resets itself and every level below it.
+                        1..=3 => levels.iter_mut().skip(level - 1).for_each(HashMap::clear),
+                        4 => file.regions.push(SfzRegion {
+                            opcodes: levels
+                                .iter()
+                                .flatten()
+                                .map(|(name, value)| (name.clone(), value.clone()))
+                                .collect(),
+                        }),
+                        _ => (),
+                    }
+                }
+                Token::Opcode(name, value) => match current {
+                    Some(0) => {
+                        file.control.insert(name, value);
+                    }
+                    Some(4) => {
+                        if let Some(region) = file.regions.last_mut() {
+                            region.opcodes.insert(name, value);
+                        }
+                    }
+                    Some(level) => {
+                        levels[level - 1].insert(name, value);
+                    }
+                    None => (),
+                },
+            }
+        }
+        Ok(file)
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_regions(&self) -> &Vec<SfzRegion> {
+        &self.regions
+    }
+
+    /// Path of a region's sample, or `None` if it has no sample.
+    pub fn get_sample_path(&self, region: &SfzRegion) -> Option<PathBuf> {
+        let sample = region.get("sample")?;
+        let default_path = self.control.get("default_path").map_or("", String::as_str);
+        Some(
+            self.dir
+                .join(default_path.replace('\\', "/"))
+                .join(sample.replace('\\', "/")),
+        )
+    }
+}
+
+/// Key number or note name
+fn parse_key(value: &str) -> Option<u8> {
+    if let Ok(key) = value.parse::<u8>() {
+        return (key <= 127).then_some(key);
+    }
+
+    let value = value.to_ascii_lowercase();
+    let mut chars = value.chars();
+    let semitone = match chars.next()? {
+        'c' => 0,
+        'd' => 2,
+        'e' => 4,
+        'f' => 5,
+        'g' => 7,
+        'a' => 9,
+        'b' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.chars().next()? {
+        '#' => (1, &rest[1..]),
+        'b' if rest.len() > 1 => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+    let octave: i32 = octave.parse().ok()?;
+    let key = (octave + 1) * 12 + semitone + accidental;
+    u8::try_from(key).ok().filter(|key| *key <= 127)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("60"), Some(60));
+        assert_eq!(parse_key("c4"), Some(60));
+        assert_eq!(parse_key("C#4"), Some(61));
+        assert_eq!(parse_key("eb4"), Some(63));
+        assert_eq!(parse_key("c-1"), Some(0));
+        assert_eq!(parse_key("g9"), Some(127));
+        assert_eq!(parse_key("a9"), None);
+        assert_eq!(parse_key("h4"), None);
+    }
+
+    #[test]
+    fn test_inheritance() {
+        let text = "<control> default_path=samples\\
+            <global> volume=-3 tune=5
+            <group> lokey=0 hikey=59
+            <region> sample=low.wav
+            <region> sample=low2.wav tune=-5
+            <group> lokey=60
+            <region> sample=high.wav";
+        let file = SfzFile::parse(text, Path::new("/sfz")).unwrap();
+        let regions = file.get_regions();
+        assert_eq!(regions.len(), 3);
+
+        assert_eq!(regions[0].get("volume"), Some("-3"));
+        assert_eq!(regions[0].get_key("hikey"), Some(59));
+        assert_eq!(regions[1].get_f64("tune"), Some(-5.0));
+        assert_eq!(regions[2].get("hikey"), None);
+        assert_eq!(regions[2].get("tune"), Some("5"));
+        assert_eq!(
+            file.get_sample_path(&regions[2]),
+            Some(PathBuf::from("/sfz/samples/high.wav"))
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! SFZ text: preprocessor, headers and opcodes
+
+use std::{fs, path::Path};
+
+use super::SfzError;
+
+/// Nested `#include`s beyond this are an error, as they're likely a cycle.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Header and opcodes, in file order.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) enum Token {
+    Header(String),
+    Opcode(String, String),
+}
+
+/// Expand `#include` and `#define`, and strip comments. Includes are resolved relative to `dir`.
+pub(crate) fn preprocess(text: &str, dir: &Path) -> Result<String, SfzError> {
+    let mut defines = vec![];
+    let mut out = String::new();
+    preprocess_into(text, dir, &mut defines, 0, &mut out)?;
+    Ok(out)
+}
+
+fn preprocess_into(
+    text: &str,
+    dir: &Path,
+    defines: &mut Vec<(String, String)>,
+    depth: usize,
+    out: &mut String,
+) -> Result<(), SfzError> {
+    for line in strip_comments(text).lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("#define") {
+            let mut parts = rest.split_whitespace();
+            if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+                // The name is taken as written, so a define can be redefined.
+                let value = substitute(value, defines);
+                match defines.iter_mut().find(|(old, _)| old == name) {
+                    Some((_, old)) => *old = value,
+                    None => {
+                        defines.push((name.to_string(), value));
+                        // Longest first, so `$AB` isn't replaced as `$A` followed by `B`.
+                        defines.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+                    }
+                }
+            }
+            continue;
+        }
+        let line = substitute(line, defines);
+        if let Some(rest) = line.strip_prefix("#include") {
+            if depth >= MAX_INCLUDE_DEPTH {
+                return Err(SfzError::IncludeDepth(rest.trim().to_string()));
+            }
+            let path = dir.join(rest.trim().trim_matches('"').replace('\\', "/"));
+            let included = fs::read_to_string(&path).map_err(|e| SfzError::FileError {
+                path: path.clone(),
+                source: e,
+            })?;
+            preprocess_into(&included, dir, defines, depth + 1, out)?;
+        } else {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    Ok(())
+}
+
+/// Remove `//` line comments and `/* */` block comments.
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let line_comment = rest.find("//");
+        let block_comment = rest.find("/*");
+        match (line_comment, block_comment) {
+            (Some(line), block) if block.is_none_or(|block| line < block) => {
+                out.push_str(&rest[..line]);
+                rest = rest[line..]
+                    .find('\n')
+                    .map_or("", |end| &rest[line + end..]);
+            }
+            (_, Some(block)) => {
+                out.push_str(&rest[..block]);
+                // Keep line breaks, so opcodes on either side stay apart.
+                out.push('\n');
+                rest = rest[block..]
+                    .find("*/")
+                    .map_or("", |end| &rest[block + end + 2..]);
+            }
+            _ => {
+                out.push_str(rest);
+                rest = "";
+            }
+        }
+    }
+    out
+}
+
+fn substitute(line: &str, defines: &[(String, String)]) -> String {
+    let mut line = line.to_string();
+    if line.contains('$') {
+        for (name, value) in defines {
+            line = line.replace(name.as_str(), value);
+        }
+    }
+    line
+}
+
+/// Split preprocessed text into headers and opcodes.
+///
+/// An opcode value runs until the next opcode, header or line end, so sample paths may contain
+/// spaces.
+pub(crate) fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    for line in text.lines() {
+        let mut rest = line.trim_start();
+        while !rest.is_empty() {
+            if let Some(header) = rest.strip_prefix('<') {
+                let end = header.find('>').unwrap_or(header.len());
+                tokens.push(Token::Header(header[..end].trim().to_string()));
+                rest = header.get(end + 1..).unwrap_or("").trim_start();
+                continue;
+            }
+
+            let Some(eq) = rest.find('=') else {
+                break;
+            };
+            let name = rest[..eq].trim();
+            let value_start = &rest[eq + 1..];
+            let value_end = find_value_end(value_start);
+            if !name.is_empty() && !name.contains(char::is_whitespace) {
+                tokens.push(Token::Opcode(
+                    name.to_string(),
+                    value_start[..value_end].trim().to_string(),
+                ));
+            }
+            rest = value_start[value_end..].trim_start();
+        }
+    }
+    tokens
+}
+
+/// End of an opcode value: the next header, the whitespace before the next opcode, or the end.
+fn find_value_end(value: &str) -> usize {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    for (i, c) in value.char_indices() {
+        if c == '<' {
+            return i;
+        }
+        if c.is_whitespace() {
+            let next = value[i..].trim_start();
+            let name_len = next.find(|c| !is_name_char(c)).unwrap_or(next.len());
+            if name_len > 0 && next[name_len..].starts_with('=') {
+                return i;
+            }
+        }
+    }
+    value.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opcode(name: &str, value: &str) -> Token {
+        Token::Opcode(name.into(), value.into())
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let text = "<region> sample=Grand Piano/C4 soft.wav lokey=c4 hikey=62\n<group>volume=-6";
+        assert_eq!(
+            tokenize(text),
+            vec![
+                Token::Header("region".into()),
+                opcode("sample", "Grand Piano/C4 soft.wav"),
+                opcode("lokey", "c4"),
+                opcode("hikey", "62"),
+                Token::Header("group".into()),
+                opcode("volume", "-6"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preprocess() {
+        let text = "#define $KEY 60\n#define $KEYS 72\n\
+            <region> key=$KEY // comment\n/* block\ncomment */<region> key=$KEYS\n\
+            #define $KEY 62\n<region> key=$KEY lovel=60";
+        let text = preprocess(text, Path::new(".")).unwrap();
+        assert_eq!(
+            tokenize(&text),
+            vec![
+                Token::Header("region".into()),
+                opcode("key", "60"),
+                Token::Header("region".into()),
+                opcode("key", "72"),
+                Token::Header("region".into()),
+                opcode("key", "62"),
+                opcode("lovel", "60"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_include() {
+        let result = preprocess("#include \"missing.sfz\"", Path::new("/nonexistent"));
+        assert!(matches!(result, Err(SfzError::FileError { .. })));
+    }
+}
@@ -0,0 +1,225 @@
+//! Channel state: program, controllers and pitch bend
+
+/// Registered parameter numbers handled by the synthesizer
+const RPN_PITCH_BEND_RANGE: u16 = 0x0000;
+const RPN_NULL: u16 = 0x3FFF;
+
+/// MIDI controller numbers
+pub mod controller {
+    pub const BANK_SELECT: u8 = 0;
+    pub const MODULATION: u8 = 1;
+    pub const DATA_ENTRY: u8 = 6;
+    pub const VOLUME: u8 = 7;
+    pub const PAN: u8 = 10;
+    pub const EXPRESSION: u8 = 11;
+    pub const BANK_SELECT_LSB: u8 = 32;
+    pub const DATA_ENTRY_LSB: u8 = 38;
+    pub const SUSTAIN: u8 = 64;
+    pub const NRPN_LSB: u8 = 98;
+    pub const NRPN_MSB: u8 = 99;
+    pub const RPN_LSB: u8 = 100;
+    pub const RPN_MSB: u8 = 101;
+    pub const ALL_SOUND_OFF: u8 = 120;
+    pub const RESET_ALL_CONTROLLERS: u8 = 121;
+    pub const ALL_NOTES_OFF: u8 = 123;
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Channel {
+    is_drum: bool,
+    bank_msb: u8,
+    bank_lsb: u8,
+    program: u8,
+    controllers: [u8; 128],
+    /// 14-bit, 8192 is center
+    pitch_bend: u16,
+    channel_pressure: u8,
+    /// Polyphonic key pressure per key
+    poly_pressure: [u8; 128],
+    /// Pitch bend range in cents
+    pitch_bend_range: i32,
+    rpn: u16,
+}
+
+impl Channel {
+    /// Channel 10 plays drums by default.
+    pub fn new(is_drum: bool) -> Self {
+        let mut controllers = [0; 128];
+        controllers[controller::VOLUME as usize] = 100;
+        controllers[controller::PAN as usize] = 64;
+        let mut channel = Self {
+            is_drum,
+            bank_msb: 0,
+            bank_lsb: 0,
+            program: 0,
+            controllers,
+            pitch_bend: 8192,
+            channel_pressure: 0,
+            poly_pressure: [0; 128],
+            pitch_bend_range: 200,
+            rpn: RPN_NULL,
+        };
+        channel.reset_controllers();
+        channel
+    }
+
+    /// Reset All Controllers, as RP-015 recommends: modulation, expression, pedals, parameter
+    /// selection, pitch bend and pressure. Volume, pan, effect sends and the other controllers
+    /// are part of the mix, and are kept, as are program, bank and pitch bend range.
+    pub fn reset_controllers(&mut self) {
+        self.controllers[controller::MODULATION as usize] = 0;
+        self.controllers[controller::EXPRESSION as usize] = 127;
+        // Sustain, portamento, sostenuto and soft pedals
+        self.controllers[64..=67].fill(0);
+        self.controllers[controller::NRPN_LSB as usize..=controller::RPN_MSB as usize].fill(127);
+        self.pitch_bend = 8192;
+        self.channel_pressure = 0;
+        self.poly_pressure = [0; 128];
+        self.rpn = RPN_NULL;
+    }
+
+    pub fn control_change(&mut self, control: u8, value: u8) {
+        let control = control & 0x7F;
+        let value = value & 0x7F;
+        self.controllers[control as usize] = value;
+        match control {
+            controller::BANK_SELECT => self.bank_msb = value,
+            controller::BANK_SELECT_LSB => self.bank_lsb = value,
+            controller::RPN_MSB => self.rpn = (self.rpn & 0x7F) | (value as u16) << 7,
+            controller::RPN_LSB => self.rpn = (self.rpn & 0x3F80) | value as u16,
+            // Data entry goes to the NRPN now.
+            controller::NRPN_LSB | controller::NRPN_MSB => self.rpn = RPN_NULL,
+            controller::DATA_ENTRY if self.rpn == RPN_PITCH_BEND_RANGE => {
+                self.pitch_bend_range = value as i32 * 100 + self.pitch_bend_range % 100;
+            }
+            controller::DATA_ENTRY_LSB if self.rpn == RPN_PITCH_BEND_RANGE => {
+                self.pitch_bend_range = self.pitch_bend_range / 100 * 100 + value as i32;
+            }
+            controller::RESET_ALL_CONTROLLERS => self.reset_controllers(),
+            _ => (),
+        }
+    }
+
+    pub fn program_change(&mut self, program: u8) {
+        self.program = program & 0x7F;
+    }
+
+    pub fn set_pitch_bend(&mut self, value: u16) {
+        self.pitch_bend = value & 0x3FFF;
+    }
+
+    pub fn set_channel_pressure(&mut self, value: u8) {
+        self.channel_pressure = value & 0x7F;
+    }
+
+    pub fn set_poly_pressure(&mut self, key: u8, value: u8) {
+        self.poly_pressure[(key & 0x7F) as usize] = value & 0x7F;
+    }
+
+    pub fn is_drum(&self) -> bool {
+        self.is_drum
+    }
+    pub fn set_drum(&mut self, is_drum: bool) {
+        self.is_drum = is_drum;
+    }
+
+    /// SoundFont bank: 128 for drums, otherwise the bank select MSB.
+    pub fn get_bank(&self) -> u16 {
+        match self.is_drum {
+            true => 128,
+            false => self.bank_msb as u16,
+        }
+    }
+    pub fn get_bank_lsb(&self) -> u8 {
+        self.bank_lsb
+    }
+    pub fn get_program(&self) -> u8 {
+        self.program
+    }
+    pub fn get_controller(&self, control: u8) -> u8 {
+        self.controllers[(control & 0x7F) as usize]
+    }
+    pub fn get_pitch_bend(&self) -> u16 {
+        self.pitch_bend
+    }
+    pub fn get_channel_pressure(&self) -> u8 {
+        self.channel_pressure
+    }
+    pub fn get_poly_pressure(&self, key: u8) -> u8 {
+        self.poly_pressure[(key & 0x7F) as usize]
+    }
+    pub fn get_pitch_bend_range(&self) -> i32 {
+        self.pitch_bend_range
+    }
+
+    /// Pitch bend in cents
+    pub fn get_pitch_bend_cents(&self) -> f64 {
+        (self.pitch_bend as f64 - 8192.0) / 8192.0 * self.pitch_bend_range as f64
+    }
+
+    pub fn is_sustained(&self) -> bool {
+        self.get_controller(controller::SUSTAIN) >= 64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pitch_bend_range() {
+        let mut channel = Channel::new(false);
+        assert_eq!(channel.get_pitch_bend_range(), 200);
+
+        channel.control_change(controller::RPN_MSB, 0);
+        channel.control_change(controller::RPN_LSB, 0);
+        channel.control_change(controller::DATA_ENTRY, 12);
+        channel.control_change(controller::DATA_ENTRY_LSB, 50);
+        assert_eq!(channel.get_pitch_bend_range(), 1250);
+
+        // Data entry for other parameters is ignored.
+        channel.control_change(controller::RPN_LSB, 1);
+        channel.control_change(controller::DATA_ENTRY, 2);
+        assert_eq!(channel.get_pitch_bend_range(), 1250);
+
+        channel.set_pitch_bend(0);
+        assert_eq!(channel.get_pitch_bend_cents(), -1250.0);
+    }
+
+    #[test]
+    fn test_nrpn_deselects_rpn() {
+        let mut channel = Channel::new(false);
+        channel.control_change(controller::RPN_MSB, 0);
+        channel.control_change(controller::RPN_LSB, 0);
+        channel.control_change(controller::NRPN_MSB, 1);
+        channel.control_change(controller::NRPN_LSB, 8);
+        // Meant for the NRPN, not the pitch bend range.
+        channel.control_change(controller::DATA_ENTRY, 64);
+        assert_eq!(channel.get_pitch_bend_range(), 200);
+    }
+
+    #[test]
+    fn test_reset_controllers() {
+        let mut channel = Channel::new(false);
+        channel.control_change(controller::BANK_SELECT, 8);
+        channel.control_change(controller::VOLUME, 10);
+        channel.control_change(controller::PAN, 20);
+        channel.control_change(91, 40);
+        channel.control_change(controller::MODULATION, 30);
+        channel.control_change(controller::EXPRESSION, 50);
+        channel.control_change(controller::SUSTAIN, 127);
+        channel.set_pitch_bend(0);
+        channel.control_change(controller::RESET_ALL_CONTROLLERS, 0);
+
+        // The mix survives.
+        assert_eq!(channel.get_controller(controller::VOLUME), 10);
+        assert_eq!(channel.get_controller(controller::PAN), 20);
+        assert_eq!(channel.get_controller(91), 40);
+        assert_eq!(channel.get_bank(), 8);
+
+        assert_eq!(channel.get_controller(controller::MODULATION), 0);
+        assert_eq!(channel.get_controller(controller::EXPRESSION), 127);
+        assert!(!channel.is_sustained());
+        assert_eq!(channel.get_pitch_bend(), 8192);
+    }
+}
@@ -0,0 +1,341 @@
+//! DAHDSR envelopes (SF2.04 section 8.1.2 and 9.1.7)
+//!
+//! Both envelopes go through delay, attack, hold, decay, sustain and release, advancing one
+//! output sample at a time. The attack rises linearly. The volume envelope decays and releases
+//! linearly in decibels, where the decay and release times are for a full 96 dB fall. The
+//! modulation envelope decays and releases linearly over its 0 to 1 range.
+
+use super::{
+    modulator::Generators,
+    voice::{centibels_to_gain, timecents_to_seconds},
+};
+use crate::sf2::generator::GeneratorType;
+
+/// Full scale attenuation the decay and release times refer to
+const FULL_ATTENUATION: f64 = 960.0;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum EnvelopeKind {
+    /// Output is gain. Sustain is attenuation in centibels.
+    Volume,
+    /// Output is 0 to 1. Sustain is a decrease in 0.1% units.
+    Modulation,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub(crate) enum Stage {
+    Delay,
+    Attack,
+    Hold,
+    Decay,
+    Sustain,
+    Release,
+    Finished,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Envelope {
+    kind: EnvelopeKind,
+    stage: Stage,
+    /// Stage lengths in samples
+    delay: usize,
+    attack: usize,
+    hold: usize,
+    /// Per sample gain factor for volume, per sample decrease for modulation
+    decay_step: f64,
+    release_step: f64,
+    sustain: f64,
+    value: f64,
+    /// Samples into the current stage
+    position: usize,
+}
+
+impl Envelope {
+    /// Envelope of a voice's generators for a key. Hold and decay times scale with the key
+    /// number, relative to key 60.
+    pub fn new(kind: EnvelopeKind, generators: &Generators, key: u8, sample_rate: u32) -> Self {
+        use GeneratorType::*;
+        let [delay, attack, hold, decay, sustain, release, key_to_hold, key_to_decay] =
+            match kind {
+                EnvelopeKind::Volume => [
+                    DelayVolEnv,
+                    AttackVolEnv,
+                    HoldVolEnv,
+                    DecayVolEnv,
+                    SustainVolEnv,
+                    ReleaseVolEnv,
+                    KeynumToVolEnvHold,
+                    KeynumToVolEnvDecay,
+                ],
+                EnvelopeKind::Modulation => [
+                    DelayModEnv,
+                    AttackModEnv,
+                    HoldModEnv,
+                    DecayModEnv,
+                    SustainModEnv,
+                    ReleaseModEnv,
+                    KeynumToModEnvHold,
+                    KeynumToModEnvDecay,
+                ],
+            }
+            .map(|generator_type| generators.get_i32(generator_type));
+
+        let key = key as i32;
+        let rate = sample_rate as f64;
+        let samples = |timecents: i32, max: i32| {
+            (timecents_to_seconds(timecents.clamp(-12000, max)) * rate).round() as usize
+        };
+        let hold = hold + key_to_hold.clamp(-1200, 1200) * (60 - key);
+        let decay = decay + key_to_decay.clamp(-1200, 1200) * (60 - key);
+        // At least one sample, so a step never divides by zero.
+        let decay = samples(decay, 8000).max(1) as f64;
+        let release = samples(release, 8000).max(1) as f64;
+
+        let (sustain, decay_step, release_step) = match kind {
+            EnvelopeKind::Volume => (
+                centibels_to_gain(sustain.clamp(0, 1440) as f64),
+                centibels_to_gain(FULL_ATTENUATION / decay),
+                centibels_to_gain(FULL_ATTENUATION / release),
+            ),
+            EnvelopeKind::Modulation => (
+                1.0 - sustain.clamp(0, 1000) as f64 / 1000.0,
+                1.0 / decay,
+                1.0 / release,
+            ),
+        };
+
+        let mut envelope = Self {
+            kind,
+            stage: Stage::Delay,
+            delay: samples(delay, 5000),
+            attack: samples(attack, 8000),
+            hold: samples(hold, 5000),
+            decay_step,
+            release_step,
+            sustain,
+            value: 0.0,
+            position: 0,
+        };
+        envelope.skip_empty_stages();
+        envelope
+    }
+
+    #[cfg(test)]
+    pub fn get_value(&self) -> f64 {
+        self.value
+    }
+
+    /// Value the envelope is at or rising to. Before the decay, that's the peak.
+    pub fn get_peak_value(&self) -> f64 {
+        match self.stage < Stage::Decay {
+            true => 1.0,
+            false => self.value,
+        }
+    }
+    #[cfg(test)]
+    pub fn get_stage(&self) -> Stage {
+        self.stage
+    }
+    pub fn is_finished(&self) -> bool {
+        self.stage == Stage::Finished
+    }
+
+    pub fn release(&mut self) {
+        if self.stage < Stage::Release {
+            self.enter(Stage::Release);
+        }
+    }
+
+    /// Release over a number of samples instead of the release time.
+    pub fn quick_release(&mut self, samples: usize) {
+        let samples = samples.max(1) as f64;
+        let step = match self.kind {
+            EnvelopeKind::Volume => centibels_to_gain(FULL_ATTENUATION / samples),
+            EnvelopeKind::Modulation => 1.0 / samples,
+        };
+        // Never slower than the envelope's own release
+        self.release_step = match self.kind {
+            EnvelopeKind::Volume => self.release_step.min(step),
+            EnvelopeKind::Modulation => self.release_step.max(step),
+        };
+        self.release();
+    }
+
+    /// Current value, then advance by one sample.
+    pub fn next(&mut self) -> f64 {
+        let value = self.value;
+        self.position += 1;
+        match self.stage {
+            Stage::Delay if self.position >= self.delay => self.enter(Stage::Attack),
+            Stage::Attack => {
+                self.value = self.position as f64 / self.attack as f64;
+                if self.position >= self.attack {
+                    self.enter(Stage::Hold);
+                }
+            }
+            Stage::Hold if self.position >= self.hold => self.enter(Stage::Decay),
+            Stage::Decay => {
+                self.value = self.fall(self.decay_step).max(self.sustain);
+                if self.value <= self.sustain {
+                    self.enter(Stage::Sustain);
+                } else if self.kind == EnvelopeKind::Volume && self.is_silent() {
+                    self.enter(Stage::Finished);
+                }
+            }
+            Stage::Release => {
+                self.value = self.fall(self.release_step);
+                if self.is_silent() {
+                    self.enter(Stage::Finished);
+                }
+            }
+            _ => (),
+        }
+        value
+    }
+
+    fn fall(&self, step: f64) -> f64 {
+        match self.kind {
+            EnvelopeKind::Volume => self.value * step,
+            EnvelopeKind::Modulation => (self.value - step).max(0.0),
+        }
+    }
+
+    fn is_silent(&self) -> bool {
+        match self.kind {
+            EnvelopeKind::Volume => self.value <= centibels_to_gain(FULL_ATTENUATION),
+            EnvelopeKind::Modulation => self.value <= 0.0,
+        }
+    }
+
+    fn enter(&mut self, stage: Stage) {
+        self.stage = stage;
+        self.position = 0;
+        match stage {
+            Stage::Hold => self.value = 1.0,
+            Stage::Sustain => {
+                self.value = self.sustain;
+                // A volume envelope sustaining at silence has ended.
+                if self.kind == EnvelopeKind::Volume && self.is_silent() {
+                    self.stage = Stage::Finished;
+                }
+            }
+            Stage::Release if self.is_silent() => self.stage = Stage::Finished,
+            Stage::Finished => self.value = 0.0,
+            _ => (),
+        }
+        self.skip_empty_stages();
+    }
+
+    fn skip_empty_stages(&mut self) {
+        match self.stage {
+            Stage::Delay if self.delay == 0 => self.enter(Stage::Attack),
+            Stage::Attack if self.attack == 0 => self.enter(Stage::Hold),
+            Stage::Hold if self.hold == 0 => self.enter(Stage::Decay),
+            Stage::Decay if self.value <= self.sustain => self.enter(Stage::Sustain),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sf2::region::Region;
+
+    /// Low enough that the shortest stages round to no samples
+    const RATE: u32 = 500;
+
+    fn generators(values: &[(GeneratorType, i32)]) -> Generators {
+        let mut region = Region::new(0);
+        for (generator_type, value) in values {
+            region.set(*generator_type, *value);
+        }
+        Generators::from(&region)
+    }
+
+    /// Timecents for a number of samples
+    fn samples(samples: f64) -> i32 {
+        (1200.0 * (samples / RATE as f64).log2()).round() as i32
+    }
+
+    #[test]
+    fn test_volume_stages() {
+        use GeneratorType::*;
+        let generators = generators(&[
+            (DelayVolEnv, samples(10.0)),
+            (AttackVolEnv, samples(20.0)),
+            (HoldVolEnv, samples(10.0)),
+            (DecayVolEnv, samples(100.0)),
+            (SustainVolEnv, 480),
+            (ReleaseVolEnv, samples(100.0)),
+        ]);
+        let mut envelope = Envelope::new(EnvelopeKind::Volume, &generators, 60, RATE);
+        let values: Vec<f64> = (0..100).map(|_| envelope.next()).collect();
+        assert_eq!(values[5], 0.0);
+        assert!((values[20] - 0.5).abs() < 0.01);
+        assert_eq!(values[35], 1.0);
+        // 48 dB down takes half the decay time.
+        let values: Vec<f64> = (0..60).map(|_| envelope.next()).collect();
+        assert_eq!(envelope.get_stage(), Stage::Sustain);
+        assert!((values[59] - centibels_to_gain(480.0)).abs() < 1e-9);
+
+        // 48 dB left to fall takes half the release time.
+        envelope.release();
+        for _ in 0..49 {
+            envelope.next();
+        }
+        assert!(!envelope.is_finished());
+        envelope.next();
+        envelope.next();
+        assert!(envelope.is_finished());
+    }
+
+    #[test]
+    fn test_silent_sustain() {
+        let generators = generators(&[
+            (GeneratorType::DecayVolEnv, samples(10.0)),
+            (GeneratorType::SustainVolEnv, 1440),
+        ]);
+        let mut envelope = Envelope::new(EnvelopeKind::Volume, &generators, 60, RATE);
+        for _ in 0..20 {
+            envelope.next();
+        }
+        assert!(envelope.is_finished());
+    }
+
+    #[test]
+    fn test_modulation_envelope() {
+        use GeneratorType::*;
+        let generators = generators(&[
+            (DecayModEnv, samples(100.0)),
+            (SustainModEnv, 500),
+            (ReleaseModEnv, samples(100.0)),
+        ]);
+        let mut envelope = Envelope::new(EnvelopeKind::Modulation, &generators, 60, RATE);
+        assert_eq!(envelope.next(), 1.0);
+        for _ in 0..49 {
+            envelope.next();
+        }
+        assert!((envelope.get_value() - 0.5).abs() < 0.01);
+        envelope.release();
+        for _ in 0..51 {
+            envelope.next();
+        }
+        assert!(envelope.is_finished());
+    }
+
+    #[test]
+    fn test_keynum_scaling() {
+        use GeneratorType::*;
+        // An octave up halves the hold time.
+        let generators = generators(&[(HoldVolEnv, samples(100.0)), (KeynumToVolEnvHold, 100)]);
+        let mut envelope = Envelope::new(EnvelopeKind::Volume, &generators, 72, RATE);
+        for _ in 0..49 {
+            envelope.next();
+        }
+        assert_eq!(envelope.get_stage(), Stage::Hold);
+        envelope.next();
+        envelope.next();
+        assert_eq!(envelope.get_stage(), Stage::Sustain);
+    }
+}
@@ -0,0 +1,174 @@
+//! Resonant low-pass filter (SF2.04 section 8.1.2, `initialFilterFc` and `initialFilterQ`)
+//!
+//! A two-pole state variable filter in trapezoidal form. It stays stable while its cutoff is
+//! modulated, and the cutoff follows changes through a short smoothing ramp so they don't click.
+
+use std::f64::consts::PI;
+
+use super::voice::{cents_to_ratio, CENTS_ZERO_HZ};
+
+/// Cutoff at and above which an unmodulated filter without resonance is bypassed
+const MAX_CUTOFF: f64 = 13500.0;
+const MIN_CUTOFF: f64 = 1500.0;
+
+/// Cutoff smoothing time in seconds
+const SMOOTHING_TIME: f64 = 0.002;
+
+/// Cutoff changes below this many cents keep the current coefficients.
+const COEFFICIENT_TOLERANCE: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Filter {
+    sample_rate: f64,
+    enabled: bool,
+    /// Damping, the inverse of the linear Q
+    damping: f64,
+    /// Keeps resonant filters from getting louder than unfiltered sound.
+    gain: f32,
+    smoothing: f64,
+    /// Smoothed cutoff in absolute cents, `None` until the first sample
+    cutoff: Option<f64>,
+    /// Cutoff the coefficients were calculated for
+    coefficient_cutoff: f64,
+    a1: f64,
+    a2: f64,
+    a3: f64,
+    ic1eq: f64,
+    ic2eq: f64,
+}
+
+impl Filter {
+    /// Bypassed filter, until configured.
+    pub fn new(sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f64;
+        Self {
+            sample_rate,
+            enabled: false,
+            damping: 0.0,
+            gain: 1.0,
+            smoothing: 1.0 - (-1.0 / (SMOOTHING_TIME * sample_rate)).exp(),
+            cutoff: None,
+            coefficient_cutoff: f64::INFINITY,
+            a1: 0.0,
+            a2: 0.0,
+            a3: 0.0,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        }
+    }
+
+    /// Set the cutoff in absolute cents and the resonance in centibels. `modulated` enables the
+    /// filter even when its cutoff would bypass it. Once enabled, the filter stays enabled so
+    /// its state carries on.
+    pub fn configure(&mut self, cutoff: f64, resonance: f64, modulated: bool) {
+        let resonance = resonance.clamp(0.0, 960.0);
+        self.enabled |= modulated || cutoff < MAX_CUTOFF || resonance > 0.0;
+        // Zero resonance is flat, at a Q of 1/sqrt(2).
+        let q = 10f64.powf((resonance / 10.0 - 3.01) / 20.0);
+        if 1.0 / q != self.damping {
+            self.damping = 1.0 / q;
+            self.gain = (1.0 / q.sqrt()).min(1.0) as f32;
+            self.coefficient_cutoff = f64::INFINITY;
+        }
+    }
+
+    #[cfg(test)]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Filter one sample, moving towards a cutoff in absolute cents.
+    pub fn process(&mut self, input: f32, cutoff: f64) -> f32 {
+        if !self.enabled {
+            return input;
+        }
+
+        let target = cutoff.clamp(MIN_CUTOFF, MAX_CUTOFF);
+        let cutoff = match self.cutoff {
+            Some(current) => current + (target - current) * self.smoothing,
+            None => target,
+        };
+        self.cutoff = Some(cutoff);
+        if (cutoff - self.coefficient_cutoff).abs() >= COEFFICIENT_TOLERANCE {
+            self.set_coefficients(cutoff);
+        }
+
+        let v3 = input as f64 - self.ic2eq;
+        let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+        let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+        v2 as f32 * self.gain
+    }
+
+    fn set_coefficients(&mut self, cutoff: f64) {
+        let hz = (CENTS_ZERO_HZ * cents_to_ratio(cutoff)).min(0.45 * self.sample_rate);
+        let g = (PI * hz / self.sample_rate).tan();
+        self.a1 = 1.0 / (1.0 + g * (g + self.damping));
+        self.a2 = g * self.a1;
+        self.a3 = g * self.a2;
+        self.coefficient_cutoff = cutoff;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 44100;
+
+    fn filter(cutoff: i32, resonance: i32) -> Filter {
+        let mut filter = Filter::new(RATE);
+        filter.configure(cutoff as f64, resonance as f64, false);
+        filter
+    }
+
+    /// Peak output level for a sine wave, after the filter settles.
+    fn response(filter: &mut Filter, hz: f64, cutoff: f64) -> f32 {
+        let mut peak: f32 = 0.0;
+        for i in 0..RATE as usize / 5 {
+            let input = (2.0 * PI * hz * i as f64 / RATE as f64).sin() as f32;
+            let output = filter.process(input, cutoff);
+            if i > RATE as usize / 10 {
+                peak = peak.max(output.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn test_bypass() {
+        assert!(!filter(13500, 0).is_enabled());
+        assert!(filter(13500, 100).is_enabled());
+        assert!(filter(8000, 0).is_enabled());
+    }
+
+    #[test]
+    fn test_low_pass() {
+        // 6000 cents is about 262 Hz.
+        let mut low = filter(6000, 0);
+        assert!((response(&mut low, 100.0, 6000.0) - 1.0).abs() < 0.05);
+        let mut low = filter(6000, 0);
+        assert!(response(&mut low, 4000.0, 6000.0) < 0.01);
+    }
+
+    #[test]
+    fn test_resonance() {
+        // 24 dB resonance peaks at the cutoff, reduced by the gain compensation.
+        let mut resonant = filter(6900, 240);
+        let peak = response(&mut resonant, 440.0, 6900.0);
+        assert!(peak > 2.0 && peak < 4.0, "{peak}");
+    }
+
+    #[test]
+    fn test_stable_sweep() {
+        let mut resonant = filter(13500, 960);
+        let mut peak: f32 = 0.0;
+        for i in 0..RATE as usize {
+            let input = if i % 100 < 50 { 1.0 } else { -1.0 };
+            let cutoff = 1500.0 + 12000.0 * ((i as f64 / 500.0).sin() + 1.0) / 2.0;
+            peak = peak.max(resonant.process(input, cutoff).abs());
+        }
+        assert!(peak.is_finite() && peak < 100.0);
+    }
+}
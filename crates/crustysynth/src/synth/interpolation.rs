@@ -0,0 +1,149 @@
+//! Sample interpolation
+//!
+//! Voices play samples at fractional positions. The interpolation reads the points around the
+//! position through a function, so a voice can wrap reads across its loop and the loop joins
+//! without a click.
+
+use std::{f64::consts::PI, sync::OnceLock};
+
+/// Points on each side of the position the windowed sinc reads
+const SINC_HALF_WIDTH: usize = 8;
+const SINC_TAPS: usize = 2 * SINC_HALF_WIDTH;
+/// Fractional positions in the sinc table. Positions in between blend neighbouring rows.
+const SINC_PHASES: usize = 256;
+
+/// Resampling quality, from fastest to best
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Interpolation {
+    /// The point closest to the position
+    Nearest,
+    /// A straight line between the two points around the position
+    #[default]
+    Linear,
+    /// A 4-point cubic Hermite curve
+    Hermite,
+    /// A Blackman windowed sinc over 16 points
+    Sinc,
+}
+
+impl Interpolation {
+    /// Value at a fraction between point 0 and point 1, reading points by offset.
+    pub(crate) fn interpolate(self, fraction: f32, point: impl Fn(isize) -> f32) -> f32 {
+        match self {
+            Self::Nearest => point((fraction >= 0.5) as isize),
+            Self::Linear => {
+                let x0 = point(0);
+                x0 + (point(1) - x0) * fraction
+            }
+            Self::Hermite => {
+                let [xm1, x0, x1, x2] = [point(-1), point(0), point(1), point(2)];
+                let c1 = 0.5 * (x1 - xm1);
+                let c2 = xm1 - 2.5 * x0 + 2.0 * x1 - 0.5 * x2;
+                let c3 = 0.5 * (x2 - xm1) + 1.5 * (x0 - x1);
+                ((c3 * fraction + c2) * fraction + c1) * fraction + x0
+            }
+            Self::Sinc => {
+                let table = sinc_table();
+                let phase = fraction * SINC_PHASES as f32;
+                let row = (phase as usize).min(SINC_PHASES - 1);
+                let blend = phase - row as f32;
+                let (a, b) = (&table[row], &table[row + 1]);
+                (0..SINC_TAPS)
+                    .map(|tap| {
+                        let weight = a[tap] + (b[tap] - a[tap]) * blend;
+                        weight * point(tap as isize - SINC_HALF_WIDTH as isize + 1)
+                    })
+                    .sum()
+            }
+        }
+    }
+}
+
+/// Tap weights for each phase, including the phase at a fraction of 1.
+fn sinc_table() -> &'static [[f32; SINC_TAPS]] {
+    static TABLE: OnceLock<Vec<[f32; SINC_TAPS]>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..=SINC_PHASES)
+            .map(|phase| {
+                let fraction = phase as f64 / SINC_PHASES as f64;
+                let mut weights = [0.0; SINC_TAPS];
+                for (tap, weight) in weights.iter_mut().enumerate() {
+                    let x = (tap as f64 - SINC_HALF_WIDTH as f64 + 1.0) - fraction;
+                    *weight = sinc(x) * blackman(x);
+                }
+                // Normalized, so constant signals keep their level at every phase.
+                let sum: f64 = weights.iter().sum();
+                weights.map(|weight| (weight / sum) as f32)
+            })
+            .collect()
+    })
+}
+
+fn sinc(x: f64) -> f64 {
+    match x {
+        0.0 => 1.0,
+        x => (PI * x).sin() / (PI * x),
+    }
+}
+
+fn blackman(x: f64) -> f64 {
+    let x = x / SINC_HALF_WIDTH as f64;
+    match x.abs() < 1.0 {
+        true => 0.42 + 0.5 * (PI * x).cos() + 0.08 * (2.0 * PI * x).cos(),
+        false => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [Interpolation; 4] = [
+        Interpolation::Nearest,
+        Interpolation::Linear,
+        Interpolation::Hermite,
+        Interpolation::Sinc,
+    ];
+
+    #[test]
+    fn test_exact_points() {
+        let point = |offset: isize| offset as f32 * 0.25;
+        for mode in MODES {
+            assert!(mode.interpolate(0.0, point).abs() < 1e-6, "{mode:?}");
+        }
+        assert!((Interpolation::Sinc.interpolate(1.0, point) - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_between_points() {
+        let ramp = |offset: isize| offset as f32;
+        assert_eq!(Interpolation::Nearest.interpolate(0.25, ramp), 0.0);
+        assert_eq!(Interpolation::Nearest.interpolate(0.5, ramp), 1.0);
+        assert_eq!(Interpolation::Nearest.interpolate(0.75, ramp), 1.0);
+        assert_eq!(Interpolation::Linear.interpolate(0.75, ramp), 0.75);
+        assert!((Interpolation::Hermite.interpolate(0.75, ramp) - 0.75).abs() < 1e-6);
+
+        // A sine far below Nyquist is reconstructed closely by the curves.
+        let sine = |position: f32| (position * 0.2).sin();
+        let point = |offset: isize| sine(offset as f32);
+        let expected = sine(0.4);
+        for (mode, tolerance) in [
+            (Interpolation::Linear, 0.01),
+            (Interpolation::Hermite, 0.001),
+            (Interpolation::Sinc, 0.001),
+        ] {
+            let value = mode.interpolate(0.4, point);
+            assert!((value - expected).abs() < tolerance, "{mode:?} {value}");
+        }
+    }
+
+    #[test]
+    fn test_constant() {
+        for mode in MODES {
+            for fraction in [0.0, 0.3, 0.999] {
+                let value = mode.interpolate(fraction, |_| 0.5);
+                assert!((value - 0.5).abs() < 1e-5, "{mode:?} {fraction}");
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Low frequency oscillators (SF2.04 section 8.1.2, `delayModLFO` to `freqVibLFO`)
+//!
+//! A triangle wave between -1 and 1, starting at 0 and rising after its delay.
+
+use super::voice::{cents_to_ratio, timecents_to_seconds, CENTS_ZERO_HZ};
+
+#[derive(Debug, Clone)]
+pub(crate) struct Lfo {
+    /// Delay in samples
+    delay: usize,
+    /// Phase increment per sample, where 1 is a full cycle
+    step: f64,
+    phase: f64,
+    position: usize,
+}
+
+impl Lfo {
+    /// LFO with a delay in timecents and a frequency in absolute cents.
+    pub fn new(delay: i32, frequency: i32, sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+        let hz = CENTS_ZERO_HZ * cents_to_ratio(frequency.clamp(-16000, 4500) as f64);
+        Self {
+            delay: (timecents_to_seconds(delay.clamp(-12000, 5000)) * rate).round() as usize,
+            step: hz / rate,
+            phase: 0.0,
+            position: 0,
+        }
+    }
+
+    /// Current value, then advance by one sample.
+    pub fn next(&mut self) -> f64 {
+        if self.position < self.delay {
+            self.position += 1;
+            return 0.0;
+        }
+        let value = match self.phase {
+            phase if phase < 0.25 => 4.0 * phase,
+            phase if phase < 0.75 => 2.0 - 4.0 * phase,
+            phase => 4.0 * phase - 4.0,
+        };
+        self.phase = (self.phase + self.step).fract();
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triangle() {
+        // 0 cents is 8.176 Hz, so 1200 cents at 654 Hz is 40 samples a cycle.
+        let rate = (CENTS_ZERO_HZ * 2.0 * 40.0).round() as u32;
+        let mut lfo = Lfo::new(-12000, 1200, rate);
+        // The shortest delay is a millisecond.
+        assert_eq!(lfo.next(), 0.0);
+        let values: Vec<f64> = (0..40).map(|_| lfo.next()).collect();
+        assert_eq!(values[0], 0.0);
+        assert!((values[10] - 1.0).abs() < 0.01);
+        assert!(values[20].abs() < 0.01);
+        assert!((values[30] + 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_delay() {
+        // One second
+        let mut lfo = Lfo::new(0, 0, 100);
+        for _ in 0..100 {
+            assert_eq!(lfo.next(), 0.0);
+        }
+        lfo.next();
+        assert!(lfo.next() > 0.0);
+    }
+}
@@ -0,0 +1,729 @@
+//! Wavetable synthesizer
+//!
+//! A [`Synthesizer`] plays a [`SoundFont`] from MIDI channel messages. A note starts one voice
+//! per region that covers its key and velocity, and each voice plays its sample at the note's
+//! pitch. Channel controllers and pitch bend apply to the voices already playing.
+//!
+//! A note cuts off an earlier note of the same key on its channel, and notes of an exclusive class
+//! cut off other notes of that class, like an open hi-hat closed by the pedal hi-hat. Voices beyond
+//! the polyphony limit are stolen, see [`pool`].
+//!
+//! GM, GS and XG system resets, master volume and rhythm part assignments arrive as SysEx
+//! messages and apply too.
+//!
+//! DLS and SFZ instruments play by converting them to a SoundFont first.
+
+use std::sync::Arc;
+
+use crate::{
+    midi::{
+        channels::MidiChannel,
+        keys::MidiKey,
+        sysex::{
+            roland::{GsParameter, GsPartParameter, GsRhythmMode},
+            universal::{UniversalNonRealTime, UniversalRealTime},
+            yamaha::{XgParameter, XgPartMode, XgPartParameter},
+            SysExMessage,
+        },
+    },
+    midifile::miditrack::midievent::MidiEvent,
+    sf2::{generator::GeneratorType, SoundFont},
+};
+
+pub mod channel;
+mod envelope;
+mod filter;
+pub mod interpolation;
+mod lfo;
+mod modulator;
+pub mod pool;
+mod voice;
+
+use channel::{controller, Channel};
+use interpolation::Interpolation;
+use pool::{VoicePool, DEFAULT_MAX_POLYPHONY};
+use voice::Voice;
+pub(crate) use voice::CENTS_ZERO_HZ;
+
+/// Channel 10 plays drums.
+const DRUM_CHANNEL: usize = 9;
+
+pub struct Synthesizer {
+    soundfont: Arc<SoundFont>,
+    sample_rate: u32,
+    channels: Vec<Channel>,
+    voices: VoicePool,
+    interpolation: Interpolation,
+    /// Output gain set by the Master Volume message
+    master_volume: f32,
+}
+
+impl Synthesizer {
+    pub const CHANNEL_COUNT: usize = 16;
+
+    pub fn new(soundfont: Arc<SoundFont>, sample_rate: u32) -> Self {
+        Self {
+            soundfont,
+            sample_rate: sample_rate.max(1),
+            channels: (0..Self::CHANNEL_COUNT)
+                .map(|channel| Channel::new(channel == DRUM_CHANNEL))
+                .collect(),
+            voices: VoicePool::new(DEFAULT_MAX_POLYPHONY),
+            interpolation: Interpolation::default(),
+            master_volume: 1.0,
+        }
+    }
+
+    pub fn get_soundfont(&self) -> &Arc<SoundFont> {
+        &self.soundfont
+    }
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn get_channel(&self, channel: MidiChannel) -> &Channel {
+        &self.channels[u8::from(channel) as usize]
+    }
+    pub fn get_active_voice_count(&self) -> usize {
+        self.voices.len()
+    }
+    pub fn get_max_polyphony(&self) -> usize {
+        self.voices.get_max_polyphony()
+    }
+
+    pub fn get_interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Set the resampling quality. Playing voices switch at the next render.
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// Limit the number of voices. Playing voices over the limit are stolen.
+    pub fn set_max_polyphony(&mut self, max_polyphony: usize) {
+        self.voices.set_max_polyphony(max_polyphony);
+    }
+
+    /// Apply a MIDI event. System events other than reset and the SysEx messages a GM, GS or XG
+    /// module responds to are ignored.
+    pub fn process(&mut self, event: &MidiEvent) {
+        match *event {
+            MidiEvent::NoteOn { channel, key, vel } if vel > 0 => self.note_on(channel, key, vel),
+            MidiEvent::NoteOn { channel, key, .. } | MidiEvent::NoteOff { channel, key, .. } => {
+                self.note_off(channel, key)
+            }
+            MidiEvent::ControlChange {
+                channel,
+                control,
+                value,
+            }
+            | MidiEvent::ChannelMode {
+                channel,
+                control,
+                value,
+            } => self.control_change(channel, control, value),
+            MidiEvent::ProgramChange { channel, program } => {
+                self.channels[u8::from(channel) as usize].program_change(program)
+            }
+            MidiEvent::AfterTouch {
+                channel,
+                key,
+                pressure,
+            } => {
+                let index = u8::from(channel) as usize;
+                self.channels[index].set_poly_pressure(u8::from(key), pressure);
+                self.update_voices(index);
+            }
+            MidiEvent::ChannelPressure { channel, value } => {
+                let index = u8::from(channel) as usize;
+                self.channels[index].set_channel_pressure(value);
+                self.update_voices(index);
+            }
+            MidiEvent::PitchBend { channel, value } => {
+                let index = u8::from(channel) as usize;
+                self.channels[index].set_pitch_bend(value);
+                self.update_voices(index);
+            }
+            MidiEvent::SystemReset => self.reset(),
+            MidiEvent::SysEx { .. } => {
+                if let Some(Ok(message)) = event.decode_sysex() {
+                    self.system_exclusive(&message);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn note_on(&mut self, channel: MidiChannel, key: MidiKey, velocity: u8) {
+        let index = u8::from(channel) as usize;
+        let state = &self.channels[index];
+        let Some(preset) = self.find_preset(state) else {
+            return;
+        };
+        let regions = self
+            .soundfont
+            .get_preset_regions(preset, key, velocity & 0x7F);
+        let key = u8::from(key);
+
+        let classes: Vec<i32> = regions
+            .iter()
+            .map(|region| region.get(GeneratorType::ExclusiveClass))
+            .filter(|class| *class != 0)
+            .collect();
+        for voice in self.voices.iter_mut().filter(|voice| {
+            voice.channel == index && (voice.key == key || classes.contains(&voice.exclusive_class))
+        }) {
+            voice.cut_off();
+        }
+
+        let headers = &self.soundfont.get_hydra().sample_headers;
+        for region in regions {
+            let Some(header) = headers.get(region.get_sample_index()) else {
+                continue;
+            };
+            self.voices.add(Voice::new(
+                index,
+                key,
+                velocity & 0x7F,
+                region,
+                header,
+                self.sample_rate,
+                state,
+            ));
+        }
+    }
+
+    pub fn note_off(&mut self, channel: MidiChannel, key: MidiKey) {
+        let index = u8::from(channel) as usize;
+        let sustain = self.channels[index].is_sustained();
+        let key = u8::from(key);
+        for voice in self.voices.iter_mut() {
+            if voice.channel == index && voice.key == key && !voice.is_released() {
+                voice.release(sustain);
+            }
+        }
+    }
+
+    pub fn control_change(&mut self, channel: MidiChannel, control: u8, value: u8) {
+        let index = u8::from(channel) as usize;
+        self.channels[index].control_change(control, value);
+        match control {
+            controller::ALL_SOUND_OFF => self.all_sound_off(channel),
+            controller::ALL_NOTES_OFF..=127 => self.all_notes_off(channel),
+            controller::SUSTAIN if value < 64 => self.release_sustained(index),
+            // Lifts the sustain pedal too.
+            controller::RESET_ALL_CONTROLLERS => {
+                self.release_sustained(index);
+                self.update_voices(index);
+            }
+            _ => self.update_voices(index),
+        }
+    }
+
+    /// Release all notes of a channel, as if their keys were lifted.
+    pub fn all_notes_off(&mut self, channel: MidiChannel) {
+        let index = u8::from(channel) as usize;
+        let sustain = self.channels[index].is_sustained();
+        for voice in self
+            .voices
+            .iter_mut()
+            .filter(|voice| voice.channel == index)
+        {
+            voice.release(sustain);
+        }
+    }
+
+    /// Silence a channel immediately, without release.
+    pub fn all_sound_off(&mut self, channel: MidiChannel) {
+        let index = u8::from(channel) as usize;
+        self.voices.retain(|voice| voice.channel != index);
+    }
+
+    /// Stop all voices and reset every channel and the master volume.
+    pub fn reset(&mut self) {
+        self.voices.clear();
+        self.master_volume = 1.0;
+        self.channels = (0..Self::CHANNEL_COUNT)
+            .map(|channel| Channel::new(channel == DRUM_CHANNEL))
+            .collect();
+    }
+
+    /// Render interleaved stereo frames. The buffer is overwritten.
+    pub fn render(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        let data = self.soundfont.get_sample_data();
+        for voice in self.voices.iter_mut() {
+            voice.render(data, self.interpolation, out);
+        }
+        out.iter_mut()
+            .for_each(|value| *value *= self.master_volume);
+        self.voices.retain(|voice| !voice.is_finished());
+    }
+
+    /// Preset of a channel. A missing bank falls back to bank 0, or to the standard kit for
+    /// drums.
+    fn find_preset(&self, channel: &Channel) -> Option<usize> {
+        let program = channel.get_program();
+        self.soundfont
+            .find_preset(channel.get_bank(), program)
+            .or_else(|| match channel.is_drum() {
+                true => self.soundfont.find_preset(128, 0),
+                false => self.soundfont.find_preset(0, program),
+            })
+    }
+
+    /// System resets, master volume and rhythm part assignments. Messages for any device id
+    /// apply.
+    fn system_exclusive(&mut self, message: &SysExMessage) {
+        match message {
+            SysExMessage::UniversalNonRealTime {
+                message: UniversalNonRealTime::GmSystemOn | UniversalNonRealTime::Gm2SystemOn,
+                ..
+            } => self.reset(),
+            SysExMessage::UniversalRealTime {
+                message: UniversalRealTime::MasterVolume(volume),
+                ..
+            } => self.master_volume = *volume as f32 / 0x3FFF as f32,
+            SysExMessage::RolandGs { parameters, .. } => {
+                for parameter in parameters {
+                    match parameter {
+                        GsParameter::Reset => self.reset(),
+                        GsParameter::Part {
+                            part,
+                            parameter: GsPartParameter::UseForRhythmPart(mode),
+                        } => self.set_drum(*part, *mode != GsRhythmMode::Off),
+                        _ => (),
+                    }
+                }
+            }
+            SysExMessage::YamahaXg { parameters, .. } => {
+                for parameter in parameters {
+                    match parameter {
+                        XgParameter::SystemOn => self.reset(),
+                        XgParameter::Part {
+                            part,
+                            parameter: XgPartParameter::PartMode(mode),
+                        } => self.set_drum(*part, *mode != XgPartMode::Normal),
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Parts receive the channel of the same number.
+    fn set_drum(&mut self, part: u8, is_drum: bool) {
+        if let Some(channel) = self.channels.get_mut(part as usize) {
+            channel.set_drum(is_drum);
+        }
+    }
+
+    fn release_sustained(&mut self, channel: usize) {
+        self.voices
+            .iter_mut()
+            .filter(|voice| voice.channel == channel)
+            .for_each(Voice::release_sustained);
+    }
+
+    fn update_voices(&mut self, channel: usize) {
+        let state = &self.channels[channel];
+        for voice in self
+            .voices
+            .iter_mut()
+            .filter(|voice| voice.channel == channel)
+        {
+            voice.update(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::sysex::manufacturer::ManufacturerId;
+    use crate::sf2::{
+        edit::{InstrumentRecords, PresetRecords, ZoneBuilder},
+        hydra::{Hydra, PresetHeader},
+        sample::{SampleData, SampleHeader},
+    };
+
+    const RATE: u32 = 44100;
+
+    /// Presets 0:0 and drum kit 128:0 playing a looped constant sample recorded at key 60.
+    /// Releases take 10 ms. Keys 100 and up are in exclusive class 1.
+    fn test_font() -> Arc<SoundFont> {
+        let header = SampleHeader {
+            start_loop: 10,
+            end_loop: 90,
+            sample_rate: RATE,
+            ..SampleHeader::test("Constant", 0, 100)
+        };
+        let instrument = InstrumentRecords {
+            name: "Constant".into(),
+            zones: vec![
+                ZoneBuilder::default()
+                    .set_range(GeneratorType::KeyRange, (0, 99))
+                    .set(GeneratorType::SampleModes, 1)
+                    .set(GeneratorType::ReleaseVolEnv, -7973)
+                    .set(GeneratorType::SampleId, 0)
+                    .build(),
+                ZoneBuilder::default()
+                    .set_range(GeneratorType::KeyRange, (100, 127))
+                    .set(GeneratorType::SampleModes, 1)
+                    .set(GeneratorType::ReleaseVolEnv, -7973)
+                    .set(GeneratorType::ExclusiveClass, 1)
+                    .set(GeneratorType::SampleId, 0)
+                    .build(),
+            ],
+        };
+        let preset = |bank| PresetRecords {
+            header: PresetHeader {
+                name: "Constant".into(),
+                bank,
+                ..Default::default()
+            },
+            zones: vec![ZoneBuilder::default()
+                .set(GeneratorType::Instrument, 0)
+                .build()],
+        };
+        let mut smpl = vec![16384; 100];
+        smpl.extend([0; 46]);
+        Arc::new(SoundFont::new(
+            Default::default(),
+            SampleData::new(smpl, None),
+            Hydra::from_records(&[preset(0), preset(128)], &[instrument], vec![header]),
+        ))
+    }
+
+    fn note_on(key: u8, vel: u8) -> MidiEvent {
+        MidiEvent::NoteOn {
+            channel: MidiChannel::Ch1,
+            key: MidiKey::try_from(key).unwrap(),
+            vel,
+        }
+    }
+
+    fn playing_keys(synth: &mut Synthesizer) -> Vec<(usize, u8)> {
+        let mut keys: Vec<(usize, u8)> = synth
+            .voices
+            .iter_mut()
+            .filter(|voice| !voice.is_released())
+            .map(|voice| (voice.channel, voice.key))
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    fn control_change(control: u8, value: u8) -> MidiEvent {
+        MidiEvent::ControlChange {
+            channel: MidiChannel::Ch1,
+            control,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_note_on_off() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&control_change(controller::VOLUME, 127));
+        synth.process(&note_on(60, 127));
+        assert_eq!(synth.get_active_voice_count(), 1);
+
+        let mut out = vec![0.0; 512];
+        synth.render(&mut out);
+        // Full volume and velocity, centered
+        let expected = 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        assert!((out[200] - expected).abs() < 1e-4);
+        assert!((out[201] - expected).abs() < 1e-4);
+
+        // Zero velocity is note off. The release fades out within 10 ms.
+        synth.process(&note_on(60, 0));
+        let mut out = vec![0.0; 1024];
+        synth.render(&mut out);
+        assert_eq!(synth.get_active_voice_count(), 0);
+        assert_eq!(out[1000], 0.0);
+    }
+
+    #[test]
+    fn test_sustain() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&control_change(controller::SUSTAIN, 127));
+        synth.process(&note_on(60, 100));
+        synth.process(&note_on(60, 0));
+        let mut out = vec![0.0; 2048];
+        synth.render(&mut out);
+        assert_eq!(synth.get_active_voice_count(), 1);
+
+        synth.process(&control_change(controller::SUSTAIN, 0));
+        synth.render(&mut out);
+        assert_eq!(synth.get_active_voice_count(), 0);
+
+        // Resetting the controllers lifts the pedal.
+        synth.process(&control_change(controller::SUSTAIN, 127));
+        synth.process(&note_on(60, 100));
+        synth.process(&note_on(60, 0));
+        synth.process(&control_change(controller::RESET_ALL_CONTROLLERS, 0));
+        synth.render(&mut out);
+        assert_eq!(synth.get_active_voice_count(), 0);
+    }
+
+    #[test]
+    fn test_pitch() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&note_on(72, 100));
+        synth.process(&MidiEvent::PitchBend {
+            channel: MidiChannel::Ch1,
+            value: 16383,
+        });
+        let mut out = vec![0.0; 2];
+        synth.render(&mut out);
+        // An octave up, plus almost two semitones of bend
+        let voice = synth.voices.iter_mut().next().unwrap();
+        let expected = 2.0 * 2f64.powf(200.0 * 8191.0 / 8192.0 / 1200.0);
+        assert!((voice.get_increment() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&control_change(controller::VOLUME, 127));
+        // Off the recorded pitch, so reads fall between points and across the loop.
+        synth.process(&note_on(67, 127));
+        let mut out = vec![0.0; 512];
+        synth.render(&mut out);
+        let expected = out[500];
+
+        for interpolation in [
+            Interpolation::Nearest,
+            Interpolation::Hermite,
+            Interpolation::Sinc,
+            Interpolation::Linear,
+        ] {
+            synth.set_interpolation(interpolation);
+            assert_eq!(synth.get_interpolation(), interpolation);
+            synth.render(&mut out);
+            // The constant loop plays without clicks at its ends.
+            for value in &out {
+                assert!((value - expected).abs() < 1e-4, "{interpolation:?} {value}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_pan() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&control_change(controller::PAN, 0));
+        synth.process(&note_on(60, 127));
+        let mut out = vec![0.0; 512];
+        synth.render(&mut out);
+        assert!(out[400] > 0.1);
+        assert!(out[401].abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_live_controllers() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&control_change(controller::VOLUME, 127));
+        synth.process(&note_on(60, 127));
+        let mut out = vec![0.0; 512];
+        synth.render(&mut out);
+        let full = out[510];
+
+        // Volume applies to the playing note, through the concave default modulator.
+        synth.process(&control_change(controller::VOLUME, 64));
+        synth.render(&mut out);
+        let expected = full * 10f32.powf(-119.0 / 200.0);
+        assert!(
+            (out[510] - expected).abs() < 0.01,
+            "{} {expected}",
+            out[510]
+        );
+    }
+
+    #[test]
+    fn test_missing_preset() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        // Bank 5 falls back to bank 0.
+        synth.process(&control_change(controller::BANK_SELECT, 5));
+        synth.process(&note_on(60, 100));
+        assert_eq!(synth.get_active_voice_count(), 1);
+
+        synth.process(&MidiEvent::ProgramChange {
+            channel: MidiChannel::Ch1,
+            program: 3,
+        });
+        synth.process(&note_on(60, 100));
+        assert_eq!(synth.get_active_voice_count(), 1);
+
+        synth.process(&control_change(controller::ALL_SOUND_OFF, 0));
+        assert_eq!(synth.get_active_voice_count(), 0);
+    }
+
+    #[test]
+    fn test_retrigger() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&note_on(60, 100));
+        synth.process(&note_on(60, 100));
+        assert_eq!(synth.get_active_voice_count(), 2);
+        assert_eq!(playing_keys(&mut synth), [(0, 60)]);
+
+        // The first note fades out quickly.
+        let mut out = vec![0.0; 1024];
+        synth.render(&mut out);
+        assert_eq!(synth.get_active_voice_count(), 1);
+    }
+
+    #[test]
+    fn test_exclusive_class() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&note_on(60, 100));
+        synth.process(&note_on(100, 100));
+        synth.process(&note_on(101, 100));
+        assert_eq!(playing_keys(&mut synth), [(0, 60), (0, 101)]);
+
+        // Only notes on the same channel are cut off.
+        synth.process(&MidiEvent::NoteOn {
+            channel: MidiChannel::Ch2,
+            key: MidiKey::try_from(102).unwrap(),
+            vel: 100,
+        });
+        assert_eq!(playing_keys(&mut synth), [(0, 60), (0, 101), (1, 102)]);
+    }
+
+    #[test]
+    fn test_voice_stealing() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.set_max_polyphony(3);
+        synth.process(&MidiEvent::NoteOn {
+            channel: MidiChannel::Ch10,
+            key: MidiKey::try_from(36).unwrap(),
+            vel: 100,
+        });
+        synth.process(&note_on(60, 100));
+        synth.process(&note_on(61, 100));
+        synth.process(&note_on(60, 0));
+
+        // The released note goes first, then the oldest held note, but not the drum.
+        synth.process(&note_on(62, 100));
+        assert_eq!(synth.get_active_voice_count(), 3);
+        assert_eq!(playing_keys(&mut synth), [(0, 61), (0, 62), (9, 36)]);
+        synth.process(&note_on(63, 20));
+        assert_eq!(playing_keys(&mut synth), [(0, 62), (0, 63), (9, 36)]);
+
+        // The quieter note goes before the older one.
+        let mut out = vec![0.0; 512];
+        synth.render(&mut out);
+        synth.process(&note_on(64, 100));
+        assert_eq!(playing_keys(&mut synth), [(0, 62), (0, 64), (9, 36)]);
+
+        synth.set_max_polyphony(1);
+        assert_eq!(playing_keys(&mut synth), [(9, 36)]);
+        assert_eq!(synth.get_max_polyphony(), 1);
+    }
+
+    #[test]
+    fn test_steal_before_render() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.set_max_polyphony(3);
+        for key in 40..=42 {
+            synth.process(&note_on(key, 100));
+        }
+        let mut out = vec![0.0; 512];
+        synth.render(&mut out);
+
+        // Notes that haven't played yet don't steal each other.
+        synth.process(&note_on(50, 100));
+        synth.process(&note_on(51, 100));
+        assert_eq!(playing_keys(&mut synth), [(0, 42), (0, 50), (0, 51)]);
+    }
+
+    #[test]
+    fn test_steal_oldest_released() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.set_max_polyphony(2);
+        let note = |channel, key, vel| MidiEvent::NoteOn {
+            channel,
+            key: MidiKey::try_from(key).unwrap(),
+            vel,
+        };
+        synth.process(&note(MidiChannel::Ch1, 60, 100));
+        synth.process(&note(MidiChannel::Ch16, 61, 100));
+        synth.process(&note(MidiChannel::Ch1, 60, 0));
+        synth.process(&note(MidiChannel::Ch16, 61, 0));
+
+        // The older released note goes, though it's on a higher priority channel.
+        synth.process(&note(MidiChannel::Ch2, 62, 100));
+        let mut keys: Vec<u8> = synth.voices.iter_mut().map(|voice| voice.key).collect();
+        keys.sort();
+        assert_eq!(keys, [61, 62]);
+    }
+
+    fn sysex(id: u8, data: &[u8]) -> MidiEvent {
+        MidiEvent::SysEx {
+            id: ManufacturerId::Standard(id),
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_sysex_reset() {
+        for reset in [
+            sysex(0x7E, &[0x7F, 0x09, 0x01, 0xF7]),
+            sysex(0x7E, &[0x7F, 0x09, 0x03, 0xF7]),
+            sysex(
+                0x41,
+                &[0x10, 0x42, 0x12, 0x40, 0x00, 0x7F, 0x00, 0x41, 0xF7],
+            ),
+            sysex(0x43, &[0x10, 0x4C, 0x00, 0x00, 0x7E, 0x00, 0xF7]),
+        ] {
+            let mut synth = Synthesizer::new(test_font(), RATE);
+            synth.process(&control_change(controller::VOLUME, 10));
+            synth.process(&note_on(60, 100));
+            synth.process(&reset);
+            assert_eq!(synth.get_active_voice_count(), 0, "{reset:?}");
+            let channel = synth.get_channel(MidiChannel::Ch1);
+            assert_eq!(channel.get_controller(controller::VOLUME), 100, "{reset:?}");
+        }
+    }
+
+    #[test]
+    fn test_sysex_master_volume() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        synth.process(&note_on(60, 127));
+        let mut out = vec![0.0; 512];
+        synth.render(&mut out);
+        let full = out[510];
+
+        synth.process(&sysex(0x7F, &[0x7F, 0x04, 0x01, 0x00, 0x40, 0xF7]));
+        synth.render(&mut out);
+        let expected = full * 0x2000 as f32 / 0x3FFF as f32;
+        assert!((out[510] - expected).abs() < 1e-6);
+
+        // Reset restores full volume.
+        synth.process(&sysex(0x7E, &[0x7F, 0x09, 0x01, 0xF7]));
+        synth.process(&note_on(60, 127));
+        synth.render(&mut out);
+        assert!((out[510] - full).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sysex_rhythm_part() {
+        let mut synth = Synthesizer::new(test_font(), RATE);
+        // GS: part 11 to drum map 1, part 10 off
+        synth.process(&sysex(
+            0x41,
+            &[0x10, 0x42, 0x12, 0x40, 0x1A, 0x15, 0x01, 0x10, 0xF7],
+        ));
+        synth.process(&sysex(
+            0x41,
+            &[0x10, 0x42, 0x12, 0x40, 0x10, 0x15, 0x00, 0x1B, 0xF7],
+        ));
+        assert!(synth.get_channel(MidiChannel::Ch11).is_drum());
+        assert!(!synth.get_channel(MidiChannel::Ch10).is_drum());
+
+        // XG: part 4 to drum setup 1
+        synth.process(&sysex(0x43, &[0x10, 0x4C, 0x08, 0x03, 0x07, 0x02, 0xF7]));
+        assert!(synth.get_channel(MidiChannel::Ch4).is_drum());
+    }
+}
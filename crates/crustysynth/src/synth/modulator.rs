@@ -0,0 +1,195 @@
+//! Modulator evaluation (SF2.04 section 9.5)
+//!
+//! A voice's generator values are its region's values plus the sum of its modulators. Modulators
+//! read the note and the channel state, so voices evaluate them again whenever the channel
+//! changes.
+//!
+//! Linked modulators and those with sources the spec disallows are ignored.
+
+use super::channel::{controller, Channel};
+use crate::sf2::{
+    generator::{GeneratorType, GENERATOR_COUNT},
+    modulator::{
+        GeneralController, Modulator, ModulatorCurve, ModulatorSource, ModulatorTransform,
+        INITIAL_PITCH,
+    },
+    region::Region,
+};
+
+/// Generator values with modulators applied
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Generators([f64; GENERATOR_COUNT]);
+
+impl Generators {
+    /// Evaluate a region's modulators for a note on a channel.
+    pub fn new(region: &Region, key: u8, velocity: u8, channel: &Channel) -> Self {
+        let mut generators = Self::from(region);
+        // The initial pitch destination has no generator value of its own.
+        generators.0[INITIAL_PITCH as usize] = 0.0;
+        for modulator in region.get_modulators() {
+            let destination = modulator.destination as usize;
+            if destination >= GENERATOR_COUNT {
+                continue;
+            }
+            if let Some(value) = evaluate(modulator, key, velocity, channel) {
+                generators.0[destination] += value;
+            }
+        }
+        generators
+    }
+
+    pub fn get(&self, generator_type: GeneratorType) -> f64 {
+        self.0[generator_type as usize]
+    }
+
+    /// Generator value, rounded to the integer units of the generator.
+    pub fn get_i32(&self, generator_type: GeneratorType) -> i32 {
+        self.get(generator_type).round() as i32
+    }
+
+    /// Pitch offset in cents from the pitch wheel modulator
+    pub fn get_initial_pitch(&self) -> f64 {
+        self.0[INITIAL_PITCH as usize]
+    }
+}
+
+/// Unmodulated values
+impl From<&Region> for Generators {
+    fn from(region: &Region) -> Self {
+        let mut generators = [0.0; GENERATOR_COUNT];
+        for (oper, value) in generators.iter_mut().enumerate() {
+            if let Ok(generator_type) = GeneratorType::try_from(oper as u16) {
+                *value = region.get(generator_type) as f64;
+            }
+        }
+        Self(generators)
+    }
+}
+
+/// Modulator output in the units of its destination. `None` if the modulator is ignored.
+fn evaluate(modulator: &Modulator, key: u8, velocity: u8, channel: &Channel) -> Option<f64> {
+    let source = source_value(modulator.source, key, velocity, channel)?;
+    let amount_source = source_value(modulator.amount_source, key, velocity, channel)?;
+    let value = source * amount_source * modulator.amount as f64;
+    match modulator.get_transform()? {
+        ModulatorTransform::Linear => Some(value),
+        ModulatorTransform::AbsoluteValue => Some(value.abs()),
+    }
+}
+
+/// Mapped source value. No controller is 1. `None` for sources that can't be used.
+fn source_value(source: ModulatorSource, key: u8, velocity: u8, channel: &Channel) -> Option<f64> {
+    if source.is_cc() {
+        return match source.index() {
+            controller::BANK_SELECT
+            | controller::DATA_ENTRY
+            | controller::BANK_SELECT_LSB
+            | controller::DATA_ENTRY_LSB
+            | 98..=101
+            | 120..=127 => None,
+            control => Some(source.map(channel.get_controller(control) as u16, 127)),
+        };
+    }
+
+    let (value, max) = match source.general_controller()? {
+        GeneralController::NoController => return Some(1.0),
+        GeneralController::NoteOnVelocity => (velocity as u16, 127),
+        GeneralController::NoteOnKeyNumber => (key as u16, 127),
+        GeneralController::PolyPressure => (channel.get_poly_pressure(key) as u16, 127),
+        GeneralController::ChannelPressure => (channel.get_channel_pressure() as u16, 127),
+        GeneralController::PitchWheel => (channel.get_pitch_bend(), 16383),
+        GeneralController::PitchWheelSensitivity => {
+            // Semitones of 127, keeping cents as a fraction.
+            let x = (channel.get_pitch_bend_range() as f64 / 100.0 / 127.0).min(1.0);
+            let x = match source.is_descending() {
+                true => 1.0 - x,
+                false => x,
+            };
+            return Some(source.curve().unwrap_or(ModulatorCurve::Linear).apply(x));
+        }
+        GeneralController::Link => return None,
+    };
+    Some(source.map(value, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel() -> Channel {
+        Channel::new(false)
+    }
+
+    #[test]
+    fn test_default_modulators() {
+        let region = Region::new(0);
+        let mut channel = channel();
+        channel.control_change(controller::VOLUME, 127);
+        let generators = Generators::new(&region, 60, 127, &channel);
+        assert_eq!(generators.get(GeneratorType::InitialAttenuation), 0.0);
+        assert_eq!(generators.get(GeneratorType::InitialFilterFc), 13500.0);
+        assert_eq!(generators.get(GeneratorType::Pan), 0.0);
+        assert_eq!(generators.get_initial_pitch(), 0.0);
+
+        channel.control_change(controller::MODULATION, 127);
+        channel.control_change(controller::PAN, 0);
+        channel.set_channel_pressure(127);
+        channel.set_pitch_bend(0);
+        let generators = Generators::new(&region, 60, 0, &channel);
+        assert_eq!(generators.get(GeneratorType::InitialAttenuation), 960.0);
+        assert_eq!(generators.get(GeneratorType::InitialFilterFc), 11100.0);
+        assert_eq!(generators.get(GeneratorType::VibLfoToPitch), 100.0);
+        // Voices clamp pan to -500.
+        assert_eq!(generators.get(GeneratorType::Pan), -1000.0);
+        // Two semitones of pitch bend range
+        assert!((generators.get_initial_pitch() + 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_custom_modulators() {
+        let mut region = Region::new(0);
+        // CC74 to cutoff, bipolar
+        region.set_modulator(Modulator::new(
+            0x02CA,
+            GeneratorType::InitialFilterFc as u16,
+            2400,
+            0,
+        ));
+        // Replaces the default velocity to attenuation amount.
+        region.set_modulator(Modulator::new(
+            0x0502,
+            GeneratorType::InitialAttenuation as u16,
+            480,
+            0,
+        ));
+        // Key number scaled by the modulation wheel, absolute value
+        region.set_modulator(Modulator {
+            transform: ModulatorTransform::AbsoluteValue as u16,
+            ..Modulator::new(0x0203, GeneratorType::FineTune as u16, -100, 0x0081)
+        });
+
+        let mut channel = channel();
+        channel.control_change(controller::VOLUME, 127);
+        channel.control_change(74, 0);
+        channel.control_change(controller::MODULATION, 127);
+        let generators = Generators::new(&region, 0, 0, &channel);
+        assert_eq!(
+            generators.get(GeneratorType::InitialFilterFc),
+            13500.0 - 2400.0 - 2400.0
+        );
+        assert_eq!(generators.get(GeneratorType::InitialAttenuation), 480.0);
+        assert_eq!(generators.get(GeneratorType::FineTune), 100.0);
+    }
+
+    #[test]
+    fn test_ignored_modulators() {
+        let mut region = Region::new(0);
+        // Data entry isn't a valid source, and 0x8000 links to another modulator.
+        region.set_modulator(Modulator::new(0x0086, GeneratorType::Pan as u16, 500, 0));
+        region.set_modulator(Modulator::new(0x0002, 0x8000, 500, 0));
+        let mut channel = channel();
+        channel.control_change(controller::DATA_ENTRY, 127);
+        let generators = Generators::new(&region, 60, 127, &channel);
+        assert_eq!(generators.get(GeneratorType::Pan), 0.0);
+    }
+}
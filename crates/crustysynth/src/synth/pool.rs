@@ -0,0 +1,132 @@
+//! Voice pool: polyphony limit and voice stealing
+//!
+//! When the pool is full, a new voice replaces the one that is missed least:
+//! 1. voices on drum channels only if nothing else is playing,
+//! 2. released voices before held ones, the oldest first,
+//! 3. the quietest held voice, in steps of 6 dB,
+//! 4. the voice on the lowest priority channel, where channel 10 comes first and then 1 to 16,
+//! 5. the oldest voice.
+
+use std::cmp::Reverse;
+
+use super::{voice::Voice, DRUM_CHANNEL};
+
+pub const DEFAULT_MAX_POLYPHONY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub(crate) struct VoicePool {
+    voices: Vec<Voice>,
+    max_polyphony: usize,
+    /// Voices added so far, for voice age
+    counter: u64,
+}
+
+impl VoicePool {
+    pub fn new(max_polyphony: usize) -> Self {
+        Self {
+            voices: vec![],
+            max_polyphony: max_polyphony.max(1),
+            counter: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.voices.len()
+    }
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Voice> {
+        self.voices.iter_mut()
+    }
+    pub fn retain(&mut self, f: impl FnMut(&Voice) -> bool) {
+        self.voices.retain(f)
+    }
+    pub fn clear(&mut self) {
+        self.voices.clear()
+    }
+
+    pub fn get_max_polyphony(&self) -> usize {
+        self.max_polyphony
+    }
+
+    /// Set the maximum voice count. Voices over the new limit are stolen.
+    pub fn set_max_polyphony(&mut self, max_polyphony: usize) {
+        self.max_polyphony = max_polyphony.max(1);
+        while self.voices.len() > self.max_polyphony {
+            self.steal();
+        }
+    }
+
+    /// Add a voice, stealing another if the pool is full.
+    pub fn add(&mut self, mut voice: Voice) {
+        if self.voices.len() >= self.max_polyphony {
+            self.steal();
+        }
+        voice.order = self.counter;
+        self.counter += 1;
+        self.voices.push(voice);
+    }
+
+    fn steal(&mut self) {
+        let victim = self
+            .voices
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, voice)| {
+                // Released voices go by age alone.
+                let (loudness, priority) = match voice.is_released() {
+                    true => (i32::MIN, 0),
+                    false => (
+                        loudness_step(voice.get_level()),
+                        channel_priority(voice.channel),
+                    ),
+                };
+                (
+                    voice.drum,
+                    !voice.is_released(),
+                    loudness,
+                    Reverse(priority),
+                    voice.order,
+                )
+            })
+            .map(|(index, _)| index);
+        if let Some(index) = victim {
+            self.voices.swap_remove(index);
+        }
+    }
+}
+
+/// Level in 6 dB steps, so voices about as loud compare by channel instead.
+fn loudness_step(level: f32) -> i32 {
+    match level > 0.0 {
+        true => (20.0 * level.log10() / 6.0).floor() as i32,
+        false => i32::MIN + 1,
+    }
+}
+
+/// Lower is more important. The drum channel comes first, then channels in order.
+fn channel_priority(channel: usize) -> usize {
+    match channel {
+        DRUM_CHANNEL => 0,
+        channel => channel + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loudness_step() {
+        assert_eq!(loudness_step(1.0), 0);
+        assert_eq!(loudness_step(0.9), -1);
+        assert_eq!(loudness_step(0.6), -1);
+        assert_eq!(loudness_step(0.4), -2);
+        assert_eq!(loudness_step(0.2), -3);
+        assert!(loudness_step(0.0) < loudness_step(1e-9));
+    }
+
+    #[test]
+    fn test_channel_priority() {
+        assert!(channel_priority(DRUM_CHANNEL) < channel_priority(0));
+        assert!(channel_priority(0) < channel_priority(15));
+    }
+}
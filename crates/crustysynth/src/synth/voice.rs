@@ -0,0 +1,520 @@
+//! Voices: one region of a note, playing its sample
+//!
+//! The sample plays from its start to its end, shifted by the region's address offsets, looping
+//! as its `sampleModes` says. Loops that end before they start, or have no length, play as if
+//! the sample had no loop.
+
+use std::f32::consts::FRAC_PI_2;
+
+use super::{
+    channel::Channel,
+    envelope::{Envelope, EnvelopeKind},
+    filter::Filter,
+    interpolation::Interpolation,
+    lfo::Lfo,
+    modulator::Generators,
+};
+use crate::sf2::{
+    edit::COARSE_OFFSET,
+    generator::GeneratorType,
+    region::Region,
+    sample::{SampleData, SampleHeader},
+};
+
+/// Full scale of a 24-bit sample point
+const POINT_SCALE: f32 = 8388608.0;
+
+/// Fade out time in seconds of voices cut off by an exclusive class or a retriggered note
+const QUICK_RELEASE_TIME: f64 = 0.01;
+
+/// `sampleModes` generator values
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LoopMode {
+    NoLoop,
+    Continuous,
+    /// Loop while the key is held, then play on to the end of the sample.
+    UntilRelease,
+}
+
+impl From<i32> for LoopMode {
+    fn from(v: i32) -> Self {
+        match v & 3 {
+            1 => Self::Continuous,
+            3 => Self::UntilRelease,
+            _ => Self::NoLoop,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Voice {
+    /// Channel index, 0-15
+    pub channel: usize,
+    pub key: u8,
+    /// Voice plays on a drum channel.
+    pub drum: bool,
+    /// Order voices were started in
+    pub order: u64,
+    /// Voices of a non-zero class cut off others of the same class on their channel.
+    pub exclusive_class: i32,
+    region: Region,
+    /// Key and velocity modulators see, after the `keynum` and `velocity` generators
+    modulation_key: u8,
+    modulation_velocity: u8,
+    /// Key difference to the root key, which scale tuning applies to
+    key_offset: f64,
+    pitch_correction: f64,
+    /// Sample bounds, as indices into `smpl`
+    start: usize,
+    end: usize,
+    loop_start: usize,
+    loop_end: usize,
+    loop_mode: LoopMode,
+    /// Playback rate at the recorded pitch, relative to the output rate
+    rate_ratio: f64,
+    /// Pitch in cents relative to the recorded pitch, before envelope and LFO modulation
+    pitch: f64,
+    /// Absolute position in `smpl`
+    position: f64,
+    /// Playback has wrapped around the loop, so points before the loop start come from its end.
+    looped: bool,
+    increment: f64,
+    gain_left: f32,
+    gain_right: f32,
+    /// Key is up. The envelopes are releasing.
+    released: bool,
+    /// Key is up, but the sustain pedal holds the voice.
+    sustained: bool,
+    volume_envelope: Envelope,
+    modulation_envelope: Envelope,
+    quick_release: usize,
+    filter: Filter,
+    /// Filter cutoff in absolute cents, before envelope and LFO modulation
+    filter_cutoff: f64,
+    modulation_lfo: Lfo,
+    vibrato_lfo: Lfo,
+    /// Envelope and LFO depths at full scale: cents for pitch and cutoff, centibels for volume
+    mod_env_to_pitch: f64,
+    mod_env_to_filter_fc: f64,
+    mod_lfo_to_pitch: f64,
+    mod_lfo_to_filter_fc: f64,
+    mod_lfo_to_volume: f64,
+    vib_lfo_to_pitch: f64,
+    finished: bool,
+}
+
+impl Voice {
+    pub fn new(
+        channel_index: usize,
+        key: u8,
+        velocity: u8,
+        region: Region,
+        header: &SampleHeader,
+        output_rate: u32,
+        channel: &Channel,
+    ) -> Self {
+        use GeneratorType::*;
+        let modulation_key = match region.get(Keynum) {
+            key @ 0..=127 => key as u8,
+            _ => key,
+        };
+        let modulation_velocity = match region.get(Velocity) {
+            velocity @ 0..=127 => velocity as u8,
+            _ => velocity,
+        };
+        let root_key = match region.get(OverridingRootKey) {
+            root @ 0..=127 => root,
+            _ => header.original_key.min(127) as i32,
+        };
+        let sample_rate = match header.sample_rate {
+            0 => output_rate,
+            rate => rate,
+        };
+
+        // Sample addresses with their offsets. Loops stay within the sample.
+        let address = |base: u32, fine, coarse| {
+            let offset = region.get(fine) as i64 + region.get(coarse) as i64 * COARSE_OFFSET as i64;
+            (base as i64 + offset).max(0) as usize
+        };
+        let start = address(header.start, StartAddrsOffset, StartAddrsCoarseOffset);
+        let end = address(header.end, EndAddrsOffset, EndAddrsCoarseOffset).max(start);
+        let loop_start = address(
+            header.start_loop,
+            StartloopAddrsOffset,
+            StartloopAddrsCoarseOffset,
+        )
+        .clamp(start, end);
+        let loop_end = address(
+            header.end_loop,
+            EndloopAddrsOffset,
+            EndloopAddrsCoarseOffset,
+        )
+        .clamp(start, end);
+        let loop_mode = match loop_start < loop_end {
+            true => LoopMode::from(region.get(SampleModes)),
+            false => LoopMode::NoLoop,
+        };
+
+        // Envelopes and LFOs take their modulated values at the start of the note.
+        let generators = Generators::new(&region, modulation_key, modulation_velocity, channel);
+        let lfo = |delay, frequency| {
+            Lfo::new(
+                generators.get_i32(delay),
+                generators.get_i32(frequency),
+                output_rate,
+            )
+        };
+
+        let mut voice = Self {
+            channel: channel_index,
+            key,
+            drum: channel.is_drum(),
+            order: 0,
+            exclusive_class: region.get(ExclusiveClass),
+            modulation_key,
+            modulation_velocity,
+            key_offset: (modulation_key as i32 - root_key) as f64,
+            pitch_correction: header.pitch_correction as f64,
+            start,
+            end,
+            loop_start,
+            loop_end,
+            loop_mode,
+            rate_ratio: sample_rate as f64 / output_rate as f64,
+            pitch: 0.0,
+            position: start as f64,
+            looped: false,
+            increment: 0.0,
+            gain_left: 0.0,
+            gain_right: 0.0,
+            released: false,
+            sustained: false,
+            volume_envelope: Envelope::new(
+                EnvelopeKind::Volume,
+                &generators,
+                modulation_key,
+                output_rate,
+            ),
+            modulation_envelope: Envelope::new(
+                EnvelopeKind::Modulation,
+                &generators,
+                modulation_key,
+                output_rate,
+            ),
+            quick_release: (QUICK_RELEASE_TIME * output_rate as f64) as usize,
+            filter: Filter::new(output_rate),
+            filter_cutoff: 0.0,
+            modulation_lfo: lfo(DelayModLfo, FreqModLfo),
+            vibrato_lfo: lfo(DelayVibLfo, FreqVibLfo),
+            mod_env_to_pitch: 0.0,
+            mod_env_to_filter_fc: 0.0,
+            mod_lfo_to_pitch: 0.0,
+            mod_lfo_to_filter_fc: 0.0,
+            mod_lfo_to_volume: 0.0,
+            vib_lfo_to_pitch: 0.0,
+            finished: false,
+            region,
+        };
+        voice.update(channel);
+        voice
+    }
+
+    #[cfg(test)]
+    pub fn get_increment(&self) -> f64 {
+        self.increment
+    }
+
+    /// Evaluate the modulators again for the channel state, and apply the new generator values.
+    pub fn update(&mut self, channel: &Channel) {
+        use GeneratorType::*;
+        let generators = Generators::new(
+            &self.region,
+            self.modulation_key,
+            self.modulation_velocity,
+            channel,
+        );
+
+        self.pitch = generators.get(ScaleTuning) * self.key_offset
+            + generators.get(CoarseTune) * 100.0
+            + generators.get(FineTune)
+            + self.pitch_correction
+            + generators.get_initial_pitch();
+        self.increment = self.rate_ratio * cents_to_ratio(self.pitch);
+
+        let attenuation = generators.get(InitialAttenuation).clamp(0.0, 1440.0);
+        let gain = centibels_to_gain(attenuation) as f32;
+        let pan = generators.get(Pan).clamp(-500.0, 500.0);
+        let angle = ((pan + 500.0) / 1000.0) as f32 * FRAC_PI_2;
+        self.gain_left = gain * angle.cos();
+        self.gain_right = gain * angle.sin();
+
+        self.mod_env_to_pitch = generators.get(ModEnvToPitch);
+        self.mod_env_to_filter_fc = generators.get(ModEnvToFilterFc);
+        self.mod_lfo_to_pitch = generators.get(ModLfoToPitch);
+        self.mod_lfo_to_filter_fc = generators.get(ModLfoToFilterFc);
+        self.mod_lfo_to_volume = generators.get(ModLfoToVolume);
+        self.vib_lfo_to_pitch = generators.get(VibLfoToPitch);
+
+        self.filter_cutoff = generators.get(InitialFilterFc);
+        self.filter.configure(
+            self.filter_cutoff,
+            generators.get(InitialFilterQ),
+            self.mod_env_to_filter_fc != 0.0 || self.mod_lfo_to_filter_fc != 0.0,
+        );
+    }
+
+    pub fn release(&mut self, sustain: bool) {
+        match sustain {
+            true => self.sustained = true,
+            false => self.start_release(),
+        }
+    }
+
+    /// Sustain pedal lifted: release the voice if its key is already up.
+    pub fn release_sustained(&mut self) {
+        if self.sustained {
+            self.sustained = false;
+            self.start_release();
+        }
+    }
+
+    fn start_release(&mut self) {
+        self.released = true;
+        self.volume_envelope.release();
+        self.modulation_envelope.release();
+    }
+
+    /// Fade out within a few milliseconds, even if the sustain pedal is down.
+    pub fn cut_off(&mut self) {
+        self.sustained = false;
+        self.start_release();
+        self.volume_envelope.quick_release(self.quick_release);
+    }
+
+    /// Output level, for comparing voices. Voices that haven't reached their peak yet count at
+    /// their peak, so a note that just started isn't the quietest.
+    pub fn get_level(&self) -> f32 {
+        self.gain_left.max(self.gain_right) * self.volume_envelope.get_peak_value() as f32
+    }
+
+    pub fn is_released(&self) -> bool {
+        self.released
+    }
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn is_looping(&self) -> bool {
+        match self.loop_mode {
+            LoopMode::NoLoop => false,
+            LoopMode::Continuous => true,
+            LoopMode::UntilRelease => !self.released,
+        }
+    }
+
+    /// Sample index of a point near the position. Points past the loop end wrap to the loop
+    /// start while looping, and the other way once the loop has played. Points outside the
+    /// sample repeat its first or last point.
+    fn point_index(&self, i: isize, looping: bool) -> usize {
+        let (loop_start, loop_end) = (self.loop_start as isize, self.loop_end as isize);
+        let length = loop_end - loop_start;
+        let i = match looping {
+            true if i >= loop_end => loop_start + (i - loop_start) % length,
+            true if self.looped && i < loop_start => loop_end - 1 - (loop_start - 1 - i) % length,
+            _ => i,
+        };
+        i.clamp(self.start as isize, self.end as isize - 1) as usize
+    }
+
+    /// Mix into interleaved stereo frames.
+    pub fn render(&mut self, data: &SampleData, interpolation: Interpolation, out: &mut [f32]) {
+        for frame in out.chunks_exact_mut(2) {
+            if self.finished {
+                return;
+            }
+
+            let looping = self.is_looping();
+            if looping {
+                let length = (self.loop_end - self.loop_start) as f64;
+                while self.position >= self.loop_end as f64 {
+                    self.position -= length;
+                    self.looped = true;
+                }
+            }
+            // The sample may claim more points than there are.
+            let index = self.position as usize;
+            if index >= self.end || index >= data.get_sample_count() {
+                self.finished = true;
+                return;
+            }
+
+            let fraction = (self.position - index as f64) as f32;
+            let value = interpolation.interpolate(fraction, |offset| {
+                let i = self.point_index(index as isize + offset, looping);
+                data.get_point_24(i) as f32 / POINT_SCALE
+            });
+
+            let modulation = self.modulation_envelope.next();
+            let mod_lfo = self.modulation_lfo.next();
+            let vib_lfo = self.vibrato_lfo.next();
+
+            let cutoff = self.filter_cutoff
+                + modulation * self.mod_env_to_filter_fc
+                + mod_lfo * self.mod_lfo_to_filter_fc;
+            let value = self.filter.process(value, cutoff);
+
+            let mut gain = self.volume_envelope.next();
+            if self.mod_lfo_to_volume != 0.0 {
+                // A positive amount makes the LFO peak louder.
+                gain *= centibels_to_gain(-mod_lfo * self.mod_lfo_to_volume);
+            }
+            let value = value * gain as f32;
+            frame[0] += value * self.gain_left;
+            frame[1] += value * self.gain_right;
+
+            let pitch = modulation * self.mod_env_to_pitch
+                + mod_lfo * self.mod_lfo_to_pitch
+                + vib_lfo * self.vib_lfo_to_pitch;
+            self.position += match pitch {
+                0.0 => self.increment,
+                pitch => self.rate_ratio * cents_to_ratio(self.pitch + pitch),
+            };
+            if self.volume_envelope.is_finished() {
+                self.finished = true;
+            }
+        }
+    }
+}
+
+pub(crate) fn timecents_to_seconds(timecents: i32) -> f64 {
+    2f64.powf(timecents as f64 / 1200.0)
+}
+
+/// Frequency of absolute cents 0, MIDI key 0
+pub(crate) const CENTS_ZERO_HZ: f64 = 8.175798915643707;
+
+pub(crate) fn cents_to_ratio(cents: f64) -> f64 {
+    2f64.powf(cents / 1200.0)
+}
+
+pub(crate) fn centibels_to_gain(centibels: f64) -> f64 {
+    10f64.powf(-centibels / 200.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 1000;
+
+    /// 100 points with a loop, recorded at key 60
+    fn header(start_loop: u32, end_loop: u32) -> SampleHeader {
+        SampleHeader {
+            start_loop,
+            end_loop,
+            sample_rate: RATE,
+            ..SampleHeader::test("Sample", 0, 100)
+        }
+    }
+
+    /// Key 60 of a region with a one second release
+    fn voice(header: &SampleHeader, values: &[(GeneratorType, i32)]) -> Voice {
+        let mut region = Region::new(0);
+        region.set(GeneratorType::ReleaseVolEnv, 0);
+        for (generator_type, value) in values {
+            region.set(*generator_type, *value);
+        }
+        Voice::new(0, 60, 127, region, header, RATE, &Channel::new(false))
+    }
+
+    /// Render a number of frames. Returns whether the voice is still playing.
+    fn play(voice: &mut Voice, frames: usize) -> bool {
+        let data = SampleData::new(vec![1000; 100], None);
+        voice.render(&data, Interpolation::Linear, &mut vec![0.0; frames * 2]);
+        !voice.is_finished()
+    }
+
+    #[test]
+    fn test_loop_mode() {
+        assert_eq!(LoopMode::from(0), LoopMode::NoLoop);
+        assert_eq!(LoopMode::from(1), LoopMode::Continuous);
+        assert_eq!(LoopMode::from(2), LoopMode::NoLoop);
+        assert_eq!(LoopMode::from(3), LoopMode::UntilRelease);
+    }
+
+    #[test]
+    fn test_loop_playback() {
+        use GeneratorType::SampleModes;
+        let header = header(10, 90);
+        let mut no_loop = voice(&header, &[]);
+        assert!(!play(&mut no_loop, 101));
+
+        let mut continuous = voice(&header, &[(SampleModes, 1)]);
+        assert!(play(&mut continuous, 1000));
+        continuous.release(false);
+        assert!(play(&mut continuous, 200));
+
+        // Plays on to the end of the sample once released.
+        let mut until_release = voice(&header, &[(SampleModes, 3)]);
+        assert!(play(&mut until_release, 1000));
+        until_release.release(false);
+        assert!(!play(&mut until_release, 100));
+    }
+
+    #[test]
+    fn test_address_offsets() {
+        use GeneratorType::*;
+        let long = SampleHeader {
+            end: 100000,
+            end_loop: 90000,
+            ..header(10, 0)
+        };
+        let offset = voice(
+            &long,
+            &[
+                (StartAddrsOffset, 5),
+                (EndAddrsOffset, -10),
+                (StartloopAddrsOffset, 2),
+                (EndloopAddrsOffset, -1000),
+                (EndloopAddrsCoarseOffset, -1),
+            ],
+        );
+        assert_eq!(offset.start, 5);
+        assert_eq!(offset.position, 5.0);
+        assert_eq!(offset.end, 99990);
+        assert_eq!(offset.loop_start, 12);
+        assert_eq!(offset.loop_end, 90000 - 32768 - 1000);
+
+        // Offsets keep within the sample.
+        let clamped = voice(
+            &long,
+            &[(StartAddrsOffset, -20), (EndloopAddrsCoarseOffset, 10)],
+        );
+        assert_eq!(clamped.start, 0);
+        assert_eq!(clamped.loop_end, 100000);
+    }
+
+    #[test]
+    fn test_malformed_loops() {
+        use GeneratorType::SampleModes;
+        for (start_loop, end_loop) in [(90, 10), (50, 50), (200, 300)] {
+            let mut malformed = voice(&header(start_loop, end_loop), &[(SampleModes, 1)]);
+            assert_eq!(malformed.loop_mode, LoopMode::NoLoop);
+            assert!(!play(&mut malformed, 101));
+        }
+
+        let reversed = SampleHeader {
+            start: 100,
+            end: 0,
+            ..header(10, 90)
+        };
+        assert!(!play(&mut voice(&reversed, &[(SampleModes, 1)]), 1));
+
+        // Claims more points than the sample data has
+        let oversized = SampleHeader {
+            end: 1000,
+            ..header(10, 900)
+        };
+        assert!(!play(&mut voice(&oversized, &[(SampleModes, 1)]), 101));
+    }
+}
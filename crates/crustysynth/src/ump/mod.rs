@@ -0,0 +1,663 @@
+//! MIDI 2.0 Universal MIDI Packets
+//!
+//! UMP wraps MIDI messages in 32-bit words. Each packet belongs to one of 16 groups, which map
+//! onto MIDI 1.0 ports: group 0 is port 1, and so on. Utility and stream messages are groupless.
+//!
+//! Channel voice messages can be sent either as MIDI 1.0 messages (type 2), or as MIDI 2.0
+//! messages (type 4) with higher resolution. Values are converted between the two with the
+//! min-center-max scaling described in the UMP spec.
+
+use std::{fmt::Debug, io};
+
+use crate::{
+    midi::{
+        channels::MidiChannel, keys::MidiKey, stream::MidiStreamParser,
+        sysex::manufacturer::ManufacturerId,
+    },
+    midifile::miditrack::midievent::MidiEvent,
+};
+
+const TYPE_UTILITY: u8 = 0x0;
+const TYPE_SYSTEM: u8 = 0x1;
+const TYPE_MIDI1_CHANNEL_VOICE: u8 = 0x2;
+const TYPE_DATA64: u8 = 0x3;
+const TYPE_MIDI2_CHANNEL_VOICE: u8 = 0x4;
+const TYPE_FLEX_DATA: u8 = 0xD;
+const TYPE_STREAM: u8 = 0xF;
+
+const SYSEX7_COMPLETE: u8 = 0x0;
+const SYSEX7_START: u8 = 0x1;
+const SYSEX7_CONTINUE: u8 = 0x2;
+const SYSEX7_END: u8 = 0x3;
+
+/// Which message types to use for channel voice messages.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UmpProtocol {
+    /// MIDI 1.0 channel voice messages (type 2)
+    Midi1,
+    /// MIDI 2.0 channel voice messages (type 4)
+    Midi2,
+}
+
+/// One Universal MIDI Packet of 1 to 4 words.
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct UmpPacket {
+    words: [u32; 4],
+}
+
+impl Debug for UmpPacket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "UmpPacket(")?;
+        for (i, word) in self.words().iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{word:08X}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl UmpPacket {
+    /// Create a packet from words. Words past the packet length are ignored. Returns `None` if
+    /// there are too few words for the message type.
+    pub fn from_words(words: &[u32]) -> Option<Self> {
+        let first = *words.first()?;
+        let len = packet_len((first >> 28) as u8);
+        if words.len() < len {
+            return None;
+        }
+        let mut packet = Self { words: [0; 4] };
+        packet.words[..len].copy_from_slice(&words[..len]);
+        Some(packet)
+    }
+
+    /// Read a packet of big endian words.
+    pub fn read<R>(reader: &mut R) -> Result<Self, io::Error>
+    where
+        R: io::Read,
+    {
+        let mut buf = [0_u8; 4];
+        reader.read_exact(&mut buf)?;
+        let mut words = [u32::from_be_bytes(buf), 0, 0, 0];
+        for word in words.iter_mut().take(packet_len(buf[0] >> 4)).skip(1) {
+            reader.read_exact(&mut buf)?;
+            *word = u32::from_be_bytes(buf);
+        }
+        Ok(Self { words })
+    }
+
+    /// Write the packet as big endian words.
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), io::Error>
+    where
+        W: io::Write,
+    {
+        for word in self.words() {
+            writer.write_all(&word.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn words(&self) -> &[u32] {
+        &self.words[..self.word_count()]
+    }
+
+    /// Packet length in words.
+    pub fn word_count(&self) -> usize {
+        packet_len(self.message_type())
+    }
+
+    pub fn message_type(&self) -> u8 {
+        (self.words[0] >> 28) as u8
+    }
+
+    /// Group. Meaningless for utility and stream messages.
+    pub fn group(&self) -> u8 {
+        ((self.words[0] >> 24) & 0x0F) as u8
+    }
+
+    pub fn noop() -> Self {
+        Self::utility(0x0, 0)
+    }
+
+    /// Jitter reduction clock: sender time in units of 1/31250 seconds.
+    pub fn jr_clock(time: u16) -> Self {
+        Self::utility(0x1, time as u32)
+    }
+
+    /// Jitter reduction timestamp for the following message, in units of 1/31250 seconds.
+    pub fn jr_timestamp(time: u16) -> Self {
+        Self::utility(0x2, time as u32)
+    }
+
+    /// Delta clockstamp ticks per quarter note.
+    pub fn delta_clockstamp_tpq(ticks_per_quarter: u16) -> Self {
+        Self::utility(0x3, ticks_per_quarter as u32)
+    }
+
+    /// Delta clockstamp: ticks since the previous one. Limited to 20 bits.
+    pub fn delta_clockstamp(ticks: u32) -> Self {
+        Self::utility(0x4, ticks & 0xF_FFFF)
+    }
+
+    /// Flex data: set tempo, in units of 10 nanoseconds per quarter note.
+    pub fn set_tempo(group: u8, ten_ns_per_quarter: u32) -> Self {
+        Self::flex_data(group, 0x00, [ten_ns_per_quarter, 0, 0])
+    }
+
+    /// Flex data: set time signature. The denominator is a power of 2, as in SMF.
+    pub fn time_signature(
+        group: u8,
+        numerator: u8,
+        denominator: u8,
+        thirty_seconds_per_quarter: u8,
+    ) -> Self {
+        let word1 = (numerator as u32) << 24
+            | (denominator as u32) << 16
+            | (thirty_seconds_per_quarter as u32) << 8;
+        Self::flex_data(group, 0x01, [word1, 0, 0])
+    }
+
+    pub fn start_of_clip() -> Self {
+        Self::stream(0x20)
+    }
+
+    pub fn end_of_clip() -> Self {
+        Self::stream(0x21)
+    }
+
+    fn utility(status: u32, data: u32) -> Self {
+        Self {
+            words: [status << 20 | (data & 0xF_FFFF), 0, 0, 0],
+        }
+    }
+
+    /// Complete flex data message addressed to a whole group, status bank 0.
+    fn flex_data(group: u8, status: u32, data: [u32; 3]) -> Self {
+        let word0 =
+            (TYPE_FLEX_DATA as u32) << 28 | ((group & 0x0F) as u32) << 24 | 0x1 << 20 | status;
+        Self {
+            words: [word0, data[0], data[1], data[2]],
+        }
+    }
+
+    fn stream(status: u32) -> Self {
+        Self {
+            words: [(TYPE_STREAM as u32) << 28 | status << 16, 0, 0, 0],
+        }
+    }
+}
+
+/// Packet length in words, by message type.
+fn packet_len(message_type: u8) -> usize {
+    match message_type & 0x0F {
+        0x0..=0x2 | 0x6 | 0x7 => 1,
+        0x3 | 0x4 | 0x8..=0xA => 2,
+        0xB | 0xC => 3,
+        _ => 4,
+    }
+}
+
+/// A decoded packet.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum UmpMessage {
+    Noop,
+    JrClock(u16),
+    JrTimestamp(u16),
+    DeltaClockstampTpq(u16),
+    DeltaClockstamp(u32),
+    StartOfClip,
+    EndOfClip,
+    SetTempo {
+        group: u8,
+        ten_ns_per_quarter: u32,
+    },
+    TimeSignature {
+        group: u8,
+        numerator: u8,
+        denominator: u8,
+        thirty_seconds_per_quarter: u8,
+    },
+    /// A MIDI message on a group. MIDI 2.0 messages are scaled down to MIDI 1.0 resolution.
+    Midi {
+        group: u8,
+        event: MidiEvent,
+    },
+    /// A valid packet without a MIDI 1.0 equivalent.
+    Unsupported(UmpPacket),
+}
+
+/// Convert an event to packets. Most events fit in one packet, long SysEx messages are split.
+/// Meta events, End of Exclusive and undefined statuses have no UMP equivalent, and produce no
+/// packets.
+///
+/// In MIDI 2.0 protocol, Note On with velocity 0 becomes Note Off, as MIDI 2.0 allows Note On
+/// with zero velocity.
+pub fn encode(event: &MidiEvent, group: u8, protocol: UmpProtocol) -> Vec<UmpPacket> {
+    let group = (group & 0x0F) as u32;
+
+    match event {
+        MidiEvent::SysEx { id, data } => {
+            let mut payload = id.to_bytes();
+            payload.extend(data);
+            encode_sysex7(group, &payload)
+        }
+        MidiEvent::Meta { .. } | MidiEvent::EndOfExclusive | MidiEvent::Undefined { .. } => {
+            vec![]
+        }
+        _ if event.status_byte() >= 0xF0 => {
+            let bytes = event.to_bytes();
+            let word = (TYPE_SYSTEM as u32) << 28 | group << 24 | pack_bytes(&bytes) >> 8;
+            vec![UmpPacket {
+                words: [word, 0, 0, 0],
+            }]
+        }
+        _ => match protocol {
+            UmpProtocol::Midi1 => {
+                let bytes = event.to_bytes();
+                let word =
+                    (TYPE_MIDI1_CHANNEL_VOICE as u32) << 28 | group << 24 | pack_bytes(&bytes) >> 8;
+                vec![UmpPacket {
+                    words: [word, 0, 0, 0],
+                }]
+            }
+            UmpProtocol::Midi2 => vec![encode_midi2(event, group)],
+        },
+    }
+}
+
+fn encode_midi2(event: &MidiEvent, group: u32) -> UmpPacket {
+    let header = |status: u8, channel: &MidiChannel, index1: u8, index2: u8| {
+        (TYPE_MIDI2_CHANNEL_VOICE as u32) << 28
+            | group << 24
+            | (status as u32) << 20
+            | (u8::from(*channel) as u32) << 16
+            | (index1 as u32) << 8
+            | index2 as u32
+    };
+
+    let words = match event {
+        MidiEvent::NoteOn { channel, key, vel } if *vel == 0 => {
+            [header(0x8, channel, u8::from(*key), 0), 0]
+        }
+        MidiEvent::NoteOn { channel, key, vel } => [
+            header(0x9, channel, u8::from(*key), 0),
+            scale_up(*vel as u32, 7, 16) << 16,
+        ],
+        MidiEvent::NoteOff { channel, key, vel } => [
+            header(0x8, channel, u8::from(*key), 0),
+            scale_up(*vel as u32, 7, 16) << 16,
+        ],
+        MidiEvent::AfterTouch {
+            channel,
+            key,
+            pressure,
+        } => [
+            header(0xA, channel, u8::from(*key), 0),
+            scale_up(*pressure as u32, 7, 32),
+        ],
+        MidiEvent::ControlChange {
+            channel,
+            control,
+            value,
+        }
+        | MidiEvent::ChannelMode {
+            channel,
+            control,
+            value,
+        } => [
+            header(0xB, channel, *control, 0),
+            scale_up(*value as u32, 7, 32),
+        ],
+        MidiEvent::ProgramChange { channel, program } => {
+            [header(0xC, channel, 0, 0), (*program as u32) << 24]
+        }
+        MidiEvent::ChannelPressure { channel, value } => {
+            [header(0xD, channel, 0, 0), scale_up(*value as u32, 7, 32)]
+        }
+        MidiEvent::PitchBend { channel, value } => {
+            [header(0xE, channel, 0, 0), scale_up(*value as u32, 14, 32)]
+        }
+        _ => unreachable!("Not a channel voice message"),
+    };
+
+    UmpPacket {
+        words: [words[0], words[1], 0, 0],
+    }
+}
+
+fn encode_sysex7(group: u32, payload: &[u8]) -> Vec<UmpPacket> {
+    let chunks: Vec<&[u8]> = if payload.is_empty() {
+        vec![&[]]
+    } else {
+        payload.chunks(6).collect()
+    };
+    let last = chunks.len() - 1;
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let status = match (i, last) {
+                (0, 0) => SYSEX7_COMPLETE,
+                (0, _) => SYSEX7_START,
+                (i, last) if i == last => SYSEX7_END,
+                _ => SYSEX7_CONTINUE,
+            };
+            let mut bytes = [0_u8; 6];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            let word0 = (TYPE_DATA64 as u32) << 28
+                | group << 24
+                | (status as u32) << 20
+                | (chunk.len() as u32) << 16
+                | (bytes[0] as u32) << 8
+                | bytes[1] as u32;
+            let word1 = u32::from_be_bytes(bytes[2..6].try_into().unwrap());
+            UmpPacket {
+                words: [word0, word1, 0, 0],
+            }
+        })
+        .collect()
+}
+
+/// Pack up to 4 bytes into a word, most significant first.
+fn pack_bytes(bytes: &[u8]) -> u32 {
+    let mut word = [0_u8; 4];
+    word[..bytes.len()].copy_from_slice(bytes);
+    u32::from_be_bytes(word)
+}
+
+/// Decodes packets back to messages. SysEx split across packets is reassembled per group.
+#[derive(Debug, Default)]
+pub struct UmpDecoder {
+    sysex: [Option<Vec<u8>>; 16],
+}
+
+impl UmpDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode a packet. Returns `None` for a partial SysEx message, or a malformed packet.
+    pub fn decode(&mut self, packet: &UmpPacket) -> Option<UmpMessage> {
+        let word0 = packet.words[0];
+        let group = packet.group();
+
+        match packet.message_type() {
+            TYPE_UTILITY => {
+                let data = word0 & 0xF_FFFF;
+                match (word0 >> 20) & 0x0F {
+                    0x0 => Some(UmpMessage::Noop),
+                    0x1 => Some(UmpMessage::JrClock(data as u16)),
+                    0x2 => Some(UmpMessage::JrTimestamp(data as u16)),
+                    0x3 => Some(UmpMessage::DeltaClockstampTpq(data as u16)),
+                    0x4 => Some(UmpMessage::DeltaClockstamp(data)),
+                    _ => Some(UmpMessage::Unsupported(*packet)),
+                }
+            }
+            TYPE_STREAM => match (word0 >> 16) & 0x3FF {
+                0x20 => Some(UmpMessage::StartOfClip),
+                0x21 => Some(UmpMessage::EndOfClip),
+                _ => Some(UmpMessage::Unsupported(*packet)),
+            },
+            TYPE_SYSTEM | TYPE_MIDI1_CHANNEL_VOICE => {
+                let bytes = (word0 << 8).to_be_bytes();
+                let mut parser = MidiStreamParser::new();
+                let event = parser.feed(&bytes[..3]).next()?;
+                Some(UmpMessage::Midi { group, event })
+            }
+            TYPE_DATA64 => self.decode_sysex7(packet),
+            TYPE_FLEX_DATA => {
+                let word1 = packet.words[1];
+                match word0 & 0xFFFF {
+                    0x0000 => Some(UmpMessage::SetTempo {
+                        group,
+                        ten_ns_per_quarter: word1,
+                    }),
+                    0x0001 => Some(UmpMessage::TimeSignature {
+                        group,
+                        numerator: (word1 >> 24) as u8,
+                        denominator: (word1 >> 16) as u8,
+                        thirty_seconds_per_quarter: (word1 >> 8) as u8,
+                    }),
+                    _ => Some(UmpMessage::Unsupported(*packet)),
+                }
+            }
+            TYPE_MIDI2_CHANNEL_VOICE => match decode_midi2(packet) {
+                Some(event) => Some(UmpMessage::Midi { group, event }),
+                None => Some(UmpMessage::Unsupported(*packet)),
+            },
+            _ => Some(UmpMessage::Unsupported(*packet)),
+        }
+    }
+
+    fn decode_sysex7(&mut self, packet: &UmpPacket) -> Option<UmpMessage> {
+        let group = packet.group();
+        let status = ((packet.words[0] >> 20) & 0x0F) as u8;
+        let count = (((packet.words[0] >> 16) & 0x0F) as usize).min(6);
+        let mut bytes = [0_u8; 6];
+        bytes[..2].copy_from_slice(&(packet.words[0] as u16).to_be_bytes());
+        bytes[2..].copy_from_slice(&packet.words[1].to_be_bytes());
+        let bytes = &bytes[..count];
+
+        let buffer = &mut self.sysex[group as usize];
+        let payload = match status {
+            SYSEX7_COMPLETE => bytes.to_vec(),
+            SYSEX7_START => {
+                *buffer = Some(bytes.to_vec());
+                return None;
+            }
+            SYSEX7_CONTINUE => {
+                buffer.as_mut()?.extend(bytes);
+                return None;
+            }
+            SYSEX7_END => {
+                let mut payload = buffer.take()?;
+                payload.extend(bytes);
+                payload
+            }
+            _ => return Some(UmpMessage::Unsupported(*packet)),
+        };
+
+        let (id, data) = ManufacturerId::parse(&payload)?;
+        Some(UmpMessage::Midi {
+            group,
+            event: MidiEvent::SysEx {
+                id,
+                data: data.to_vec(),
+            },
+        })
+    }
+}
+
+/// Convert a MIDI 2.0 channel voice message to its MIDI 1.0 equivalent.
+fn decode_midi2(packet: &UmpPacket) -> Option<MidiEvent> {
+    let [word0, word1, ..] = packet.words;
+    let channel = MidiChannel::from((word0 >> 16) as u8);
+    let index1 = ((word0 >> 8) & 0x7F) as u8;
+
+    let event = match (word0 >> 20) & 0x0F {
+        0x8 => MidiEvent::NoteOff {
+            channel,
+            key: MidiKey::try_from(index1).ok()?,
+            vel: scale_down(word1 >> 16, 16, 7) as u8,
+        },
+        0x9 => MidiEvent::NoteOn {
+            channel,
+            key: MidiKey::try_from(index1).ok()?,
+            // Zero velocity would turn this into a note off in MIDI 1.0.
+            vel: (scale_down(word1 >> 16, 16, 7) as u8).max(1),
+        },
+        0xA => MidiEvent::AfterTouch {
+            channel,
+            key: MidiKey::try_from(index1).ok()?,
+            pressure: scale_down(word1, 32, 7) as u8,
+        },
+        0xB => match index1 {
+            122..=127 => MidiEvent::ChannelMode {
+                channel,
+                control: index1,
+                value: scale_down(word1, 32, 7) as u8,
+            },
+            _ => MidiEvent::ControlChange {
+                channel,
+                control: index1,
+                value: scale_down(word1, 32, 7) as u8,
+            },
+        },
+        0xC => MidiEvent::ProgramChange {
+            channel,
+            program: ((word1 >> 24) & 0x7F) as u8,
+        },
+        0xD => MidiEvent::ChannelPressure {
+            channel,
+            value: scale_down(word1, 32, 7) as u8,
+        },
+        0xE => MidiEvent::PitchBend {
+            channel,
+            value: scale_down(word1, 32, 14) as u16,
+        },
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Min-center-max upscaling. Minimum, center and maximum values map exactly to their
+/// counterparts in the higher resolution.
+pub fn scale_up(value: u32, src_bits: u32, dst_bits: u32) -> u32 {
+    let scale_bits = dst_bits - src_bits;
+    let center = 1 << (src_bits - 1);
+    if value <= center {
+        return value << scale_bits;
+    }
+
+    // Fill the lower bits by repeating the bits below the top bit.
+    let repeat_bits = src_bits - 1;
+    let repeat_mask = (1 << repeat_bits) - 1;
+    let mut repeat_value = value & repeat_mask;
+    if scale_bits > repeat_bits {
+        repeat_value <<= scale_bits - repeat_bits;
+    } else {
+        repeat_value >>= repeat_bits - scale_bits;
+    }
+
+    let mut result = value << scale_bits;
+    while repeat_value != 0 {
+        result |= repeat_value;
+        repeat_value >>= repeat_bits;
+    }
+    result
+}
+
+/// Downscaling simply drops the lower bits.
+pub fn scale_down(value: u32, src_bits: u32, dst_bits: u32) -> u32 {
+    value >> (src_bits - dst_bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_up() {
+        assert_eq!(scale_up(0, 7, 16), 0);
+        assert_eq!(scale_up(0x40, 7, 16), 0x8000);
+        assert_eq!(scale_up(0x7F, 7, 16), 0xFFFF);
+        assert_eq!(scale_up(0x7F, 7, 32), 0xFFFF_FFFF);
+        assert_eq!(scale_up(0x2000, 14, 32), 0x8000_0000);
+        assert_eq!(scale_up(0x3FFF, 14, 32), 0xFFFF_FFFF);
+        for value in 0..128 {
+            assert_eq!(scale_down(scale_up(value, 7, 32), 32, 7), value);
+        }
+    }
+
+    #[test]
+    fn test_midi1_packets() {
+        let event = MidiEvent::NoteOn {
+            channel: MidiChannel::Ch2,
+            key: MidiKey::C5,
+            vel: 0x40,
+        };
+        let packets = encode(&event, 3, UmpProtocol::Midi1);
+        assert_eq!(packets[0].words(), &[0x2391_3C40]);
+
+        let message = UmpDecoder::new().decode(&packets[0]).unwrap();
+        assert_eq!(message, UmpMessage::Midi { group: 3, event });
+
+        let packets = encode(&MidiEvent::TimingClock, 0, UmpProtocol::Midi1);
+        assert_eq!(packets[0].words(), &[0x10F8_0000]);
+    }
+
+    #[test]
+    fn test_midi2_packets() {
+        let event = MidiEvent::NoteOn {
+            channel: MidiChannel::Ch1,
+            key: MidiKey::C5,
+            vel: 0x7F,
+        };
+        let packets = encode(&event, 0, UmpProtocol::Midi2);
+        assert_eq!(packets[0].words(), &[0x4090_3C00, 0xFFFF_0000]);
+        let message = UmpDecoder::new().decode(&packets[0]).unwrap();
+        assert_eq!(message, UmpMessage::Midi { group: 0, event });
+
+        let event = MidiEvent::PitchBend {
+            channel: MidiChannel::Ch16,
+            value: 0x2000,
+        };
+        let packets = encode(&event, 15, UmpProtocol::Midi2);
+        assert_eq!(packets[0].words(), &[0x4FEF_0000, 0x8000_0000]);
+        let message = UmpDecoder::new().decode(&packets[0]).unwrap();
+        assert_eq!(message, UmpMessage::Midi { group: 15, event });
+    }
+
+    #[test]
+    fn test_sysex7() {
+        let event = MidiEvent::SysEx {
+            id: ManufacturerId::UNIVERSAL_NON_REALTIME,
+            data: vec![0x7F, 0x09, 0x01, 0x10, 0x20, 0x30, 0x40],
+        };
+        let packets = encode(&event, 1, UmpProtocol::Midi2);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].words(), &[0x3116_7E7F, 0x0901_1020]);
+        assert_eq!(packets[1].words(), &[0x3132_3040, 0x0000_0000]);
+
+        let mut decoder = UmpDecoder::new();
+        assert_eq!(decoder.decode(&packets[0]), None);
+        assert_eq!(
+            decoder.decode(&packets[1]),
+            Some(UmpMessage::Midi { group: 1, event })
+        );
+    }
+
+    #[test]
+    fn test_read_write() {
+        let packets = [
+            UmpPacket::delta_clockstamp_tpq(960),
+            UmpPacket::start_of_clip(),
+            UmpPacket::jr_timestamp(1234),
+        ];
+        let mut bytes = vec![];
+        for packet in &packets {
+            packet.write(&mut bytes).unwrap();
+        }
+        assert_eq!(bytes.len(), 4 + 16 + 4);
+
+        let mut slice = bytes.as_slice();
+        let mut decoder = UmpDecoder::new();
+        let messages: Vec<_> = (0..3)
+            .map(|_| {
+                decoder
+                    .decode(&UmpPacket::read(&mut slice).unwrap())
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                UmpMessage::DeltaClockstampTpq(960),
+                UmpMessage::StartOfClip,
+                UmpMessage::JrTimestamp(1234)
+            ]
+        );
+    }
+}